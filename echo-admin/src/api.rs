@@ -0,0 +1,422 @@
+use {
+    chrono::{DateTime ,  Utc} , 
+    echo_core::session::{
+        Error as SessError ,  ManageMessage ,  ManagerHandle ,  SessionId ,  SessionInfo ,  SessionProps , 
+        StateReason , 
+    } , 
+    echo_types::Protocol , 
+    serde::{Deserialize ,  Serialize} , 
+    std::{collections::HashMap ,  convert::Infallible ,  time::Duration} , 
+    tokio::sync::oneshot , 
+    warp::{
+        http::StatusCode , 
+        hyper::body::Bytes , 
+        reject::{self ,  Reject} , 
+        Filter ,  Rejection ,  Reply , 
+    } , 
+};
+
+const KICK_CODE: u16 = 30005;
+const KICK_MESSAGE: &str = "terminated by admin";
+// a day ,  sessions are extended by shorter steps
+const MAX_EXTEND_SECONDS: u64 = 86_400;
+
+#[derive(Debug)]
+struct Unauthorized;
+impl Reject for Unauthorized {}
+
+// a built-in not_found would lose to the method mismatch of the other routes
+#[derive(Debug)]
+struct NotFound;
+impl Reject for NotFound {}
+
+#[derive(Debug)]
+struct BadRequest;
+impl Reject for BadRequest {}
+
+#[derive(Debug)]
+struct Unavailable;
+impl Reject for Unavailable {}
+
+// the session has no maximum duration to extend
+#[derive(Debug)]
+struct Unlimited;
+impl Reject for Unlimited {}
+
+#[derive(Serialize)]
+struct SessionResponse {
+    id: SessionId , 
+    name: String , 
+    protocol: Protocol , 
+    ingest_id: SessionId , 
+    created_at: DateTime<Utc> , 
+    expires_at: Option<DateTime<Utc>> , 
+    props: Option<SessionProps> , 
+}
+
+impl From<SessionInfo> for SessionResponse {
+    fn from(info: SessionInfo) -> Self {
+        Self {
+            id: info.id , 
+            name: info.name , 
+            protocol: info.protocol , 
+            ingest_id: info.ingest_id , 
+            created_at: info.created_at.into() , 
+            expires_at: info.expires_at.map(DateTime::from) , 
+            props: info.props , 
+        }
+    }
+}
+
+// every field is optional ,  an empty body is fine too
+#[derive(Default ,  Deserialize)]
+struct KickRequest {
+    code: Option<u16> , 
+    message: Option<String> , 
+}
+
+#[derive(Deserialize)]
+struct ExtendRequest {
+    seconds: u64 , 
+}
+
+#[derive(Serialize)]
+struct ExtendResponse {
+    // seconds left
+    remaining: u64 , 
+}
+
+// a null value removes the prop
+type PropsPatch = HashMap<String ,  Option<String>>;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: &'static str , 
+}
+
+pub(crate) fn routes(
+    session_manager: ManagerHandle , 
+    token: String , 
+) -> impl Filter<Extract = impl Reply ,  Error = Infallible> + Clone {
+    let manager = warp::any().map(move || session_manager.clone());
+
+    let list_sessions = warp::path!("admin" / "1" / "sessions")
+        .and(warp::get())
+        .and(manager.clone())
+        .and_then(list_sessions);
+    let get_session = warp::path!("admin" / "1" / "sessions" / SessionId)
+        .and(warp::get())
+        .and(manager.clone())
+        .and_then(get_session);
+    let kick_session = warp::path!("admin" / "1" / "sessions" / SessionId)
+        .and(warp::delete())
+        .and(warp::body::bytes())
+        .and(manager.clone())
+        .and_then(kick_session);
+    let extend_session = warp::path!("admin" / "1" / "sessions" / SessionId / "extend")
+        .and(warp::post())
+        .and(warp::body::bytes())
+        .and(manager.clone())
+        .and_then(extend_session);
+    let patch_props = warp::path!("admin" / "1" / "sessions" / SessionId / "props")
+        .and(warp::patch())
+        .and(warp::body::bytes())
+        .and(manager)
+        .and_then(patch_props);
+
+    authorized(token)
+        .and(
+            list_sessions
+                .or(get_session)
+                .or(kick_session)
+                .or(extend_session)
+                .or(patch_props) , 
+        )
+        .recover(handle_rejection)
+        .with(warp::log("echo-admin"))
+}
+
+fn authorized(token: String) -> impl Filter<Extract = () ,  Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let expected = format!("Bearer {}" ,  token);
+            async move {
+                match header {
+                    Some(header) if constant_time_eq(header.as_bytes() ,  expected.as_bytes()) => {
+                        Ok(())
+                    }
+                    _ => Err(reject::custom(Unauthorized)) , 
+                }
+            }
+        })
+        .untuple_one()
+}
+
+fn constant_time_eq(a: &[u8] ,  b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0 ,  |acc ,  (x ,  y)| acc | (x ^ y)) == 0
+}
+
+async fn sessions(session_manager: &ManagerHandle) -> Result<Vec<SessionInfo> ,  Rejection> {
+    let (responder ,  response) = oneshot::channel();
+    session_manager
+        .send(ManageMessage::ListSessions(responder))
+        .map_err(|_| reject::custom(Unavailable))?;
+    response.await.map_err(|_| reject::custom(Unavailable))
+}
+
+async fn list_sessions(session_manager: ManagerHandle) -> Result<impl Reply ,  Rejection> {
+    let sessions: Vec<SessionResponse> = sessions(&session_manager)
+        .await?
+        .into_iter()
+        .map(SessionResponse::from)
+        .collect();
+    Ok(warp::reply::json(&sessions))
+}
+
+async fn get_session(
+    id: SessionId , 
+    session_manager: ManagerHandle , 
+) -> Result<impl Reply ,  Rejection> {
+    match sessions(&session_manager)
+        .await?
+        .into_iter()
+        .find(|info| info.id == id)
+    {
+        Some(info) => Ok(warp::reply::json(&SessionResponse::from(info))) , 
+        None => Err(reject::custom(NotFound)) , 
+    }
+}
+
+async fn kick_session(
+    id: SessionId , 
+    body: Bytes , 
+    session_manager: ManagerHandle , 
+) -> Result<impl Reply ,  Rejection> {
+    let request = if body.is_empty() {
+        KickRequest::default()
+    } else {
+        serde_json::from_slice::<KickRequest>(&body).map_err(|_| reject::custom(BadRequest))?
+    };
+    let reason = StateReason::new(
+        request.code.unwrap_or(KICK_CODE) , 
+        request.message.as_deref().unwrap_or(KICK_MESSAGE) , 
+    );
+
+    let (responder ,  response) = oneshot::channel();
+    session_manager
+        .send(ManageMessage::KickSession(id ,  reason ,  responder))
+        .map_err(|_| reject::custom(Unavailable))?;
+    match response.await.map_err(|_| reject::custom(Unavailable))? {
+        Ok(()) => Ok(StatusCode::NO_CONTENT) , 
+        Err(SessError::NotFound) => Err(reject::custom(NotFound)) , 
+        Err(err) => {
+            log::error!("Failed to kick session {}: {}" ,  id ,  err);
+            Err(reject::custom(Unavailable))
+        }
+    }
+}
+
+async fn extend_session(
+    id: SessionId , 
+    body: Bytes , 
+    session_manager: ManagerHandle , 
+) -> Result<impl Reply ,  Rejection> {
+    let request: ExtendRequest =
+        serde_json::from_slice(&body).map_err(|_| reject::custom(BadRequest))?;
+    if request.seconds > MAX_EXTEND_SECONDS {
+        return Err(reject::custom(BadRequest));
+    }
+    let duration = Duration::from_secs(request.seconds);
+    let (responder ,  response) = oneshot::channel();
+    session_manager
+        .send(ManageMessage::ExtendSession(id ,  duration ,  responder))
+        .map_err(|_| reject::custom(Unavailable))?;
+    match response.await.map_err(|_| reject::custom(Unavailable))? {
+        Ok(remaining) => Ok(warp::reply::json(&ExtendResponse {
+            remaining: remaining.as_secs() , 
+        })) , 
+        Err(SessError::NotFound) => Err(reject::custom(NotFound)) , 
+        Err(SessError::Unlimited) => Err(reject::custom(Unlimited)) , 
+        Err(SessError::OutOfRange) => Err(reject::custom(BadRequest)) , 
+        Err(err) => {
+            log::error!("Failed to extend session {}: {}" ,  id ,  err);
+            Err(reject::custom(Unavailable))
+        }
+    }
+}
+
+async fn patch_props(
+    id: SessionId , 
+    body: Bytes , 
+    session_manager: ManagerHandle , 
+) -> Result<impl Reply ,  Rejection> {
+    let patch: PropsPatch =
+        serde_json::from_slice(&body).map_err(|_| reject::custom(BadRequest))?;
+    let (responder ,  response) = oneshot::channel();
+    session_manager
+        .send(ManageMessage::PatchSessionProps(id ,  patch ,  responder))
+        .map_err(|_| reject::custom(Unavailable))?;
+    match response.await.map_err(|_| reject::custom(Unavailable))? {
+        Ok(props) => Ok(warp::reply::json(&props)) , 
+        Err(SessError::NotFound) => Err(reject::custom(NotFound)) , 
+        Err(err) => {
+            log::error!("Failed to patch props of session {}: {}" ,  id ,  err);
+            Err(reject::custom(Unavailable))
+        }
+    }
+}
+
+async fn handle_rejection(err: Rejection) -> Result<impl Reply ,  Infallible> {
+    let (status ,  error) = if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED ,  "unauthorized")
+    } else if err.is_not_found() || err.find::<NotFound>().is_some() {
+        (StatusCode::NOT_FOUND ,  "not found")
+    } else if err.find::<BadRequest>().is_some() {
+        (StatusCode::BAD_REQUEST ,  "bad request")
+    } else if err.find::<Unlimited>().is_some() {
+        (StatusCode::CONFLICT ,  "no time limit")
+    } else if err.find::<reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED ,  "method not allowed")
+    } else if err.find::<Unavailable>().is_some() {
+        (StatusCode::SERVICE_UNAVAILABLE ,  "unavailable")
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR ,  "internal error")
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&ErrorResponse { error }) , 
+        status , 
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::* ,  std::time::SystemTime ,  tokio::sync::mpsc};
+
+    // answers like a session manager holding a single session 1
+    fn manager() -> ManagerHandle {
+        let (handle ,  mut incoming) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(message) = incoming.recv().await {
+                match message {
+                    ManageMessage::ListSessions(responder) => {
+                        let _ = responder.send(vec![SessionInfo {
+                            id: 1 , 
+                            name: "dj".to_string() , 
+                            protocol: Protocol::RTMP , 
+                            ingest_id: 1 , 
+                            created_at: SystemTime::now() , 
+                            expires_at: None , 
+                            props: None , 
+                        }]);
+                    }
+                    ManageMessage::KickSession(id ,  _ ,  responder) => {
+                        let result = if id == 1 {
+                            Ok(())
+                        } else {
+                            Err(SessError::NotFound)
+                        };
+                        let _ = responder.send(result);
+                    }
+                    ManageMessage::PatchSessionProps(1 ,  patch ,  responder) => {
+                        let props = patch
+                            .into_iter()
+                            .filter_map(|(key ,  value)| value.map(|value| (key ,  value)))
+                            .collect();
+                        let _ = responder.send(Ok(props));
+                    }
+                    ManageMessage::ExtendSession(id ,  duration ,  responder) => {
+                        let result = if id == 1 {
+                            Ok(duration + Duration::from_secs(60))
+                        } else {
+                            Err(SessError::NotFound)
+                        };
+                        let _ = responder.send(result);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        handle
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized() {
+        let routes = routes(manager() ,  "secret".to_string());
+        let res = warp::test::request()
+            .path("/admin/1/sessions")
+            .header("authorization" ,  "Bearer wrong")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_sessions() {
+        let routes = routes(manager() ,  "secret".to_string());
+        let res = warp::test::request()
+            .path("/admin/1/sessions/1")
+            .header("authorization" ,  "Bearer secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::OK);
+        let session: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(session["name"] ,  "dj");
+        assert_eq!(session["protocol"] ,  "rtmp");
+
+        let res = warp::test::request()
+            .method("DELETE")
+            .path("/admin/1/sessions/1")
+            .header("authorization" ,  "Bearer secret")
+            .body(r#"{"code":40001,"message":"abusive stream"}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::NO_CONTENT);
+
+        let res = warp::test::request()
+            .method("DELETE")
+            .path("/admin/1/sessions/2")
+            .header("authorization" ,  "Bearer secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_extend_session() {
+        let routes = routes(manager() ,  "secret".to_string());
+        let res = warp::test::request()
+            .method("POST")
+            .path("/admin/1/sessions/1/extend")
+            .header("authorization" ,  "Bearer secret")
+            .body(r#"{"seconds":600}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::OK);
+        let extended: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(extended["remaining"] ,  660);
+
+        let res = warp::test::request()
+            .method("POST")
+            .path("/admin/1/sessions/1/extend")
+            .header("authorization" ,  "Bearer secret")
+            .body("600")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_patch_props() {
+        let routes = routes(manager() ,  "secret".to_string());
+        let res = warp::test::request()
+            .method("PATCH")
+            .path("/admin/1/sessions/1/props")
+            .header("authorization" ,  "Bearer secret")
+            .body(r#"{"stage":"live","country":null}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status() ,  StatusCode::OK);
+        let props: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(props ,  serde_json::json!({ "stage": "live" }));
+    }
+}
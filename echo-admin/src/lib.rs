@@ -0,0 +1,4 @@
+mod api;
+pub mod service;
+
+pub use self::service::Service;
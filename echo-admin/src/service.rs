@@ -0,0 +1,27 @@
+use {
+    crate::api , 
+    echo_core::{session::ManagerHandle ,  Config} , 
+};
+
+pub struct Service {
+    config: Config , 
+    session_manager: ManagerHandle , 
+}
+
+impl Service {
+    pub fn new(session_manager: ManagerHandle ,  config: Config) -> Self {
+        Self {
+            config , 
+            session_manager , 
+        }
+    }
+
+    pub async fn run(self) {
+        let token = self.config.admin_token.clone().unwrap_or_default();
+        let routes = api::routes(self.session_manager.clone() ,  token);
+
+        let addr = self.config.admin_addr;
+        log::info!("Admin API listening on {}" ,  addr);
+        warp::serve(routes).run(addr).await;
+    }
+}
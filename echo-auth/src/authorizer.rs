@@ -0,0 +1,265 @@
+use {
+    anyhow::Result , 
+    echo_core::{
+        authorization::{Authorization ,  Error as AuthError} , 
+        session::SessionProps , 
+        Config , 
+    } , 
+    hyper::{
+        client::HttpConnector , 
+        header::{CONTENT_TYPE ,  USER_AGENT} , 
+        Body ,  Client ,  Method ,  Request ,  StatusCode , 
+    } , 
+    lru_time_cache::LruCache , 
+    serde_json::json , 
+    std::time::Duration , 
+    tokio::{sync::Mutex ,  time::timeout} , 
+};
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+// same status code echo-transfer answers for an expired token
+const STATUS_EXPIRED_TOKEN: u16 = 460;
+
+pub(crate) struct Authorizer {
+    client: Client<HttpConnector> , 
+    url: String , 
+    timeout: Duration , 
+    fail_open: bool , 
+    cache: Option<Mutex<LruCache<String ,  Result<() ,  AuthError>>>> , 
+}
+
+impl Authorizer {
+    pub(crate) fn new(config: &Config) -> Self {
+        let cache = if config.auth_cache_ttl > Duration::from_secs(0) {
+            Some(Mutex::new(LruCache::with_expiry_duration(
+                config.auth_cache_ttl , 
+            )))
+        } else {
+            None
+        };
+
+        Self {
+            client: Client::new() , 
+            url: config.auth_url.clone().unwrap_or_default() , 
+            timeout: config.auth_timeout , 
+            fail_open: config.auth_fail_open , 
+            cache , 
+        }
+    }
+
+    pub(crate) async fn authorize(
+        &self , 
+        name: &str , 
+        authorization: &Authorization , 
+        props: Option<&SessionProps> , 
+    ) -> Result<() ,  AuthError> {
+        let cache_key = cache_key(name ,  authorization);
+        if let Some(ref cache) = self.cache {
+            let mut cache = cache.lock().await;
+            if let Some(result) = cache.get(&cache_key) {
+                log::debug!("{} authorization cache hit {:?}" ,  name ,  result);
+                return result.clone();
+            }
+        }
+
+        match timeout(self.timeout ,  self.request(name ,  authorization ,  props)).await {
+            Ok(Ok(status)) => {
+                let result = match status.as_u16() {
+                    200..=299 => Ok(()) , 
+                    401 | 403 => Err(AuthError::Unauthorized) , 
+                    STATUS_EXPIRED_TOKEN => Err(AuthError::ExpiredToken) , 
+                    _ => {
+                        log::error!("{} authorization unexpected status {}" ,  name ,  status);
+                        return self.fallback();
+                    }
+                };
+                if let Some(ref cache) = self.cache {
+                    let mut cache = cache.lock().await;
+                    cache.insert(cache_key ,  result.clone());
+                }
+                result
+            }
+            Ok(Err(err)) => {
+                log::error!("{} authorization request error: {}" ,  name ,  err);
+                self.fallback()
+            }
+            Err(_) => {
+                log::error!("{} authorization timeout {:?}" ,  name ,  self.timeout);
+                self.fallback()
+            }
+        }
+    }
+
+    async fn request(
+        &self , 
+        name: &str , 
+        authorization: &Authorization , 
+        props: Option<&SessionProps> , 
+    ) -> Result<StatusCode> {
+        let authorization = match authorization {
+            Authorization::Bearer(token) => json!({ "type": "bearer" ,  "token": token }) , 
+            Authorization::Basic(user_id ,  password) => {
+                json!({ "type": "basic" ,  "user_id": user_id ,  "password": password })
+            }
+        };
+        let body = json!({
+            "name": name , 
+            "authorization": authorization , 
+            "props": props , 
+        });
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header(CONTENT_TYPE ,  "application/json")
+            .header(USER_AGENT ,  format!("Echo/{}" ,  VERSION.unwrap()))
+            .body(Body::from(body.to_string()))?;
+        let res = self.client.request(req).await?;
+
+        Ok(res.status())
+    }
+
+    // the endpoint could not give an answer
+    fn fallback(&self) -> Result<() ,  AuthError> {
+        if self.fail_open {
+            log::warn!("authorization failed open");
+            Ok(())
+        } else {
+            Err(AuthError::Unauthorized)
+        }
+    }
+}
+
+fn cache_key(name: &str ,  authorization: &Authorization) -> String {
+    match authorization {
+        Authorization::Bearer(token) => format!("{}\nbearer\n{}" ,  name ,  token) , 
+        Authorization::Basic(user_id ,  password) => {
+            format!("{}\nbasic\n{}\n{}" ,  name ,  user_id ,  password)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        hyper::{
+            service::{make_service_fn ,  service_fn} , 
+            Response ,  Server , 
+        } , 
+        std::{
+            convert::Infallible , 
+            net::SocketAddr , 
+            sync::{
+                atomic::{AtomicUsize ,  Ordering} , 
+                Arc , 
+            } , 
+        } , 
+    };
+
+    // answers by the bearer token: "ok" ,  "expired" ,  "slow" or anything else
+    async fn mock_endpoint(req: Request<Body>) -> Result<Response<Body> ,  Infallible> {
+        let bytes = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let status = match body["authorization"]["token"].as_str() {
+            Some("ok") => 200 , 
+            Some("expired") => STATUS_EXPIRED_TOKEN , 
+            Some("slow") => {
+                tokio::time::delay_for(Duration::from_millis(500)).await;
+                200
+            }
+            Some("broken") => 500 , 
+            _ => 401 , 
+        };
+        Ok(Response::builder()
+            .status(status)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    fn start_mock(hits: Arc<AtomicUsize>) -> SocketAddr {
+        let make_svc = make_service_fn(move |_| {
+            let hits = hits.clone();
+            async move {
+                Ok::<_ ,  Infallible>(service_fn(move |req| {
+                    hits.fetch_add(1 ,  Ordering::SeqCst);
+                    mock_endpoint(req)
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127 ,  0 ,  0 ,  1] ,  0))).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    fn test_config(addr: SocketAddr) -> Config {
+        Config {
+            auth_enabled: true , 
+            auth_url: Some(format!("http://{}/authorize" ,  addr)) , 
+            auth_timeout: Duration::from_millis(200) , 
+            ..Config::default()
+        }
+    }
+
+    fn bearer(token: &str) -> Authorization {
+        Authorization::Bearer(token.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_authorize() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let authorizer = Authorizer::new(&test_config(start_mock(hits)));
+
+        assert!(authorizer.authorize("dj" ,  &bearer("ok") ,  None).await.is_ok());
+        assert!(matches!(
+            authorizer.authorize("dj" ,  &bearer("nope") ,  None).await , 
+            Err(AuthError::Unauthorized)
+        ));
+        assert!(matches!(
+            authorizer.authorize("dj" ,  &bearer("expired") ,  None).await , 
+            Err(AuthError::ExpiredToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authorize_cache() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let authorizer = Authorizer::new(&test_config(start_mock(hits.clone())));
+
+        for _ in 0..3 {
+            assert!(authorizer.authorize("dj" ,  &bearer("ok") ,  None).await.is_ok());
+        }
+        assert_eq!(hits.load(Ordering::SeqCst) ,  1);
+
+        // another stream name is another cache entry
+        assert!(authorizer.authorize("dj2" ,  &bearer("ok") ,  None).await.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst) ,  2);
+    }
+
+    #[tokio::test]
+    async fn test_authorize_fail_policy() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let mut config = test_config(start_mock(hits.clone()));
+
+        let authorizer = Authorizer::new(&config);
+        assert!(matches!(
+            authorizer.authorize("dj" ,  &bearer("slow") ,  None).await , 
+            Err(AuthError::Unauthorized)
+        ));
+        assert!(matches!(
+            authorizer.authorize("dj" ,  &bearer("broken") ,  None).await , 
+            Err(AuthError::Unauthorized)
+        ));
+
+        config.auth_fail_open = true;
+        let authorizer = Authorizer::new(&config);
+        assert!(authorizer.authorize("dj" ,  &bearer("slow") ,  None).await.is_ok());
+        assert!(authorizer.authorize("dj" ,  &bearer("broken") ,  None).await.is_ok());
+
+        // undecided answers are never cached
+        assert!(authorizer.authorize("dj" ,  &bearer("broken") ,  None).await.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst) ,  5);
+    }
+}
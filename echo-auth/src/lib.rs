@@ -0,0 +1,4 @@
+mod authorizer;
+pub mod service;
+
+pub use self::service::Service;
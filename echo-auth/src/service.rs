@@ -0,0 +1,59 @@
+use {
+    crate::authorizer::Authorizer , 
+    echo_core::{
+        session::{self ,  EventKind ,  EventMessage ,  ManageMessage ,  ManagerHandle} , 
+        Config , 
+    } , 
+    std::sync::Arc , 
+};
+
+pub struct Service {
+    config: Config , 
+    session_manager: ManagerHandle , 
+}
+
+impl Service {
+    pub fn new(session_manager: ManagerHandle ,  config: Config) -> Self {
+        Self {
+            config , 
+            session_manager , 
+        }
+    }
+
+    pub async fn run(self) {
+        let authorizer = Arc::new(Authorizer::new(&self.config));
+        log::info!(
+            "Authorize publish requests with '{}'" , 
+            self.config.auth_url.as_deref().unwrap_or("-")
+        );
+
+        let (trigger ,  mut trigger_watcher) = session::trigger_channel();
+
+        if self
+            .session_manager
+            .send(ManageMessage::RegisterTrigger(
+                EventKind::AuthorizeSession , 
+                trigger , 
+            ))
+            .is_err()
+        {
+            log::error!("Failed to register AuthorizeSession trigger");
+            panic!("Failed to register AuthorizeSession trigger");
+        }
+
+        while let Some((name ,  event)) = trigger_watcher.recv().await {
+            if let EventMessage::AuthorizeSession(authorization ,  props ,  responder) = event {
+                let authorizer = authorizer.clone();
+                tokio::spawn(async move {
+                    let result = authorizer
+                        .authorize(&name ,  &authorization ,  props.as_ref())
+                        .await;
+                    log::info!("{} authorization result {:?}" ,  name ,  result);
+                    if responder.send(result).is_err() {
+                        log::error!("{} failed to send authorization result" ,  name);
+                    }
+                });
+            }
+        }
+    }
+}
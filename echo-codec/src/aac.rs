@@ -1,9 +1,11 @@
 pub mod adts;
+mod bits;
 pub mod common;
 pub mod config;
 pub mod error;
+pub mod silence;
 
-pub use self::{adts::AudioDataTransportStream ,  error::AacError};
+pub use self::{adts::AudioDataTransportStream ,  error::AacError ,  silence::silent_raw_data_block};
 use {
     self::config::AudioSpecificConfiguration , 
     crate::{FormatReader ,  FormatWriter ,  ReadFormat ,  WriteFormat} , 
@@ -12,6 +14,7 @@ use {
 
 pub const ADTS_FRAME_SAMPLES: u32 = 1024;
 
+#[derive(Debug ,  Clone ,  PartialEq ,  Eq)]
 pub struct Aac(Vec<u8>);
 
 impl From<&[u8]> for Aac {
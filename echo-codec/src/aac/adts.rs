@@ -1,10 +1,15 @@
 use {
-    super::config::AudioSpecificConfiguration , 
+    super::{
+        bits::BitReader , 
+        common::{AudioObjectType ,  ChannelConfiguration ,  SamplingFrequencyIndex} , 
+        config::AudioSpecificConfiguration , 
+    } , 
     crate::{
         aac::{error::AacError ,  Aac} , 
-        WriteFormat , 
+        ReadFormat ,  WriteFormat , 
     } , 
     bytes::BufMut , 
+    std::convert::TryFrom , 
 };
 
 // Bits | Description
@@ -24,7 +29,9 @@ use {
 // 13   | Frame length
 // 11   | Buffer fullness
 // 2    | Number of AAC frames - 1
+// 16   | Position of each AAC frame after the first if protected
 // 16   | CRC if protection flag not set
+// AAC frames ,  each followed by a 16 bit CRC if protected and more than one
 //
 // https://wiki.multimedia.cx/index.php/ADTS
 #[derive(Debug ,  Clone)]
@@ -33,6 +40,247 @@ pub struct AudioDataTransportStream;
 impl AudioDataTransportStream {
     const SYNCWORD: u16 = 0xFFF0;
     const PROTECTION_ABSENCE: u16 = 0x0001;
+    const HEADER_LENGTH: usize = 7;
+    const CRC_LENGTH: usize = 2;
+
+    /// Reads the frames of the buffer one after another ,  each of them must end
+    /// the buffer or be followed by the next. After corrupt data an error is
+    /// told once ,  then the frames go on from the next syncword.
+    pub fn read_frames<'a>(&self ,  buf: &'a [u8]) -> AdtsFrames<'a> {
+        AdtsFrames {
+            buf , 
+            pos: 0 , 
+            resync: false , 
+        }
+    }
+}
+
+/// The header of an ADTS frame ,  without the bits only telling copyright.
+#[derive(Debug ,  Clone ,  PartialEq ,  Eq)]
+pub struct AdtsHeader {
+    /// MPEG-2 rather than MPEG-4.
+    pub mpeg2: bool , 
+    pub protection_absent: bool , 
+    pub object_type: AudioObjectType , 
+    pub sampling_frequency_index: SamplingFrequencyIndex , 
+    pub channel_configuration: ChannelConfiguration , 
+    /// The bytes of the frame ,  header included.
+    pub frame_length: u16 , 
+    pub buffer_fullness: u16 , 
+    /// The AAC frames of the ADTS frame minus one.
+    pub number_of_raw_data_blocks: u8 , 
+}
+
+impl AdtsHeader {
+    /// The configuration of the frames ,  HE-AAC being told implicitly.
+    pub fn audio_specific_config(&self) -> AudioSpecificConfiguration {
+        AudioSpecificConfiguration::new(
+            self.object_type , 
+            self.sampling_frequency_index , 
+            self.channel_configuration , 
+        )
+    }
+
+    // the header with the block positions and the CRC
+    fn len(&self) -> usize {
+        if self.protection_absent {
+            AudioDataTransportStream::HEADER_LENGTH
+        } else {
+            AudioDataTransportStream::HEADER_LENGTH
+                + AudioDataTransportStream::CRC_LENGTH * (self.number_of_raw_data_blocks as usize + 1)
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for AdtsHeader {
+    type Error = AacError;
+
+    fn try_from(val: &[u8]) -> Result<Self ,  Self::Error> {
+        if val.len() < AudioDataTransportStream::HEADER_LENGTH {
+            return Err(AacError::NotEnoughData("ADTS header"));
+        }
+        let mut bits = BitReader::new(val ,  "ADTS header");
+
+        if bits.get(12)? != 0xFFF {
+            return Err(AacError::InvalidAdtsHeader("syncword"));
+        }
+        let mpeg2 = bits.get_bit()?;
+        if bits.get(2)? != 0 {
+            return Err(AacError::InvalidAdtsHeader("layer"));
+        }
+        let protection_absent = bits.get_bit()?;
+        let object_type = AudioObjectType::try_from(bits.get(2)? as u8 + 1)?;
+        let sampling_frequency_index = SamplingFrequencyIndex::try_from(bits.get(4)? as u8)?;
+        if u8::from(sampling_frequency_index) == SamplingFrequencyIndex::EXPLICIT {
+            return Err(AacError::ForbiddenSamplingFrequencyIndex(
+                SamplingFrequencyIndex::EXPLICIT , 
+            ));
+        }
+        // private
+        bits.get_bit()?;
+        let channel_configuration = ChannelConfiguration::try_from(bits.get(3)? as u8)?;
+        // originality ,  home ,  copyrighted ID and its start
+        bits.get(4)?;
+        let frame_length = bits.get(13)? as u16;
+        let buffer_fullness = bits.get(11)? as u16;
+        let number_of_raw_data_blocks = bits.get(2)? as u8;
+
+        let header = Self {
+            mpeg2 , 
+            protection_absent , 
+            object_type , 
+            sampling_frequency_index , 
+            channel_configuration , 
+            frame_length , 
+            buffer_fullness , 
+            number_of_raw_data_blocks , 
+        };
+        if (frame_length as usize) <= header.len() {
+            return Err(AacError::InvalidAdtsHeader("frame length"));
+        }
+        Ok(header)
+    }
+}
+
+/// An ADTS frame split into its AAC frames.
+#[derive(Debug ,  Clone)]
+pub struct AdtsFrame {
+    pub header: AdtsHeader , 
+    /// A single one holding all of them if they could not be split.
+    pub blocks: Vec<Aac> , 
+}
+
+impl AdtsFrame {
+    /// Whether each AAC frame is a block of its own.
+    pub fn is_split(&self) -> bool {
+        self.blocks.len() == self.header.number_of_raw_data_blocks as usize + 1
+    }
+}
+
+/// Reads the frame at the start of the input.
+///
+/// The AAC frames of a protected frame are split at the positions in its
+/// header ,  whose CRC is checked. An unprotected frame has no positions and
+/// only a decoder finds the end of each AAC frame ,  so they are left in a
+/// single block. The CRC of a frame of a single AAC frame ,  like the ones
+/// after each AAC frame ,  covers the first bits of each channel as well , 
+/// which only a decoder can tell apart ,  so it is not checked.
+impl ReadFormat<AdtsFrame> for AudioDataTransportStream {
+    type Context = ();
+    type Error = AacError;
+
+    fn read_format(&self ,  input: &[u8] ,  _ctx: &Self::Context) -> Result<AdtsFrame ,  Self::Error> {
+        let header = AdtsHeader::try_from(input)?;
+        let frame_length = header.frame_length as usize;
+        if input.len() < frame_length {
+            return Err(AacError::NotEnoughData("ADTS frame"));
+        }
+        let frame = &input[..frame_length];
+        let first = header.len();
+
+        if header.number_of_raw_data_blocks == 0 || header.protection_absent {
+            let blocks = vec![Aac::from(&frame[first..])];
+            return Ok(AdtsFrame { header ,  blocks });
+        }
+
+        let checked = first - Self::CRC_LENGTH;
+        let crc = u16::from_be_bytes([frame[checked] ,  frame[checked + 1]]);
+        let expected = crc16(&frame[..checked]);
+        if crc != expected {
+            return Err(AacError::CrcMismatch { expected ,  actual: crc });
+        }
+
+        // the positions are counted from the first AAC frame
+        let mut starts = vec![first];
+        for position in frame[Self::HEADER_LENGTH..checked].chunks(2) {
+            starts.push(first + u16::from_be_bytes([position[0] ,  position[1]]) as usize);
+        }
+        starts.push(frame_length);
+
+        let mut blocks = Vec::with_capacity(starts.len() - 1);
+        for bounds in starts.windows(2) {
+            // each AAC frame is followed by its CRC
+            let end = bounds[1].saturating_sub(Self::CRC_LENGTH);
+            if end <= bounds[0] || bounds[1] > frame_length {
+                return Err(AacError::InvalidAdtsHeader("AAC frame position"));
+            }
+            blocks.push(Aac::from(&frame[bounds[0]..end]));
+        }
+        Ok(AdtsFrame { header ,  blocks })
+    }
+}
+
+/// The frames of a buffer ,  see [`AudioDataTransportStream::read_frames`].
+pub struct AdtsFrames<'a> {
+    buf: &'a [u8] , 
+    pos: usize , 
+    // looking for the next frame after corrupt data
+    resync: bool , 
+}
+
+impl Iterator for AdtsFrames<'_> {
+    type Item = Result<AdtsFrame ,  AacError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.resync {
+                match self.buf[self.pos..].windows(2).position(is_syncword) {
+                    Some(offset) => self.pos += offset , 
+                    None => self.pos = self.buf.len() , 
+                }
+            }
+            let input = &self.buf[self.pos..];
+            if input.is_empty() {
+                return None;
+            }
+
+            let frame = AudioDataTransportStream
+                .read_format(input ,  &())
+                .and_then(|frame| {
+                    // a syncword in corrupt data is taken only if a frame follows
+                    let len = frame.header.frame_length as usize;
+                    if len == input.len() || is_syncword(&input[len..]) {
+                        Ok(frame)
+                    } else {
+                        Err(AacError::InvalidAdtsHeader("frame length"))
+                    }
+                });
+            match frame {
+                Ok(frame) => {
+                    self.resync = false;
+                    self.pos += frame.header.frame_length as usize;
+                    return Some(Ok(frame));
+                }
+                Err(err) => {
+                    self.pos += 1;
+                    if !self.resync {
+                        self.resync = true;
+                        return Some(Err(err));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_syncword(buf: &[u8]) -> bool {
+    buf.len() >= 2 && buf[0] == 0xFF && buf[1] & 0xF6 == 0xF0
+}
+
+// CRC-16 of MPEG audio ,  x^16 + x^15 + x^2 + 1
+fn crc16(buf: &[u8]) -> u16 {
+    let mut crc = 0xFFFF;
+    for &byte in buf {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 == 0x8000 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
 }
 
 impl WriteFormat<Aac> for AudioDataTransportStream {
@@ -49,15 +297,20 @@ impl WriteFormat<Aac> for AudioDataTransportStream {
 
         // Profile (2 bits = 0) ,  sampling frequency index (4 bits) , 
         // private (1 bit = 0) and channel configuration (1 bit)
+        // HE-AAC is told implicitly ,  the header is of its AAC core
         let object_type = ctx.object_type as u8;
+        if object_type > AudioObjectType::AacLongTermPrediction as u8 {
+            return Err(AacError::UnsupportedAudioFormat);
+        }
         let profile = (object_type - 1) << 6;
 
-        let sampling_frequency_index = u8::from(ctx.sampling_frequency_index) << 2;
-        if sampling_frequency_index == 0x0F {
+        let sampling_frequency_index = u8::from(ctx.sampling_frequency_index);
+        if sampling_frequency_index == SamplingFrequencyIndex::EXPLICIT {
             return Err(AacError::ForbiddenSamplingFrequencyIndex(
                 sampling_frequency_index , 
             ));
         }
+        let sampling_frequency_index = sampling_frequency_index << 2;
 
         let channel_configuration: u8 = ctx.channel_configuration.into();
         let channel_configuration1 = (channel_configuration & 0x07) >> 2;
@@ -86,3 +339,117 @@ impl WriteFormat<Aac> for AudioDataTransportStream {
         Ok(tmp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a silent frame of a voice optimised app
+    const ADTS_32000_MONO_FRAME: &[u8] = &[
+        0xff ,  0xf1 ,  0x54 ,  0x40 ,  0x01 ,  0x7f ,  0xfc ,  0x01 ,  0x18 ,  0x20 ,  0x07 , 
+    ];
+
+    // two AAC frames with the positions and CRCs of protection
+    fn protected_frame() -> Vec<u8> {
+        let blocks: [&[u8]; 2] = [&[1 ,  2 ,  3] ,  &[4 ,  5]];
+        let payload_len = 2 + 2 + blocks[0].len() + 2 + blocks[1].len() + 2;
+        let asc = AdtsHeader::try_from(ADTS_32000_MONO_FRAME)
+            .unwrap()
+            .audio_specific_config();
+        let mut frame = AudioDataTransportStream
+            .write_format(Aac::from(&vec![0; payload_len][..]) ,  &asc)
+            .unwrap();
+        frame.truncate(AudioDataTransportStream::HEADER_LENGTH);
+        // protected with one more AAC frame
+        frame[1] &= 0xFE;
+        frame[6] |= 0x01;
+        frame.extend_from_slice(&[0x00 ,  0x05]);
+        let crc = crc16(&frame);
+        frame.extend_from_slice(&crc.to_be_bytes());
+        for block in &blocks {
+            frame.extend_from_slice(block);
+            frame.extend_from_slice(&[0x00 ,  0x00]);
+        }
+        frame
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let frame = AudioDataTransportStream
+            .read_format(ADTS_32000_MONO_FRAME ,  &())
+            .unwrap();
+        assert_eq!(frame.header.object_type ,  AudioObjectType::AacLowComplexity);
+        assert_eq!(frame.header.sampling_frequency_index.freq() ,  Some(32000));
+        assert_eq!(u8::from(frame.header.channel_configuration) ,  1);
+        assert_eq!(frame.header.frame_length ,  11);
+        assert_eq!(frame.blocks ,  vec![Aac::from(&ADTS_32000_MONO_FRAME[7..])]);
+
+        let written = AudioDataTransportStream
+            .write_format(
+                frame.blocks[0].clone() , 
+                &frame.header.audio_specific_config() , 
+            )
+            .unwrap();
+        assert_eq!(written ,  ADTS_32000_MONO_FRAME);
+
+        assert!(AudioDataTransportStream
+            .read_format(&ADTS_32000_MONO_FRAME[..10] ,  &())
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_protected_frame() {
+        let mut buf = protected_frame();
+        let frame = AudioDataTransportStream.read_format(&buf ,  &()).unwrap();
+        assert!(!frame.header.protection_absent);
+        assert_eq!(
+            frame.blocks , 
+            vec![Aac::from(&[1u8 ,  2 ,  3][..]) ,  Aac::from(&[4u8 ,  5][..])]
+        );
+
+        buf[8] ^= 0x01;
+        match AudioDataTransportStream.read_format(&buf ,  &()) {
+            Err(AacError::CrcMismatch { .. }) => {}
+            _ => panic!("CRC not checked") , 
+        }
+    }
+
+    #[test]
+    fn test_read_unprotected_frames() {
+        let asc = AdtsHeader::try_from(ADTS_32000_MONO_FRAME)
+            .unwrap()
+            .audio_specific_config();
+        let mut buf = AudioDataTransportStream
+            .write_format(Aac::from(&[1u8 ,  2 ,  3 ,  4 ,  5][..]) ,  &asc)
+            .unwrap();
+        // two AAC frames
+        buf[6] |= 0x01;
+
+        let frame = AudioDataTransportStream.read_format(&buf ,  &()).unwrap();
+        assert_eq!(frame.header.number_of_raw_data_blocks ,  1);
+        assert!(!frame.is_split());
+        assert_eq!(frame.blocks ,  vec![Aac::from(&[1u8 ,  2 ,  3 ,  4 ,  5][..])]);
+
+        let frame = AudioDataTransportStream
+            .read_format(&protected_frame() ,  &())
+            .unwrap();
+        assert!(frame.is_split());
+    }
+
+    #[test]
+    fn test_read_frames_after_corruption() {
+        let protected = protected_frame();
+        let mut buf = vec![0x00 ,  0xFF];
+        buf.extend_from_slice(ADTS_32000_MONO_FRAME);
+        buf.extend_from_slice(&ADTS_32000_MONO_FRAME[..5]);
+        buf.extend_from_slice(&protected);
+        buf.extend_from_slice(ADTS_32000_MONO_FRAME);
+
+        let frames: Vec<_> = AudioDataTransportStream.read_frames(&buf).collect();
+        let blocks: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.as_ref().map(|frame| frame.blocks.len()).ok())
+            .collect();
+        assert_eq!(blocks ,  vec![None ,  Some(1) ,  None ,  Some(2) ,  Some(1)]);
+    }
+}
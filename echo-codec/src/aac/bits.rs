@@ -0,0 +1,89 @@
+use super::AacError;
+
+/// Reads bits most significant first ,  as the AAC syntax is laid out.
+pub(crate) struct BitReader<'a> {
+    buf: &'a [u8] , 
+    pos: usize , 
+    what: &'static str , 
+}
+
+impl<'a> BitReader<'a> {
+    /// `what` is told when there's not enough data.
+    pub(crate) fn new(buf: &'a [u8] ,  what: &'static str) -> Self {
+        Self { buf ,  pos: 0 ,  what }
+    }
+
+    /// Reads `count` bits ,  at most 32.
+    pub(crate) fn get(&mut self ,  count: u8) -> Result<u32 ,  AacError> {
+        if self.remaining() < count as usize {
+            return Err(AacError::NotEnoughData(self.what));
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let bit = (self.buf[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn get_bit(&mut self) -> Result<bool ,  AacError> {
+        Ok(self.get(1)? == 1)
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len() * 8 - self.pos
+    }
+
+    /// Skips to the next byte from the start.
+    pub(crate) fn byte_align(&mut self) {
+        let offset = self.pos % 8;
+        if offset != 0 {
+            self.pos += 8 - offset;
+        }
+    }
+}
+
+/// Writes bits most significant first ,  as the AAC syntax is laid out.
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    buf: Vec<u8> , 
+    len: usize , 
+}
+
+impl BitWriter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the lowest `count` bits of `value`.
+    pub(crate) fn put(&mut self ,  value: u32 ,  count: u8) {
+        for i in (0..count).rev() {
+            self.put_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    pub(crate) fn put_bit(&mut self ,  bit: bool) {
+        let offset = self.len % 8;
+        if offset == 0 {
+            self.buf.push(0);
+        }
+        if bit {
+            *self.buf.last_mut().unwrap() |= 0x80 >> offset;
+        }
+        self.len += 1;
+    }
+
+    /// Pads with zeros to the next byte from the start.
+    pub(crate) fn byte_align(&mut self) {
+        let offset = self.len % 8;
+        if offset != 0 {
+            self.put(0 ,  8 - offset as u8);
+        }
+    }
+
+    /// The bytes written ,  the last one padded with zeros.
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
@@ -1,6 +1,6 @@
 use {super::AacError ,  std::convert::TryFrom};
 
-#[derive(Debug ,  Clone ,  Copy)]
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
 pub struct SamplingFrequencyIndex(u8);
 
 impl From<SamplingFrequencyIndex> for u8 {
@@ -9,6 +9,20 @@ impl From<SamplingFrequencyIndex> for u8 {
     }
 }
 
+impl SamplingFrequencyIndex {
+    /// The index telling the sampling frequency is written after it.
+    pub const EXPLICIT: u8 = 15;
+
+    const FREQUENCIES: [u32; 13] = [
+        96000 ,  88200 ,  64000 ,  48000 ,  44100 ,  32000 ,  24000 ,  22050 ,  16000 ,  12000 ,  11025 ,  8000 ,  7350 , 
+    ];
+
+    /// `None` if the frequency is explicit.
+    pub fn freq(&self) -> Option<u32> {
+        Self::FREQUENCIES.get(self.0 as usize).copied()
+    }
+}
+
 impl TryFrom<u8> for SamplingFrequencyIndex {
     type Error = AacError;
 
@@ -20,7 +34,7 @@ impl TryFrom<u8> for SamplingFrequencyIndex {
     }
 }
 
-#[derive(Debug ,  Clone ,  Copy)]
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
 pub struct ChannelConfiguration(u8);
 
 impl From<ChannelConfiguration> for u8 {
@@ -50,6 +64,28 @@ pub enum AudioObjectType {
     AacLowComplexity = 2 , 
     AacScalableSampleRate = 3 , 
     AacLongTermPrediction = 4 , 
+    SpectralBandReplication = 5 , 
+    AacScalable = 6 , 
+    TwinVq = 7 , 
+    ErAacLowComplexity = 17 , 
+    ErAacLongTermPrediction = 19 , 
+    ErAacScalable = 20 , 
+    ErTwinVq = 21 , 
+    ErAacLowDelay = 23 , 
+    ParametricStereo = 29 , 
+}
+
+impl AudioObjectType {
+    /// Whether the object type is coded with the general audio syntax ,  e.g.
+    /// the core of HE-AAC.
+    pub fn is_general_audio(&self) -> bool {
+        !matches!(self ,  Self::SpectralBandReplication | Self::ParametricStereo)
+    }
+
+    /// Whether the object type is error resilient.
+    pub fn is_error_resilient(&self) -> bool {
+        *self as u8 >= 17 && *self != Self::ParametricStereo
+    }
 }
 
 impl TryFrom<u8> for AudioObjectType {
@@ -61,6 +97,15 @@ impl TryFrom<u8> for AudioObjectType {
             2 => Self::AacLowComplexity , 
             3 => Self::AacScalableSampleRate , 
             4 => Self::AacLongTermPrediction , 
+            5 => Self::SpectralBandReplication , 
+            6 => Self::AacScalable , 
+            7 => Self::TwinVq , 
+            17 => Self::ErAacLowComplexity , 
+            19 => Self::ErAacLongTermPrediction , 
+            20 => Self::ErAacScalable , 
+            21 => Self::ErTwinVq , 
+            23 => Self::ErAacLowDelay , 
+            29 => Self::ParametricStereo , 
             _ => return Err(AacError::UnsupportedAudioFormat) , 
         })
     }
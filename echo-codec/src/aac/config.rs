@@ -1,64 +1,704 @@
 use {
     super::{
+        bits::{BitReader ,  BitWriter} , 
         common::{AudioObjectType ,  ChannelConfiguration ,  SamplingFrequencyIndex} , 
         AacError , 
     } , 
-    bytes::Buf , 
-    std::{convert::TryFrom ,  io::Cursor} , 
+    std::convert::TryFrom , 
 };
 
+const OBJECT_TYPE_ESCAPE: u8 = 31;
+const SYNC_EXTENSION_SBR: u32 = 0x2B7;
+const SYNC_EXTENSION_PS: u32 = 0x548;
+
 // Bits | Description
 // ---- | -----------
-// 5    | Audio object type
+// 5    | Audio object type ,  6 more if 31
 // 4    | Sampling frequency index
+// 24   | Sampling frequency if the index is 15
 // 4    | Channel configuration
-// AOT specific section
+// Hierarchical SBR if the object type is 5 or 29
+// 4    | Extension sampling frequency index
+// 24   | Extension sampling frequency if the index is 15
+// 5    | Audio object type of the core ,  6 more if 31
+// GA specific config
 // 1    | Frame length flag
 // 1    | Depends on core coder
+// 14   | Core coder delay if depending on core coder
 // 1    | Extension flag
-///
-#[derive(Debug ,  Clone)]
+// PCE  | Program config element if the channel configuration is 0
+// 3    | Layer number of the scalable object types
+// 3    | Resilience flags if extended and error resilient
+// 1    | Extension flag 3 if extended
+// 2    | Error protection config if error resilient
+// Backward compatible SBR if 16 bits are left
+// 11   | Sync extension type ,  constant 0x2B7
+// 5    | Extension audio object type ,  constant 5
+// 1    | SBR present flag
+// 4    | Extension sampling frequency index if SBR is present
+// 24   | Extension sampling frequency if the index is 15
+// 11   | Sync extension type ,  constant 0x548 ,  if 12 bits are left
+// 1    | PS present flag
+//
+// https://wiki.multimedia.cx/index.php/MPEG-4_Audio#Audio_Specific_Config
+#[derive(Debug ,  Clone ,  PartialEq ,  Eq)]
 pub struct AudioSpecificConfiguration {
+    /// The object type of the core ,  e.g. AAC LC for HE-AAC.
     pub object_type: AudioObjectType , 
     pub sampling_frequency_index: SamplingFrequencyIndex , 
     pub sampling_frequency: Option<u32> , 
     pub channel_configuration: ChannelConfiguration , 
     pub frame_length_flag: bool , 
     pub depends_on_core_coder: bool , 
+    pub core_coder_delay: u16 , 
     pub extension_flag: bool , 
+    /// The channel layout if the channel configuration is 0.
+    pub program_config: Option<Box<ProgramConfigElement>> , 
+    pub layer_number: u8 , 
+    pub resilience_flags: u8 , 
+    pub extension_flag3: bool , 
+    pub error_protection_config: u8 , 
+    pub sbr: SbrSignalling , 
+}
+
+/// How a configuration tells HE-AAC.
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub enum SbrSignalling {
+    /// Not told ,  decoders find SBR data in the frames if there is any.
+    Implicit , 
+    /// Told absent after the core configuration.
+    Absent , 
+    /// The object type of SBR or PS leads the configuration.
+    Hierarchical(SbrConfiguration) , 
+    /// Told after the core configuration ,  for plain AAC decoders to skip.
+    BackwardCompatible(SbrConfiguration) , 
+}
+
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub struct SbrConfiguration {
+    /// The output sampling frequency index.
+    pub sampling_frequency_index: SamplingFrequencyIndex , 
+    pub sampling_frequency: u32 , 
+    /// Parametric stereo ,  i.e. HE-AAC v2.
+    pub ps: bool , 
+}
+
+impl AudioSpecificConfiguration {
+    /// A configuration of plain AAC without any extension.
+    pub fn new(
+        object_type: AudioObjectType , 
+        sampling_frequency_index: SamplingFrequencyIndex , 
+        channel_configuration: ChannelConfiguration , 
+    ) -> Self {
+        Self {
+            object_type , 
+            sampling_frequency_index , 
+            sampling_frequency: sampling_frequency_index.freq() , 
+            channel_configuration , 
+            frame_length_flag: false , 
+            depends_on_core_coder: false , 
+            core_coder_delay: 0 , 
+            extension_flag: false , 
+            program_config: None , 
+            layer_number: 0 , 
+            resilience_flags: 0 , 
+            extension_flag3: false , 
+            error_protection_config: 0 , 
+            sbr: SbrSignalling::Implicit , 
+        }
+    }
+
+    /// The sampling frequency of the core ,  0 if unknown.
+    pub fn core_sampling_frequency(&self) -> u32 {
+        self.sampling_frequency
+            .or_else(|| self.sampling_frequency_index.freq())
+            .unwrap_or(0)
+    }
+
+    /// The sampling frequency decoders output ,  SBR doubles the core one as a
+    /// rule. Implicitly told SBR is only known to decoders.
+    pub fn output_sampling_frequency(&self) -> u32 {
+        match self.sbr {
+            SbrSignalling::Hierarchical(sbr) | SbrSignalling::BackwardCompatible(sbr) => {
+                sbr.sampling_frequency
+            }
+            _ => self.core_sampling_frequency() , 
+        }
+    }
+
+    /// The samples of a frame at the output sampling frequency.
+    pub fn frame_samples(&self) -> u32 {
+        let samples = match (self.object_type ,  self.frame_length_flag) {
+            (AudioObjectType::ErAacLowDelay ,  false) => 512 , 
+            (AudioObjectType::ErAacLowDelay ,  true) => 480 , 
+            (_ ,  false) => 1024 , 
+            (_ ,  true) => 960 , 
+        };
+        match self.core_sampling_frequency() {
+            0 => samples , 
+            freq => {
+                (samples as u64 * self.output_sampling_frequency() as u64 / freq as u64) as u32
+            }
+        }
+    }
+
+    /// The channels decoders output ,  0 if unknown.
+    pub fn channels(&self) -> u8 {
+        let channels = match u8::from(self.channel_configuration) {
+            0 => self
+                .program_config
+                .as_ref()
+                .map_or(0 ,  |pce| pce.channels()) , 
+            7 => 8 , 
+            n => n , 
+        };
+        match self.sbr {
+            // parametric stereo makes two channels of one
+            SbrSignalling::Hierarchical(sbr) | SbrSignalling::BackwardCompatible(sbr)
+                if sbr.ps && channels == 1 =>
+            {
+                2
+            }
+            _ => channels , 
+        }
+    }
+
+    fn read_ga_specific_config(&mut self ,  bits: &mut BitReader<'_>) -> Result<() ,  AacError> {
+        self.frame_length_flag = bits.get_bit()?;
+        self.depends_on_core_coder = bits.get_bit()?;
+        if self.depends_on_core_coder {
+            self.core_coder_delay = bits.get(14)? as u16;
+        }
+        self.extension_flag = bits.get_bit()?;
+        if u8::from(self.channel_configuration) == 0 {
+            self.program_config = Some(Box::new(ProgramConfigElement::read(bits)?));
+        }
+        if self.is_scalable() {
+            self.layer_number = bits.get(3)? as u8;
+        }
+        if self.extension_flag {
+            if self.has_resilience_flags() {
+                self.resilience_flags = bits.get(3)? as u8;
+            }
+            self.extension_flag3 = bits.get_bit()?;
+        }
+        Ok(())
+    }
+
+    fn put_ga_specific_config(&self ,  bits: &mut BitWriter) {
+        bits.put_bit(self.frame_length_flag);
+        bits.put_bit(self.depends_on_core_coder);
+        if self.depends_on_core_coder {
+            bits.put(self.core_coder_delay.into() ,  14);
+        }
+        bits.put_bit(self.extension_flag);
+        if u8::from(self.channel_configuration) == 0 {
+            self.program_config
+                .clone()
+                .unwrap_or_default()
+                .put(bits);
+        }
+        if self.is_scalable() {
+            bits.put(self.layer_number.into() ,  3);
+        }
+        if self.extension_flag {
+            if self.has_resilience_flags() {
+                bits.put(self.resilience_flags.into() ,  3);
+            }
+            bits.put_bit(self.extension_flag3);
+        }
+    }
+
+    fn is_scalable(&self) -> bool {
+        matches!(
+            self.object_type , 
+            AudioObjectType::AacScalable | AudioObjectType::ErAacScalable
+        )
+    }
+
+    fn has_resilience_flags(&self) -> bool {
+        self.object_type.is_error_resilient() && self.object_type != AudioObjectType::ErTwinVq
+    }
 }
 
 impl TryFrom<&[u8]> for AudioSpecificConfiguration {
     type Error = AacError;
 
     fn try_from(val: &[u8]) -> Result<Self ,  Self::Error> {
-        if val.len() < 2 {
-            return Err(AacError::NotEnoughData("AAC audio specific config"));
+        let mut bits = BitReader::new(val ,  "AAC audio specific config");
+
+        let mut object_type = AudioObjectType::try_from(read_object_type(&mut bits)?)?;
+        let (sampling_frequency_index ,  sampling_frequency) = read_sampling_frequency(&mut bits)?;
+        let channel_configuration = ChannelConfiguration::try_from(bits.get(4)? as u8)?;
+
+        let mut sbr = SbrSignalling::Implicit;
+        if !object_type.is_general_audio() {
+            let (index ,  freq) = read_sampling_frequency(&mut bits)?;
+            sbr = SbrSignalling::Hierarchical(SbrConfiguration {
+                sampling_frequency_index: index , 
+                sampling_frequency: freq , 
+                ps: object_type == AudioObjectType::ParametricStereo , 
+            });
+            object_type = AudioObjectType::try_from(read_object_type(&mut bits)?)?;
+            if !object_type.is_general_audio() {
+                return Err(AacError::UnsupportedConfiguration("SBR core"));
+            }
+        }
+
+        let mut asc = Self::new(object_type ,  sampling_frequency_index ,  channel_configuration);
+        asc.sampling_frequency = Some(sampling_frequency);
+        asc.sbr = sbr;
+        asc.read_ga_specific_config(&mut bits)?;
+
+        if object_type.is_error_resilient() {
+            asc.error_protection_config = bits.get(2)? as u8;
+            if asc.error_protection_config > 1 {
+                return Err(AacError::UnsupportedConfiguration("error protection"));
+            }
+        }
+
+        if asc.sbr == SbrSignalling::Implicit
+            && bits.remaining() >= 16
+            && bits.get(11)? == SYNC_EXTENSION_SBR
+            && read_object_type(&mut bits)? == AudioObjectType::SpectralBandReplication as u8
+        {
+            asc.sbr = if bits.get_bit()? {
+                let (index ,  freq) = read_sampling_frequency(&mut bits)?;
+                let ps = bits.remaining() >= 12
+                    && bits.get(11)? == SYNC_EXTENSION_PS
+                    && bits.get_bit()?;
+                SbrSignalling::BackwardCompatible(SbrConfiguration {
+                    sampling_frequency_index: index , 
+                    sampling_frequency: freq , 
+                    ps , 
+                })
+            } else {
+                SbrSignalling::Absent
+            };
+        }
+
+        Ok(asc)
+    }
+}
+
+impl From<&AudioSpecificConfiguration> for Vec<u8> {
+    fn from(val: &AudioSpecificConfiguration) -> Self {
+        let mut bits = BitWriter::new();
+
+        let core_frequency = val.core_sampling_frequency();
+        if let SbrSignalling::Hierarchical(sbr) = val.sbr {
+            let object_type = if sbr.ps {
+                AudioObjectType::ParametricStereo
+            } else {
+                AudioObjectType::SpectralBandReplication
+            };
+            put_object_type(&mut bits ,  object_type);
+            put_sampling_frequency(&mut bits ,  val.sampling_frequency_index ,  core_frequency);
+            bits.put(u8::from(val.channel_configuration).into() ,  4);
+            put_sampling_frequency(&mut bits ,  sbr.sampling_frequency_index ,  sbr.sampling_frequency);
+            put_object_type(&mut bits ,  val.object_type);
+        } else {
+            put_object_type(&mut bits ,  val.object_type);
+            put_sampling_frequency(&mut bits ,  val.sampling_frequency_index ,  core_frequency);
+            bits.put(u8::from(val.channel_configuration).into() ,  4);
         }
 
-        let mut buf = Cursor::new(val);
+        val.put_ga_specific_config(&mut bits);
+        if val.object_type.is_error_resilient() {
+            bits.put(val.error_protection_config.into() ,  2);
+        }
+
+        match val.sbr {
+            SbrSignalling::BackwardCompatible(sbr) => {
+                bits.put(SYNC_EXTENSION_SBR ,  11);
+                put_object_type(&mut bits ,  AudioObjectType::SpectralBandReplication);
+                bits.put_bit(true);
+                put_sampling_frequency(&mut bits ,  sbr.sampling_frequency_index ,  sbr.sampling_frequency);
+                if sbr.ps {
+                    bits.put(SYNC_EXTENSION_PS ,  11);
+                    bits.put_bit(true);
+                }
+            }
+            SbrSignalling::Absent => {
+                bits.put(SYNC_EXTENSION_SBR ,  11);
+                put_object_type(&mut bits ,  AudioObjectType::SpectralBandReplication);
+                bits.put_bit(false);
+            }
+            _ => {}
+        }
 
-        let header_a = buf.get_u8();
-        let header_b = buf.get_u8();
+        bits.into_bytes()
+    }
+}
 
-        let object_type = AudioObjectType::try_from((header_a & 0xF8) >> 3)?;
+fn read_object_type(bits: &mut BitReader<'_>) -> Result<u8 ,  AacError> {
+    let object_type = bits.get(5)? as u8;
+    if object_type == OBJECT_TYPE_ESCAPE {
+        return Ok(32 + bits.get(6)? as u8);
+    }
+    Ok(object_type)
+}
 
-        let sf_idx = ((header_a & 0x07) << 1) | (header_b >> 7);
-        let sampling_frequency_index = SamplingFrequencyIndex::try_from(sf_idx)?;
+fn put_object_type(bits: &mut BitWriter ,  object_type: AudioObjectType) {
+    let object_type = object_type as u8;
+    if object_type < OBJECT_TYPE_ESCAPE {
+        bits.put(object_type.into() ,  5);
+    } else {
+        bits.put(OBJECT_TYPE_ESCAPE.into() ,  5);
+        bits.put((object_type - 32).into() ,  6);
+    }
+}
 
-        let channel_configuration = ChannelConfiguration::try_from((header_b >> 3) & 0x0F)?;
-        let frame_length_flag = (header_b & 0x04) == 0x04;
-        let depends_on_core_coder = (header_b & 0x02) == 0x02;
-        let extension_flag = (header_b & 0x01) == 0x01;
+fn read_sampling_frequency(
+    bits: &mut BitReader<'_> , 
+) -> Result<(SamplingFrequencyIndex ,  u32) ,  AacError> {
+    let index = SamplingFrequencyIndex::try_from(bits.get(4)? as u8)?;
+    let freq = match index.freq() {
+        Some(freq) => freq , 
+        None => bits.get(24)? , 
+    };
+    Ok((index ,  freq))
+}
+
+fn put_sampling_frequency(bits: &mut BitWriter ,  index: SamplingFrequencyIndex ,  freq: u32) {
+    bits.put(u8::from(index).into() ,  4);
+    if index.freq().is_none() {
+        bits.put(freq ,  24);
+    }
+}
+
+// Bits | Description
+// ---- | -----------
+// 4    | Element instance tag
+// 2    | Object type
+// 4    | Sampling frequency index
+// 4    | Front channel elements
+// 4    | Side channel elements
+// 4    | Back channel elements
+// 2    | LFE channel elements
+// 3    | Associated data elements
+// 4    | Coupling channel elements
+// 1    | Mono mixdown present ,  4 bits of element number if set
+// 1    | Stereo mixdown present ,  4 bits of element number if set
+// 1    | Matrix mixdown present ,  2 bits of index and 1 of pseudo surround if set
+// 5    | Front ,  side and back elements each ,  CPE flag and tag
+// 4    | LFE elements and associated data elements each ,  tag
+// 5    | Coupling channel elements each ,  independent switching flag and tag
+// Byte alignment
+// 8    | Comment length
+// Comment
+/// The channel layout of a configuration without channel configuration.
+#[derive(Debug ,  Clone ,  Default ,  PartialEq ,  Eq)]
+pub struct ProgramConfigElement {
+    pub element_instance_tag: u8 , 
+    pub object_type: u8 , 
+    pub sampling_frequency_index: u8 , 
+    pub front_channel_elements: Vec<ChannelElement> , 
+    pub side_channel_elements: Vec<ChannelElement> , 
+    pub back_channel_elements: Vec<ChannelElement> , 
+    pub lfe_channel_elements: Vec<u8> , 
+    pub assoc_data_elements: Vec<u8> , 
+    pub coupling_channel_elements: Vec<CouplingChannelElement> , 
+    pub mono_mixdown_element: Option<u8> , 
+    pub stereo_mixdown_element: Option<u8> , 
+    pub matrix_mixdown: Option<MatrixMixdown> , 
+    pub comment: Vec<u8> , 
+}
+
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub struct ChannelElement {
+    /// A channel pair rather than a single channel.
+    pub is_cpe: bool , 
+    pub tag: u8 , 
+}
+
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub struct CouplingChannelElement {
+    pub is_independently_switched: bool , 
+    pub tag: u8 , 
+}
+
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub struct MatrixMixdown {
+    pub index: u8 , 
+    pub pseudo_surround: bool , 
+}
+
+impl ProgramConfigElement {
+    pub fn channels(&self) -> u8 {
+        let channels: usize = self
+            .front_channel_elements
+            .iter()
+            .chain(&self.side_channel_elements)
+            .chain(&self.back_channel_elements)
+            .map(|element| if element.is_cpe { 2 } else { 1 })
+            .sum();
+        (channels + self.lfe_channel_elements.len()) as u8
+    }
+
+    fn read(bits: &mut BitReader<'_>) -> Result<Self ,  AacError> {
+        let element_instance_tag = bits.get(4)? as u8;
+        let object_type = bits.get(2)? as u8;
+        let sampling_frequency_index = bits.get(4)? as u8;
+        let front = bits.get(4)?;
+        let side = bits.get(4)?;
+        let back = bits.get(4)?;
+        let lfe = bits.get(2)?;
+        let assoc_data = bits.get(3)?;
+        let coupling = bits.get(4)?;
+
+        let mono_mixdown_element = read_element_number(bits)?;
+        let stereo_mixdown_element = read_element_number(bits)?;
+        let matrix_mixdown = if bits.get_bit()? {
+            Some(MatrixMixdown {
+                index: bits.get(2)? as u8 , 
+                pseudo_surround: bits.get_bit()? , 
+            })
+        } else {
+            None
+        };
+
+        let front_channel_elements = read_channel_elements(bits ,  front)?;
+        let side_channel_elements = read_channel_elements(bits ,  side)?;
+        let back_channel_elements = read_channel_elements(bits ,  back)?;
+        let lfe_channel_elements = read_tags(bits ,  lfe)?;
+        let assoc_data_elements = read_tags(bits ,  assoc_data)?;
+        let mut coupling_channel_elements = Vec::with_capacity(coupling as usize);
+        for _ in 0..coupling {
+            coupling_channel_elements.push(CouplingChannelElement {
+                is_independently_switched: bits.get_bit()? , 
+                tag: bits.get(4)? as u8 , 
+            });
+        }
+
+        bits.byte_align();
+        let comment_len = bits.get(8)?;
+        let mut comment = Vec::with_capacity(comment_len as usize);
+        for _ in 0..comment_len {
+            comment.push(bits.get(8)? as u8);
+        }
 
         Ok(Self {
+            element_instance_tag , 
             object_type , 
             sampling_frequency_index , 
-            sampling_frequency: None , 
-            channel_configuration , 
-            frame_length_flag , 
-            depends_on_core_coder , 
-            extension_flag , 
+            front_channel_elements , 
+            side_channel_elements , 
+            back_channel_elements , 
+            lfe_channel_elements , 
+            assoc_data_elements , 
+            coupling_channel_elements , 
+            mono_mixdown_element , 
+            stereo_mixdown_element , 
+            matrix_mixdown , 
+            comment , 
         })
     }
+
+    fn put(&self ,  bits: &mut BitWriter) {
+        bits.put(self.element_instance_tag.into() ,  4);
+        bits.put(self.object_type.into() ,  2);
+        bits.put(self.sampling_frequency_index.into() ,  4);
+        bits.put(self.front_channel_elements.len() as u32 ,  4);
+        bits.put(self.side_channel_elements.len() as u32 ,  4);
+        bits.put(self.back_channel_elements.len() as u32 ,  4);
+        bits.put(self.lfe_channel_elements.len() as u32 ,  2);
+        bits.put(self.assoc_data_elements.len() as u32 ,  3);
+        bits.put(self.coupling_channel_elements.len() as u32 ,  4);
+
+        for element in &[self.mono_mixdown_element ,  self.stereo_mixdown_element] {
+            bits.put_bit(element.is_some());
+            if let Some(number) = element {
+                bits.put((*number).into() ,  4);
+            }
+        }
+        bits.put_bit(self.matrix_mixdown.is_some());
+        if let Some(mixdown) = self.matrix_mixdown {
+            bits.put(mixdown.index.into() ,  2);
+            bits.put_bit(mixdown.pseudo_surround);
+        }
+
+        for element in self
+            .front_channel_elements
+            .iter()
+            .chain(&self.side_channel_elements)
+            .chain(&self.back_channel_elements)
+        {
+            bits.put_bit(element.is_cpe);
+            bits.put(element.tag.into() ,  4);
+        }
+        for tag in self.lfe_channel_elements.iter().chain(&self.assoc_data_elements) {
+            bits.put((*tag).into() ,  4);
+        }
+        for element in &self.coupling_channel_elements {
+            bits.put_bit(element.is_independently_switched);
+            bits.put(element.tag.into() ,  4);
+        }
+
+        bits.byte_align();
+        bits.put(self.comment.len() as u32 ,  8);
+        for byte in &self.comment {
+            bits.put((*byte).into() ,  8);
+        }
+    }
+}
+
+fn read_element_number(bits: &mut BitReader<'_>) -> Result<Option<u8> ,  AacError> {
+    Ok(if bits.get_bit()? {
+        Some(bits.get(4)? as u8)
+    } else {
+        None
+    })
+}
+
+fn read_channel_elements(
+    bits: &mut BitReader<'_> , 
+    count: u32 , 
+) -> Result<Vec<ChannelElement> ,  AacError> {
+    let mut elements = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        elements.push(ChannelElement {
+            is_cpe: bits.get_bit()? , 
+            tag: bits.get(4)? as u8 , 
+        });
+    }
+    Ok(elements)
+}
+
+fn read_tags(bits: &mut BitReader<'_> ,  count: u32) -> Result<Vec<u8> ,  AacError> {
+    (0..count).map(|_| Ok(bits.get(4)? as u8)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sbr(index: u8 ,  ps: bool) -> SbrConfiguration {
+        let sampling_frequency_index = SamplingFrequencyIndex::try_from(index).unwrap();
+        SbrConfiguration {
+            sampling_frequency_index , 
+            sampling_frequency: sampling_frequency_index.freq().unwrap() , 
+            ps , 
+        }
+    }
+
+    fn assert_written(bytes: &[u8]) -> AudioSpecificConfiguration {
+        let asc = AudioSpecificConfiguration::try_from(bytes).unwrap();
+        assert_eq!(Vec::from(&asc) ,  bytes);
+        asc
+    }
+
+    #[test]
+    fn test_aac_lc() {
+        let asc = assert_written(&[0x12 ,  0x10]);
+        assert_eq!(asc.object_type ,  AudioObjectType::AacLowComplexity);
+        assert_eq!(asc.sampling_frequency ,  Some(44100));
+        assert_eq!(asc.sbr ,  SbrSignalling::Implicit);
+        assert_eq!(asc.output_sampling_frequency() ,  44100);
+        assert_eq!(asc.frame_samples() ,  1024);
+        assert_eq!(asc.channels() ,  2);
+    }
+
+    #[test]
+    fn test_he_aac() {
+        // HE-AAC v1 told in front of the core
+        let asc = assert_written(&[0x2B ,  0x92 ,  0x08 ,  0x00]);
+        assert_eq!(asc.object_type ,  AudioObjectType::AacLowComplexity);
+        assert_eq!(asc.core_sampling_frequency() ,  22050);
+        assert_eq!(asc.sbr ,  SbrSignalling::Hierarchical(sbr(4 ,  false)));
+        assert_eq!(asc.output_sampling_frequency() ,  44100);
+        assert_eq!(asc.frame_samples() ,  2048);
+        assert_eq!(asc.channels() ,  2);
+
+        // HE-AAC v2 of a mono core
+        let asc = assert_written(&[0xEB ,  0x8A ,  0x08 ,  0x00]);
+        assert_eq!(asc.sbr ,  SbrSignalling::Hierarchical(sbr(4 ,  true)));
+        assert_eq!(asc.channels() ,  2);
+
+        // HE-AAC v1 told after the core
+        let asc = assert_written(&[0x13 ,  0x10 ,  0x56 ,  0xE5 ,  0x98]);
+        assert_eq!(asc.core_sampling_frequency() ,  24000);
+        assert_eq!(asc.sbr ,  SbrSignalling::BackwardCompatible(sbr(3 ,  false)));
+        assert_eq!(asc.output_sampling_frequency() ,  48000);
+        assert_eq!(asc.frame_samples() ,  2048);
+
+        let mut asc = AudioSpecificConfiguration::new(
+            AudioObjectType::AacLowComplexity , 
+            SamplingFrequencyIndex::try_from(8).unwrap() , 
+            ChannelConfiguration::try_from(1).unwrap() , 
+        );
+        for signalling in &[
+            SbrSignalling::BackwardCompatible(sbr(5 ,  true)) , 
+            SbrSignalling::Absent , 
+        ] {
+            asc.sbr = *signalling;
+            let written = Vec::from(&asc);
+            assert_eq!(AudioSpecificConfiguration::try_from(&written[..]).unwrap() ,  asc);
+        }
+    }
+
+    #[test]
+    fn test_explicit_sampling_frequency() {
+        let mut asc = AudioSpecificConfiguration::new(
+            AudioObjectType::AacLowComplexity , 
+            SamplingFrequencyIndex::try_from(SamplingFrequencyIndex::EXPLICIT).unwrap() , 
+            ChannelConfiguration::try_from(2).unwrap() , 
+        );
+        asc.sampling_frequency = Some(37800);
+        asc.frame_length_flag = true;
+
+        let written = Vec::from(&asc);
+        let read = AudioSpecificConfiguration::try_from(&written[..]).unwrap();
+        assert_eq!(read ,  asc);
+        assert_eq!(read.output_sampling_frequency() ,  37800);
+        assert_eq!(read.frame_samples() ,  960);
+    }
+
+    #[test]
+    fn test_program_config_element() {
+        let mut asc = AudioSpecificConfiguration::new(
+            AudioObjectType::AacLowComplexity , 
+            SamplingFrequencyIndex::try_from(3).unwrap() , 
+            ChannelConfiguration::try_from(0).unwrap() , 
+        );
+        asc.program_config = Some(Box::new(ProgramConfigElement {
+            object_type: 1 , 
+            sampling_frequency_index: 3 , 
+            front_channel_elements: vec![
+                ChannelElement {
+                    is_cpe: false , 
+                    tag: 0 , 
+                } , 
+                ChannelElement {
+                    is_cpe: true , 
+                    tag: 0 , 
+                } , 
+            ] , 
+            back_channel_elements: vec![ChannelElement {
+                is_cpe: true , 
+                tag: 1 , 
+            }] , 
+            lfe_channel_elements: vec![0] , 
+            matrix_mixdown: Some(MatrixMixdown {
+                index: 1 , 
+                pseudo_surround: true , 
+            }) , 
+            comment: b"echo".to_vec() , 
+            ..ProgramConfigElement::default()
+        }));
+
+        let written = Vec::from(&asc);
+        let read = AudioSpecificConfiguration::try_from(&written[..]).unwrap();
+        assert_eq!(read ,  asc);
+        assert_eq!(read.channels() ,  6);
+
+        assert!(AudioSpecificConfiguration::try_from(&written[..written.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_object_type() {
+        // the escaped object type 32 ,  MPEG-1 layer 1
+        assert!(AudioSpecificConfiguration::try_from(&[0xF8 ,  0x09 ,  0x10 ,  0x00][..]).is_err());
+        assert!(AudioSpecificConfiguration::try_from(&[0x12][..]).is_err());
+    }
 }
@@ -17,6 +17,15 @@ pub enum AacError {
     #[error("Reserved or unsupported channel configuration {0}")]
     UnsupportedChannelConfiguration(u8) , 
 
+    #[error("Unsupported audio specific config: {0}")]
+    UnsupportedConfiguration(&'static str) , 
+
+    #[error("Invalid ADTS header: {0}")]
+    InvalidAdtsHeader(&'static str) , 
+
+    #[error("CRC mismatch: expected {expected:#06x} but {actual:#06x}")]
+    CrcMismatch { expected: u16 ,  actual: u16 } , 
+
     #[error("Got forbidden sampling frequency index {0}")]
     ForbiddenSamplingFrequencyIndex(u8) , 
 }
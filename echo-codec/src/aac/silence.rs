@@ -0,0 +1,195 @@
+use super::{bits::BitWriter ,  config::AudioSpecificConfiguration ,  AacError};
+
+// syntactic elements of a raw data block
+const ID_SCE: u32 = 0;
+const ID_CPE: u32 = 1;
+const ID_LFE: u32 = 3;
+const ID_END: u32 = 7;
+
+// as encoders write silence ,  there's no spectral data to be scaled
+const SILENT_GLOBAL_GAIN: u32 = 140;
+
+/// A raw data block of silence for the configuration ,  e.g. the payload of an
+/// ADTS frame.
+///
+/// HE-AAC gets a block of its AAC core without SBR data ,  decoders play it as
+/// silence at the output rate too. The channel configuration 0 takes the
+/// elements of its program config element.
+pub fn silent_raw_data_block(asc: &AudioSpecificConfiguration) -> Result<Vec<u8> ,  AacError> {
+    let elements = channel_elements(asc)?;
+
+    let mut bits = BitWriter::new();
+    for (id ,  tag) in elements {
+        bits.put(id ,  3);
+        bits.put(tag ,  4);
+        if id == ID_CPE {
+            // common window
+            bits.put_bit(true);
+            put_ics_info(&mut bits);
+            // ms mask present
+            bits.put(0 ,  2);
+            put_channel_stream(&mut bits ,  false);
+            put_channel_stream(&mut bits ,  false);
+        } else {
+            put_channel_stream(&mut bits ,  true);
+        }
+    }
+    bits.put(ID_END ,  3);
+
+    Ok(bits.into_bytes())
+}
+
+// the syntactic elements and instance tags of the channels in stream order
+fn channel_elements(asc: &AudioSpecificConfiguration) -> Result<Vec<(u32 ,  u32)> ,  AacError> {
+    let ids: &[u32] = match u8::from(asc.channel_configuration) {
+        0 => {
+            let pce = match asc.program_config {
+                Some(ref pce) => pce , 
+                None => return Err(AacError::UnsupportedChannelConfiguration(0)) , 
+            };
+            let channels = pce
+                .front_channel_elements
+                .iter()
+                .chain(&pce.side_channel_elements)
+                .chain(&pce.back_channel_elements)
+                .map(|element| {
+                    let id = if element.is_cpe { ID_CPE } else { ID_SCE };
+                    (id ,  u32::from(element.tag))
+                });
+            let lfe = pce
+                .lfe_channel_elements
+                .iter()
+                .map(|&tag| (ID_LFE ,  u32::from(tag)));
+            return Ok(channels.chain(lfe).collect());
+        }
+        1 => &[ID_SCE] , 
+        2 => &[ID_CPE] , 
+        3 => &[ID_SCE ,  ID_CPE] , 
+        4 => &[ID_SCE ,  ID_CPE ,  ID_SCE] , 
+        5 => &[ID_SCE ,  ID_CPE ,  ID_CPE] , 
+        6 => &[ID_SCE ,  ID_CPE ,  ID_CPE ,  ID_LFE] , 
+        7 => &[ID_SCE ,  ID_CPE ,  ID_CPE ,  ID_CPE ,  ID_LFE] , 
+        n => return Err(AacError::UnsupportedChannelConfiguration(n)) , 
+    };
+    // the elements of a kind are told apart by their instance tag
+    let mut tags = [0; 8];
+    Ok(ids
+        .iter()
+        .map(|&id| {
+            let tag = tags[id as usize];
+            tags[id as usize] += 1;
+            (id ,  tag)
+        })
+        .collect())
+}
+
+// a long window without scale factor bands
+fn put_ics_info(bits: &mut BitWriter) {
+    // reserved
+    bits.put_bit(false);
+    // only long sequence
+    bits.put(0 ,  2);
+    // window shape
+    bits.put_bit(true);
+    // max sfb
+    bits.put(0 ,  6);
+    // predictor or LTP data present
+    bits.put_bit(false);
+}
+
+fn put_channel_stream(bits: &mut BitWriter ,  ics_info: bool) {
+    bits.put(SILENT_GLOBAL_GAIN ,  8);
+    if ics_info {
+        put_ics_info(bits);
+    }
+    // pulse ,  TNS and gain control data present
+    bits.put(0 ,  3);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        crate::aac::{
+            bits::BitReader , 
+            common::{AudioObjectType ,  ChannelConfiguration ,  SamplingFrequencyIndex} , 
+            config::{ChannelElement ,  ProgramConfigElement} , 
+        } , 
+        std::convert::TryFrom , 
+    };
+
+    fn asc(channel_configuration: u8) -> AudioSpecificConfiguration {
+        AudioSpecificConfiguration::new(
+            AudioObjectType::AacLowComplexity , 
+            SamplingFrequencyIndex::try_from(3).unwrap() , 
+            ChannelConfiguration::try_from(channel_configuration).unwrap() , 
+        )
+    }
+
+    #[test]
+    fn test_silent_raw_data_block() {
+        // as encoders make them
+        assert_eq!(
+            silent_raw_data_block(&asc(1)).unwrap() , 
+            vec![0x01 ,  0x18 ,  0x20 ,  0x07]
+        );
+        assert_eq!(
+            silent_raw_data_block(&asc(2)).unwrap() , 
+            vec![0x21 ,  0x10 ,  0x04 ,  0x60 ,  0x8c ,  0x1c]
+        );
+
+        // SCE ,  CPE ,  CPE and LFE of 29 ,  43 ,  43 and 29 bits ,  then END
+        let block = silent_raw_data_block(&asc(6)).unwrap();
+        assert_eq!(block[0] >> 5 ,  0);
+        assert_eq!(block.len() ,  19);
+
+        assert!(silent_raw_data_block(&asc(0)).is_err());
+    }
+
+    #[test]
+    fn test_program_config_element() {
+        // the layout of the channel configuration 6 ,  with other tags
+        let mut pce_asc = asc(0);
+        pce_asc.program_config = Some(Box::new(ProgramConfigElement {
+            front_channel_elements: vec![
+                ChannelElement {
+                    is_cpe: false , 
+                    tag: 1 , 
+                } , 
+                ChannelElement {
+                    is_cpe: true , 
+                    tag: 2 , 
+                } , 
+            ] , 
+            back_channel_elements: vec![ChannelElement {
+                is_cpe: true , 
+                tag: 3 , 
+            }] , 
+            lfe_channel_elements: vec![4] , 
+            ..ProgramConfigElement::default()
+        }));
+        let block = silent_raw_data_block(&pce_asc).unwrap();
+        assert_eq!(block.len() ,  19);
+
+        // the id and tag of each element ,  skipping its 29 or 43 bits
+        let mut bits = BitReader::new(&block ,  "raw data block");
+        let mut elements = Vec::new();
+        loop {
+            let id = bits.get(3).unwrap();
+            if id == ID_END {
+                break;
+            }
+            elements.push((id ,  bits.get(4).unwrap()));
+            let mut rest = if id == ID_CPE { 36 } else { 22 };
+            while rest > 0 {
+                let count = rest.min(16);
+                bits.get(count).unwrap();
+                rest -= count;
+            }
+        }
+        assert_eq!(
+            elements , 
+            vec![(ID_SCE ,  1) ,  (ID_CPE ,  2) ,  (ID_CPE ,  3) ,  (ID_LFE ,  4)]
+        );
+    }
+}
@@ -1,4 +1,9 @@
 mod error;
+pub mod reader;
 pub mod transport_stream;
 
-pub use self::{error::TsError ,  transport_stream::TransportStream};
+pub use self::{
+    error::TsError , 
+    reader::{AudioPes ,  TsReader} , 
+    transport_stream::TransportStream , 
+};
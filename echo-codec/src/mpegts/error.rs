@@ -17,6 +17,12 @@ pub enum TsError {
     #[error("Packet payload exceeded packet limit")]
     PayloadTooBig , 
 
+    #[error("Lost sync ,  skipped {0} bytes")]
+    LostSync(usize) , 
+
+    #[error("Packet of ID {0} is malformed")]
+    InvalidPacket(u16) , 
+
     #[error("Clock reference value of {0} exceeds maximum")]
     ClockValueOutOfRange(u64) , 
 }
@@ -0,0 +1,281 @@
+use {super::TsError ,  std::collections::VecDeque};
+
+const PACKET_SIZE: usize = 188;
+const SYNC_BYTE: u8 = 0x47;
+const PAT_PID: u16 = 0;
+const STREAM_TYPE_ADTS_AAC: u8 = 0x0f;
+const PTS_MASK: u64 = (1 << 33) - 1;
+
+/// An AAC PES packet ,  the ADTS frames it carries and their presentation time.
+#[derive(Debug ,  Clone ,  PartialEq)]
+pub struct AudioPes {
+    /// 90kHz ,  continuous across the 33 bit wrap around
+    pub pts: Option<u64> , 
+    pub data: Vec<u8> , 
+}
+
+struct PartialPes {
+    pts: Option<u64> , 
+    len: Option<usize> , 
+    data: Vec<u8> , 
+}
+
+/// Reads the AAC elementary stream of a transport stream which arrives in
+/// arbitrary chunks ,  e.g. SRT payloads.
+///
+/// Only the first AAC stream of the first program is read ,  every other
+/// stream is skipped.
+#[derive(Default)]
+pub struct TsReader {
+    buf: Vec<u8> , 
+    pmt_pid: Option<u16> , 
+    audio_pid: Option<u16> , 
+    pes: Option<PartialPes> , 
+    // (the last PTS as sent ,  as returned)
+    last_pts: Option<(u64 ,  u64)> , 
+    ready: VecDeque<AudioPes> , 
+}
+
+impl TsReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self ,  input: &[u8]) {
+        self.buf.extend_from_slice(input);
+    }
+
+    /// Returns the next complete PES packet. An error skips the packet ,  the
+    /// reader can be used again.
+    pub fn read_pes(&mut self) -> Option<Result<AudioPes ,  TsError>> {
+        loop {
+            if let Some(pes) = self.ready.pop_front() {
+                return Some(Ok(pes));
+            }
+            if self.buf.len() < PACKET_SIZE {
+                return None;
+            }
+            if self.buf[0] != SYNC_BYTE {
+                let skipped = self.resync();
+                return Some(Err(TsError::LostSync(skipped)));
+            }
+
+            let packet: Vec<u8> = self.buf.drain(..PACKET_SIZE).collect();
+            if let Err(err) = self.read_packet(&packet) {
+                return Some(Err(err));
+            }
+        }
+    }
+
+    // drops the bytes before the next sync byte
+    fn resync(&mut self) -> usize {
+        let skipped = self
+            .buf
+            .iter()
+            .position(|b| *b == SYNC_BYTE)
+            .unwrap_or(self.buf.len());
+        self.buf.drain(..skipped);
+        skipped
+    }
+
+    fn read_packet(&mut self ,  packet: &[u8]) -> Result<() ,  TsError> {
+        let payload_start = packet[1] & 0x40 != 0;
+        let pid = u16::from(packet[1] & 0x1f) << 8 | u16::from(packet[2]);
+        let adaptation_field = packet[3] & 0x20 != 0;
+        let has_payload = packet[3] & 0x10 != 0;
+
+        if !has_payload {
+            return Ok(());
+        }
+        let offset = if adaptation_field {
+            5 + packet[4] as usize
+        } else {
+            4
+        };
+        if offset > PACKET_SIZE {
+            return Err(TsError::InvalidPacket(pid));
+        }
+        let payload = &packet[offset..];
+
+        if pid == PAT_PID {
+            if payload_start {
+                self.read_pat(payload).ok_or(TsError::InvalidPacket(pid))?;
+            }
+        } else if Some(pid) == self.pmt_pid {
+            if payload_start {
+                self.read_pmt(payload).ok_or(TsError::InvalidPacket(pid))?;
+            }
+        } else if Some(pid) == self.audio_pid {
+            if payload_start {
+                self.flush();
+                self.pes = Some(read_pes_header(payload).ok_or(TsError::InvalidPacket(pid))?);
+            } else if let Some(ref mut pes) = self.pes {
+                pes.data.extend_from_slice(payload);
+            } else {
+                // joined in the middle of a PES packet
+                return Ok(());
+            }
+            let complete = match self.pes {
+                Some(PartialPes {
+                    len: Some(len) , 
+                    ref data , 
+                    ..
+                }) => data.len() >= len , 
+                _ => false , 
+            };
+            if complete {
+                self.flush();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_pat(&mut self ,  payload: &[u8]) -> Option<()> {
+        let section = psi_section(payload)?;
+        // the first program which is not the network information
+        for program in section.chunks_exact(4) {
+            let program_num = u16::from(program[0]) << 8 | u16::from(program[1]);
+            if program_num != 0 {
+                let pid = u16::from(program[2] & 0x1f) << 8 | u16::from(program[3]);
+                if self.pmt_pid != Some(pid) {
+                    self.pmt_pid = Some(pid);
+                    self.audio_pid = None;
+                }
+                break;
+            }
+        }
+        Some(())
+    }
+
+    fn read_pmt(&mut self ,  payload: &[u8]) -> Option<()> {
+        let section = psi_section(payload)?;
+        let info_len = (usize::from(*section.get(2)? & 0x0f) << 8) | usize::from(*section.get(3)?);
+        let mut streams = section.get(4 + info_len..)?;
+        while streams.len() >= 5 {
+            let stream_type = streams[0];
+            let pid = u16::from(streams[1] & 0x1f) << 8 | u16::from(streams[2]);
+            let es_info_len = (usize::from(streams[3] & 0x0f) << 8) | usize::from(streams[4]);
+            if stream_type == STREAM_TYPE_ADTS_AAC {
+                if self.audio_pid != Some(pid) {
+                    self.audio_pid = Some(pid);
+                    self.pes = None;
+                }
+                break;
+            }
+            streams = streams.get(5 + es_info_len..)?;
+        }
+        Some(())
+    }
+
+    fn flush(&mut self) {
+        if let Some(mut pes) = self.pes.take() {
+            if let Some(len) = pes.len {
+                pes.data.truncate(len);
+            }
+            let pts = pes.pts.map(|pts| self.unwrap_pts(pts));
+            self.ready.push_back(AudioPes {
+                pts , 
+                data: pes.data , 
+            });
+        }
+    }
+
+    fn unwrap_pts(&mut self ,  pts: u64) -> u64 {
+        let unwrapped = match self.last_pts {
+            Some((last ,  last_unwrapped)) => {
+                let forward = pts.wrapping_sub(last) & PTS_MASK;
+                if forward <= PTS_MASK / 2 {
+                    last_unwrapped + forward
+                } else {
+                    last_unwrapped.saturating_sub(last.wrapping_sub(pts) & PTS_MASK)
+                }
+            }
+            None => pts , 
+        };
+        self.last_pts = Some((pts ,  unwrapped));
+        unwrapped
+    }
+}
+
+// the table data of a PAT or PMT section without the CRC
+fn psi_section(payload: &[u8]) -> Option<&[u8]> {
+    let pointer = *payload.first()? as usize;
+    let section = payload.get(1 + pointer..)?;
+    let section_len = (usize::from(*section.get(1)? & 0x0f) << 8) | usize::from(*section.get(2)?);
+    // table id ,  length ,  id ,  version ,  section numbers ... CRC
+    section.get(8..3 + section_len.checked_sub(4)?)
+}
+
+fn read_pes_header(payload: &[u8]) -> Option<PartialPes> {
+    if payload.get(0..3)? != [0 ,  0 ,  1] {
+        return None;
+    }
+    let packet_len = usize::from(*payload.get(4)?) << 8 | usize::from(*payload.get(5)?);
+    let flags = *payload.get(7)?;
+    let header_len = *payload.get(8)? as usize;
+    let data = payload.get(9 + header_len..)?;
+
+    let pts = if flags & 0x80 != 0 {
+        let pts = payload.get(9..14)?;
+        Some(
+            (u64::from(pts[0] & 0x0e) << 29)
+                | (u64::from(pts[1]) << 22)
+                | (u64::from(pts[2] & 0xfe) << 14)
+                | (u64::from(pts[3]) << 7)
+                | (u64::from(pts[4]) >> 1) , 
+        )
+    } else {
+        None
+    };
+
+    Some(PartialPes {
+        pts , 
+        // 0 is unbounded ,  the packet ends with the next one
+        len: packet_len
+            .checked_sub(3 + header_len)
+            .filter(|_| packet_len > 0) , 
+        data: data.to_vec() , 
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::* ,  crate::mpegts::TransportStream ,  echo_types::Timestamp};
+
+    #[test]
+    fn test_read_written_stream() {
+        let frames: Vec<Vec<u8>> = (0..3u8).map(|n| vec![n; 300]).collect();
+        let mut ts = TransportStream::new();
+        for (i ,  frame) in frames.iter().enumerate() {
+            let timestamp = Timestamp::new(1024 * i as u64 ,  48_000);
+            ts.push_audio(timestamp ,  i == 0 ,  frame.clone()).unwrap();
+        }
+        let mut stream = Vec::new();
+        ts.write(&mut stream).unwrap();
+
+        let mut reader = TsReader::new();
+        // in chunks which do not line up with the packets
+        for chunk in stream.chunks(100) {
+            reader.push(chunk);
+        }
+        let mut read = Vec::new();
+        while let Some(pes) = reader.read_pes() {
+            read.push(pes.unwrap());
+        }
+
+        assert_eq!(read.len() ,  3);
+        for (i ,  pes) in read.iter().enumerate() {
+            assert_eq!(pes.pts ,  Some(1920 * i as u64));
+            assert_eq!(pes.data ,  frames[i]);
+        }
+    }
+
+    #[test]
+    fn test_unwrap_pts() {
+        let mut reader = TsReader::new();
+        assert_eq!(reader.unwrap_pts(PTS_MASK - 10) ,  PTS_MASK - 10);
+        assert_eq!(reader.unwrap_pts(20) ,  PTS_MASK + 21);
+        assert_eq!(reader.unwrap_pts(10) ,  PTS_MASK + 11);
+    }
+}
@@ -23,6 +23,10 @@ impl TransportStream {
         Self::default()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
     pub fn write<W>(&mut self ,  writer: &mut W) -> Result<() ,  TsError>
     where
         W: Write , 
@@ -8,6 +8,57 @@ use {
     } , 
 };
 
+#[derive(Clone ,  Copy ,  Debug ,  PartialEq ,  Eq ,  Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStoreKind {
+    Memory , 
+    File , 
+}
+
+/// What a full session queue does with a new sample.
+#[derive(Clone ,  Copy ,  Debug ,  PartialEq ,  Eq ,  Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    // keep the latest samples
+    DropOldest , 
+    // disconnect the publisher
+    Disconnect , 
+}
+
+/// What to do when a stream name is published while it is already live.
+#[derive(Clone ,  Copy ,  Debug ,  PartialEq ,  Eq ,  Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePublishPolicy {
+    // refuse the new publisher
+    Reject , 
+    // end the live session and start a new one
+    Evict , 
+    // the new publisher continues the live session
+    Handover , 
+    // keep the new publisher as a backup uplink of the live session
+    Backup , 
+}
+
+/// An address advertised in the transports of a publish response.
+#[derive(Clone ,  Copy ,  Debug ,  PartialEq ,  Eq)]
+pub enum TransportCandidate {
+    // the discovered public addresses
+    Public , 
+    // ECHO_SRT_PRIV_IP
+    Private , 
+    Addr(IpAddr) , 
+}
+
+impl TransportCandidate {
+    fn parse(candidate: &str) -> Option<Self> {
+        match candidate {
+            "public" => Some(Self::Public) , 
+            "private" => Some(Self::Private) , 
+            addr => addr.parse().ok().map(Self::Addr) , 
+        }
+    }
+}
+
 #[derive(Clone ,  Debug ,  Deserialize)]
 pub struct Config {
     pub log4rs_file: PathBuf , 
@@ -18,6 +69,13 @@ pub struct Config {
     pub echo_priv_key: String , 
     pub echo_srt_priv_ip: IpAddr , 
     pub echo_srt_pub_ip: Option<IpAddr> , 
+    // the SRT listeners bind to this address ,  "::" for dual-stack
+    #[serde(default = "default_echo_srt_bind_ip")]
+    pub echo_srt_bind_ip: IpAddr , 
+    pub echo_srt_transports: Option<String> , 
+    // whether the public addresses are looked up at startup
+    #[serde(default = "default_echo_public_ip_discovery")]
+    pub echo_public_ip_discovery: bool , 
     #[serde(default = "default_echo_srt_min_port")]
     pub echo_srt_min_port: u16 , 
     #[serde(default = "default_echo_srt_max_port")]
@@ -31,13 +89,28 @@ pub struct Config {
     pub echo_srt_read_timeout: Duration , 
     #[serde(default = "default_echo_srt_latency" ,  with = "duration_format")]
     pub echo_srt_latency: Duration , 
+    // one listener for every session ,  routed by the stream id of the caller
+    pub echo_srt_port: Option<u16> , 
+    // AES key length of the per-session passphrases ,  0 turns encryption off
+    #[serde(default = "default_echo_srt_key_length")]
+    pub echo_srt_key_length: u8 , 
+    // how often a live connection reports its quality ,  0 only when it closes
+    #[serde(default = "default_echo_srt_stats_interval" ,  with = "duration_format")]
+    pub echo_srt_stats_interval: Duration , 
 
     pub hls_enabled: bool , 
+    // whether sessions get HLS unless their props tell
+    #[serde(default = "default_output_selected")]
+    pub hls_default: bool , 
     pub hls_root_dir: PathBuf , 
     #[serde(default = "default_hls_target_duration" ,  with = "duration_format")]
     pub hls_target_duration: Duration , 
     #[serde(default = "default_hls_prerole_dir")]
     pub hls_prerole_dir: PathBuf , 
+    // played while a session is paused ,  "<app>/<reason code>" ,  "<app>" ,
+    // "default/<reason code>" or "default" ,  each with a live.m3u8
+    #[serde(default = "default_hls_slate_dir")]
+    pub hls_slate_dir: PathBuf , 
     pub hls_web_enabled: bool , 
     #[serde(default = "default_hls_web_addr")]
     pub hls_web_addr: SocketAddr , 
@@ -50,6 +123,8 @@ pub struct Config {
     pub rtmp_connection_timeout: Duration , 
 
     pub record_enabled: bool , 
+    #[serde(default = "default_output_selected")]
+    pub record_default: bool , 
     pub record_root_dir: PathBuf , 
     pub record_append: bool , 
 
@@ -62,12 +137,77 @@ pub struct Config {
     #[serde(default = "default_ttl_max_duration" ,  with = "duration_format")]
     pub ttl_max_duration: Duration , 
 
+    #[serde(default = "default_session_store")]
+    pub session_store: SessionStoreKind , 
+    #[serde(default = "default_session_store_path")]
+    pub session_store_path: PathBuf , 
+    #[serde(default = "default_duplicate_publish_policy")]
+    pub duplicate_publish_policy: DuplicatePublishPolicy , 
+    #[serde(default = "default_session_queue_size")]
+    pub session_queue_size: usize , 
+    #[serde(default = "default_session_overflow_policy")]
+    pub session_overflow_policy: OverflowPolicy , 
+    #[serde(default = "default_session_backlog" ,  with = "duration_format")]
+    pub session_backlog: Duration , 
+    // how long the live uplink may starve before a backup takes over
+    #[serde(default = "default_session_failover_timeout" ,  with = "duration_format")]
+    pub session_failover_timeout: Duration , 
+    // 0 for no limit
+    #[serde(default = "default_session_max_duration" ,  with = "duration_format")]
+    pub session_max_duration: Duration , 
+    pub session_expiry_warnings: Option<String> , 
+
+    #[serde(default)]
+    pub auth_enabled: bool , 
+    pub auth_url: Option<String> , 
+    #[serde(default = "default_auth_timeout" ,  with = "duration_format")]
+    pub auth_timeout: Duration , 
+    #[serde(default = "default_auth_cache_ttl" ,  with = "duration_format")]
+    pub auth_cache_ttl: Duration , 
+    #[serde(default)]
+    pub auth_fail_open: bool , 
+
+    #[serde(default)]
+    pub notify_enabled: bool , 
+    pub notify_urls: Option<String> , 
+    pub notify_secret: Option<String> , 
+    #[serde(default = "default_notify_queue_dir")]
+    pub notify_queue_dir: PathBuf , 
+    #[serde(default = "default_notify_timeout" ,  with = "duration_format")]
+    pub notify_timeout: Duration , 
+    #[serde(default = "default_notify_retry_min" ,  with = "duration_format")]
+    pub notify_retry_min: Duration , 
+    #[serde(default = "default_notify_retry_max" ,  with = "duration_format")]
+    pub notify_retry_max: Duration , 
+    #[serde(default = "default_notify_retry_limit")]
+    pub notify_retry_limit: u32 , 
+
+    #[serde(default = "default_shutdown_timeout" ,  with = "duration_format")]
+    pub shutdown_timeout: Duration , 
+    #[serde(default = "default_shutdown_flush_timeout" ,  with = "duration_format")]
+    pub shutdown_flush_timeout: Duration , 
+    #[serde(default)]
+    pub shutdown_notify_clients: bool , 
+
+    #[serde(default)]
+    pub admin_enabled: bool , 
+    #[serde(default = "default_admin_addr")]
+    pub admin_addr: SocketAddr , 
+    pub admin_token: Option<String> , 
 }
 
 fn default_echo_addr() -> SocketAddr {
     SocketAddr::from(([0 ,  0 ,  0 ,  0] ,  5021))
 }
 
+fn default_echo_srt_bind_ip() -> IpAddr {
+    IpAddr::from([0 ,  0 ,  0 ,  0])
+}
+
+fn default_echo_public_ip_discovery() -> bool {
+    true
+}
+
 fn default_echo_srt_connection_timeout() -> Duration {
     Duration::from_secs(1800)
 }
@@ -76,6 +216,10 @@ fn default_echo_srt_read_timeout() -> Duration {
     Duration::from_secs(10)
 }
 
+fn default_output_selected() -> bool {
+    true
+}
+
 fn default_hls_target_duration() -> Duration {
     Duration::from_secs(1)
 }
@@ -84,6 +228,10 @@ fn default_hls_prerole_dir() -> PathBuf {
     PathBuf::from("/var/echo/prerole")
 }
 
+fn default_hls_slate_dir() -> PathBuf {
+    PathBuf::from("/var/echo/slate")
+}
+
 fn default_hls_web_addr() -> SocketAddr {
     SocketAddr::from(([0 ,  0 ,  0 ,  0] ,  8080))
 }
@@ -108,6 +256,14 @@ fn default_echo_srt_latency() -> Duration {
     Duration::from_millis(50)
 }
 
+fn default_echo_srt_stats_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_echo_srt_key_length() -> u8 {
+    16
+}
+
 fn default_stat_web_addr() -> SocketAddr {
     SocketAddr::from(([0 ,  0 ,  0 ,  0] ,  8088))
 }
@@ -116,6 +272,78 @@ fn default_ttl_max_duration() -> Duration {
     Duration::from_secs(2 * 3600)
 }
 
+fn default_session_store() -> SessionStoreKind {
+    SessionStoreKind::Memory
+}
+
+fn default_session_store_path() -> PathBuf {
+    PathBuf::from("sessions.log")
+}
+
+fn default_duplicate_publish_policy() -> DuplicatePublishPolicy {
+    DuplicatePublishPolicy::Evict
+}
+
+fn default_session_queue_size() -> usize {
+    512
+}
+
+fn default_session_overflow_policy() -> OverflowPolicy {
+    OverflowPolicy::DropOldest
+}
+
+fn default_session_backlog() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_session_failover_timeout() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_session_max_duration() -> Duration {
+    Duration::from_secs(0)
+}
+
+fn default_auth_timeout() -> Duration {
+    Duration::from_secs(3)
+}
+
+fn default_auth_cache_ttl() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_notify_queue_dir() -> PathBuf {
+    PathBuf::from("notify")
+}
+
+fn default_notify_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_notify_retry_min() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_notify_retry_max() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_notify_retry_limit() -> u32 {
+    100
+}
+
+fn default_shutdown_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_shutdown_flush_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_admin_addr() -> SocketAddr {
+    SocketAddr::from(([127 ,  0 ,  0 ,  1] ,  8089))
+}
+
 mod duration_format {
     use {
         serde::{self ,  Deserialize ,  Deserializer} , 
@@ -142,17 +370,25 @@ impl Default for Config {
             echo_priv_key: String::from("0759230f81a40bef363d741f6b2ea274") , 
             echo_srt_priv_ip: IpAddr::from([127 ,  0 ,  0 ,  1]) , 
             echo_srt_pub_ip: None , 
+            echo_srt_bind_ip: default_echo_srt_bind_ip() , 
+            echo_srt_transports: None , 
+            echo_public_ip_discovery: default_echo_public_ip_discovery() , 
             echo_srt_min_port: default_echo_srt_min_port() , 
             echo_srt_max_port: default_echo_srt_max_port() , 
             echo_srt_connection_timeout: default_echo_srt_connection_timeout() , 
             echo_srt_read_timeout: default_echo_srt_read_timeout() , 
             echo_srt_latency: default_echo_srt_latency() , 
+            echo_srt_port: None , 
+            echo_srt_key_length: default_echo_srt_key_length() , 
+            echo_srt_stats_interval: default_echo_srt_stats_interval() , 
 
             // HLS
             hls_enabled: true , 
+            hls_default: default_output_selected() , 
             hls_root_dir: PathBuf::from(".") , 
             hls_target_duration: default_hls_target_duration() , 
             hls_prerole_dir: default_hls_prerole_dir() , 
+            hls_slate_dir: default_hls_slate_dir() , 
             hls_web_enabled: true , 
             hls_web_addr: default_hls_web_addr() , 
             hls_web_path: String::from("live") , 
@@ -164,6 +400,7 @@ impl Default for Config {
 
             // Record
             record_enabled: true , 
+            record_default: default_output_selected() , 
             record_root_dir: PathBuf::from(".") , 
             record_append: false , 
 
@@ -175,6 +412,43 @@ impl Default for Config {
             // ttl
             ttl_max_duration: default_ttl_max_duration() , 
 
+            // session store
+            session_store: default_session_store() , 
+            session_store_path: default_session_store_path() , 
+            duplicate_publish_policy: default_duplicate_publish_policy() , 
+            session_queue_size: default_session_queue_size() , 
+            session_overflow_policy: default_session_overflow_policy() , 
+            session_backlog: default_session_backlog() , 
+            session_failover_timeout: default_session_failover_timeout() , 
+            session_max_duration: default_session_max_duration() , 
+            session_expiry_warnings: None , 
+
+            // auth
+            auth_enabled: false , 
+            auth_url: None , 
+            auth_timeout: default_auth_timeout() , 
+            auth_cache_ttl: default_auth_cache_ttl() , 
+            auth_fail_open: false , 
+
+            // notify
+            notify_enabled: false , 
+            notify_urls: None , 
+            notify_secret: None , 
+            notify_queue_dir: default_notify_queue_dir() , 
+            notify_timeout: default_notify_timeout() , 
+            notify_retry_min: default_notify_retry_min() , 
+            notify_retry_max: default_notify_retry_max() , 
+            notify_retry_limit: default_notify_retry_limit() , 
+
+            // shutdown
+            shutdown_timeout: default_shutdown_timeout() , 
+            shutdown_flush_timeout: default_shutdown_flush_timeout() , 
+            shutdown_notify_clients: false , 
+
+            // admin
+            admin_enabled: false , 
+            admin_addr: default_admin_addr() , 
+            admin_token: None , 
         }
     }
 }
@@ -188,6 +462,48 @@ impl Config {
         Ok(config)
     }
 
+    /// NOTIFY_URLS is a comma separated list
+    pub fn notify_urls(&self) -> Vec<String> {
+        self.notify_urls
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// ECHO_SRT_TRANSPORTS is a comma separated list of `public` ,  `private` and
+    /// addresses ,  advertised in that order
+    pub fn echo_srt_transports(&self) -> Vec<TransportCandidate> {
+        self.echo_srt_transports
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|candidate| !candidate.is_empty())
+            .filter_map(TransportCandidate::parse)
+            .collect()
+    }
+
+    /// SESSION_EXPIRY_WARNINGS is a comma separated list of seconds before the
+    /// end of a session ,  the longest first
+    pub fn session_expiry_warnings(&self) -> Vec<Duration> {
+        let mut warnings: Vec<_> = self
+            .session_expiry_warnings
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|secs| secs.trim().parse::<f64>().ok())
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+            .collect();
+        warnings.sort_by(|a ,  b| b.cmp(a));
+        warnings.dedup();
+        warnings
+    }
+
     fn check(&mut self) -> Result<() ,  config::ConfigError> {
         if self.echo_priv_key.len() != 32 {
             return Err(config::ConfigError::Message(String::from(
@@ -209,16 +525,86 @@ impl Config {
                 "ECHO_SRT_MAX_PORT must be less than or equal to 49150" , 
             )));
         }
+        if let Some(port) = self.echo_srt_port {
+            if port < 6_970 || (self.echo_srt_min_port..=self.echo_srt_max_port).contains(&port) {
+                return Err(config::ConfigError::Message(String::from(
+                    "ECHO_SRT_PORT must be greater than or equal to 6970 and out of the port range" , 
+                )));
+            }
+        }
+        let invalid_candidate = self
+            .echo_srt_transports
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|candidate| !candidate.is_empty())
+            .any(|candidate| TransportCandidate::parse(candidate).is_none());
+        if invalid_candidate {
+            return Err(config::ConfigError::Message(String::from(
+                "ECHO_SRT_TRANSPORTS must be a comma separated list of public ,  private and addresses" , 
+            )));
+        }
+        if ![0 ,  16 ,  24 ,  32].contains(&self.echo_srt_key_length) {
+            return Err(config::ConfigError::Message(String::from(
+                "ECHO_SRT_KEY_LENGTH must be one of 0 ,  16 ,  24 and 32" , 
+            )));
+        }
         if self.echo_srt_connection_timeout < Duration::from_secs(10) {
             return Err(config::ConfigError::Message(String::from(
                 "ECHO_SRT_CONNECTION_TIMEOUT must be greater than or equal to 10" , 
             )));
         }
+        if self.echo_srt_stats_interval > Duration::from_secs(0)
+            && self.echo_srt_stats_interval < Duration::from_secs(1)
+        {
+            return Err(config::ConfigError::Message(String::from(
+                "ECHO_SRT_STATS_INTERVAL must be 0 or greater than or equal to 1" , 
+            )));
+        }
         if self.echo_srt_read_timeout < Duration::from_secs(8) {
             return Err(config::ConfigError::Message(String::from(
                 "ECHO_SRT_READ_TIMEOUT must be greater than or equal to 8" , 
             )));
         }
+        if self.session_queue_size == 0 {
+            return Err(config::ConfigError::Message(String::from(
+                "SESSION_QUEUE_SIZE must be greater than 0" , 
+            )));
+        }
+        if self.session_failover_timeout == Duration::from_secs(0) {
+            return Err(config::ConfigError::Message(String::from(
+                "SESSION_FAILOVER_TIMEOUT must be greater than 0" , 
+            )));
+        }
+        let invalid_warning = self
+            .session_expiry_warnings
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|secs| !secs.is_empty())
+            .any(|secs| secs.parse::<f64>().map_or(true ,  |secs| secs <= 0.0));
+        if invalid_warning {
+            return Err(config::ConfigError::Message(String::from(
+                "SESSION_EXPIRY_WARNINGS must be a comma separated list of positive seconds" , 
+            )));
+        }
+        if self.auth_enabled && self.auth_url.is_none() {
+            return Err(config::ConfigError::Message(String::from(
+                "AUTH_URL must be set when AUTH_ENABLED" , 
+            )));
+        }
+        if self.notify_enabled && self.notify_urls().is_empty() {
+            return Err(config::ConfigError::Message(String::from(
+                "NOTIFY_URLS must be set when NOTIFY_ENABLED" , 
+            )));
+        }
+        if self.admin_enabled && self.admin_token.as_deref().unwrap_or_default().is_empty() {
+            return Err(config::ConfigError::Message(String::from(
+                "ADMIN_TOKEN must be set when ADMIN_ENABLED" , 
+            )));
+        }
 
         Ok(())
     }
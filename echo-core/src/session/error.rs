@@ -7,4 +7,19 @@ pub enum Error {
 
     #[error("key mismatch")]
     KeyMismatch , 
+
+    #[error("already publishing")]
+    AlreadyPublishing , 
+
+    #[error("session not found")]
+    NotFound , 
+
+    #[error("shutting down")]
+    ShuttingDown , 
+
+    #[error("session has no time limit")]
+    Unlimited , 
+
+    #[error("extension out of range")]
+    OutOfRange , 
 }
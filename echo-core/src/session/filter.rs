@@ -0,0 +1,241 @@
+use {
+    echo_types::{MediaSample ,  MediaType ,  SampleType ,  Timestamp} , 
+    std::sync::Arc , 
+};
+
+const AAC_FRAME_SAMPLES: u64 = 1024;
+const ADTS_HEADER_SIZE: usize = 7;
+const MICROS_PER_SEC: u64 = 1_000_000;
+
+/// Processes the samples of a session before they are broadcast to the outputs.
+pub trait MediaFilter: Send {
+    /// `None` drops the sample.
+    fn process(&mut self ,  sample: MediaSample) -> Option<MediaSample>;
+
+    /// Another ingest continues the session.
+    fn handover(&mut self) {}
+}
+
+/// Makes the filters of a new session ,  they run after the built-in ones.
+pub type FilterFactory = Arc<dyn Fn(&str) -> Box<dyn MediaFilter> + Send + Sync>;
+
+pub(super) struct FilterChain {
+    filters: Vec<Box<dyn MediaFilter>> , 
+}
+
+impl FilterChain {
+    pub(super) fn new(name: &str ,  factories: &[FilterFactory]) -> Self {
+        let mut filters: Vec<Box<dyn MediaFilter>> = vec![
+            Box::new(ValidateFrame) , 
+            Box::new(SidFilter::default()) , 
+            Box::new(NormalizeTimestamp::default()) , 
+            Box::new(RebaseTimestamp::default()) , 
+        ];
+        filters.extend(factories.iter().map(|factory| factory(name)));
+        Self { filters }
+    }
+
+    pub(super) fn process(&mut self ,  sample: MediaSample) -> Option<MediaSample> {
+        self.filters
+            .iter_mut()
+            .try_fold(sample ,  |sample ,  filter| filter.process(sample))
+    }
+
+    pub(super) fn handover(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.handover();
+        }
+    }
+}
+
+// falls back to a frame of the usual size when the ingest left it out
+fn frame_duration(sample: &MediaSample) -> u64 {
+    if let Some(duration) = sample.duration {
+        return duration.as_micros();
+    }
+    match sample.media_type {
+        MediaType::Audio { sample_rate ,  .. } if sample_rate != 0 => {
+            AAC_FRAME_SAMPLES * MICROS_PER_SEC / sample_rate as u64
+        }
+        _ => 0 , 
+    }
+}
+
+/// Drops samples which are not a single whole ADTS frame.
+pub struct ValidateFrame;
+
+impl MediaFilter for ValidateFrame {
+    fn process(&mut self ,  sample: MediaSample) -> Option<MediaSample> {
+        match sample.sample_type {
+            SampleType::AAC => {
+                let data = sample.data();
+                if data.len() < ADTS_HEADER_SIZE || data[0] != 0xFF || data[1] & 0xF0 != 0xF0 {
+                    log::debug!("drop a sample without ADTS header");
+                    return None;
+                }
+                let frame_length = ((data[3] as usize & 0x03) << 11)
+                    | ((data[4] as usize) << 3)
+                    | ((data[5] as usize) >> 5);
+                if frame_length != data.len() {
+                    log::debug!(
+                        "drop an ADTS frame of {} bytes in {}" , 
+                        frame_length , 
+                        data.len()
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(sample)
+    }
+}
+
+/// Drops samples of an ingest which was already replaced.
+#[derive(Default)]
+pub struct SidFilter {
+    sid: u32 , 
+}
+
+impl MediaFilter for SidFilter {
+    fn process(&mut self ,  sample: MediaSample) -> Option<MediaSample> {
+        if sample.sid < self.sid {
+            return None;
+        }
+        self.sid = sample.sid;
+        Some(sample)
+    }
+}
+
+/// Fills in missing timestamps and counts them in microseconds from the first
+/// sample of the ingest.
+#[derive(Default)]
+pub struct NormalizeTimestamp {
+    base: Option<u64> , 
+    next: u64 , 
+}
+
+impl MediaFilter for NormalizeTimestamp {
+    fn process(&mut self ,  mut sample: MediaSample) -> Option<MediaSample> {
+        let timestamp = match sample.timestamp {
+            Some(timestamp) => {
+                let timestamp = timestamp.as_micros();
+                let base = *self.base.get_or_insert(timestamp);
+                timestamp.saturating_sub(base)
+            }
+            None => self.next , 
+        };
+        self.next = timestamp + frame_duration(&sample);
+        sample.timestamp = Some(Timestamp::from_micros(timestamp));
+        Some(sample)
+    }
+
+    fn handover(&mut self) {
+        // the new ingest starts its own timeline
+        self.base = None;
+        self.next = 0;
+    }
+}
+
+/// Keeps timestamps increasing when an ingest is handed over or its timeline
+/// goes backwards ,  the following samples continue after the last one.
+#[derive(Default)]
+pub struct RebaseTimestamp {
+    offset: u64 , 
+    next: Option<u64> , 
+    rebase: bool , 
+}
+
+impl MediaFilter for RebaseTimestamp {
+    fn process(&mut self ,  mut sample: MediaSample) -> Option<MediaSample> {
+        let timestamp = match sample.timestamp {
+            Some(timestamp) => timestamp.as_micros() , 
+            None => return Some(sample) , 
+        };
+        if let Some(next) = self.next {
+            let rebased = timestamp + self.offset;
+            if self.rebase || rebased + frame_duration(&sample) <= next {
+                self.rebase = false;
+                self.offset = next.saturating_sub(timestamp);
+            }
+        }
+        let timestamp = timestamp + self.offset;
+        self.next = Some(timestamp + frame_duration(&sample));
+        sample.timestamp = Some(Timestamp::from_micros(timestamp));
+        Some(sample)
+    }
+
+    fn handover(&mut self) {
+        self.rebase = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::* ,  echo_types::Duration};
+
+    const ADTS_FRAME: &[u8] = &[0xFF ,  0xF1 ,  0x50 ,  0x80 ,  0x01 ,  0x1F ,  0xFC ,  0x00];
+
+    fn sample(sid: u32 ,  millis: Option<u64> ,  data: &'static [u8]) -> MediaSample {
+        let media_type = MediaType::Audio {
+            sample_rate: 48000 , 
+            channels: 2 , 
+        };
+        let timestamp = millis.map(Timestamp::from_millis);
+        MediaSample::new(sid ,  media_type ,  SampleType::AAC ,  timestamp ,  data)
+    }
+
+    fn timestamps(chain: &mut FilterChain ,  samples: Vec<MediaSample>) -> Vec<u64> {
+        samples
+            .into_iter()
+            .filter_map(|sample| chain.process(sample))
+            .map(|sample| sample.timestamp.unwrap().as_millis())
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_frame() {
+        let mut chain = FilterChain::new("dj" ,  &[]);
+        assert!(chain.process(sample(0 ,  Some(0) ,  &[0u8])).is_none());
+        assert!(chain
+            .process(sample(0 ,  Some(0) ,  &ADTS_FRAME[..7]))
+            .is_none());
+        assert!(chain.process(sample(0 ,  Some(0) ,  ADTS_FRAME)).is_some());
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut chain = FilterChain::new("dj" ,  &[]);
+        let samples = vec![
+            sample(0 ,  Some(1000) ,  ADTS_FRAME) , 
+            sample(0 ,  None ,  ADTS_FRAME) , 
+            sample(0 ,  Some(1042) ,  ADTS_FRAME) , 
+        ];
+        assert_eq!(timestamps(&mut chain ,  samples) ,  vec![0 ,  21 ,  42]);
+
+        // the timeline of the new ingest continues after the last sample
+        chain.handover();
+        let samples = vec![
+            sample(1 ,  Some(5000) ,  ADTS_FRAME) , 
+            sample(0 ,  Some(1063) ,  ADTS_FRAME) , 
+            sample(1 ,  Some(5021) ,  ADTS_FRAME) , 
+        ];
+        assert_eq!(timestamps(&mut chain ,  samples) ,  vec![63 ,  84]);
+
+        // a timeline going backwards is rebased too
+        let samples = vec![sample(1 ,  Some(5000) ,  ADTS_FRAME)];
+        assert_eq!(timestamps(&mut chain ,  samples) ,  vec![105]);
+    }
+
+    #[test]
+    fn test_frame_duration() {
+        let mut chain = FilterChain::new("dj" ,  &[]);
+        let samples = (0..3)
+            .map(|i| {
+                let mut sample = sample(0 ,  if i == 0 { Some(0) } else { None } ,  ADTS_FRAME);
+                sample.duration = Some(Duration::new(960 ,  48000));
+                sample
+            })
+            .collect();
+        assert_eq!(timestamps(&mut chain ,  samples) ,  vec![0 ,  20 ,  40]);
+    }
+}
@@ -1,28 +1,105 @@
 use {
     super::{
-        types::{IncomingBroadcast ,  MediaMessage ,  OutgoingBroadcast} , 
-        AppName , 
+        filter::FilterChain , 
+        types::{
+            ControlMessage ,  ControlReceiver ,  IncomingBroadcast ,  ManageMessage ,  ManagerHandle , 
+            MediaMessage ,  OutgoingBroadcast ,  StateReason , 
+        } , 
+        AppName ,  SessionId ,  SessionWatcher ,  UPLINK_SIDS , 
     } , 
     anyhow::Result , 
     echo_types::{MediaSample ,  MediaType} , 
+    std::{
+        collections::VecDeque , 
+        mem , 
+        time::{Duration ,  Instant} , 
+    } , 
 };
 
+// the samples of a sid
+struct Uplink {
+    sid: u32 , 
+    // the last sample which was not a filler
+    last_seen: Instant , 
+    ended: bool , 
+    // the samples of a backup since the live uplink was last seen ,  they fill
+    // the gap when the backup takes over
+    pending: VecDeque<(Instant ,  MediaSample)> , 
+}
+
+impl Uplink {
+    fn new(sid: u32 ,  now: Instant) -> Self {
+        Self {
+            sid , 
+            last_seen: now , 
+            ended: false , 
+            pending: VecDeque::new() , 
+        }
+    }
+
+    fn is_live(&self ,  now: Instant ,  timeout: Duration) -> bool {
+        !self.ended && now.duration_since(self.last_seen) <= timeout
+    }
+}
+
 pub struct Session {
+    id: SessionId , 
     name: AppName , 
     incoming: IncomingBroadcast , 
+    control: ControlReceiver , 
     outgoing: OutgoingBroadcast , 
+    filters: FilterChain , 
     audio_seq_header: Option<MediaSample> , 
+    // the most recent samples ,  replayed to watchers joining late
+    backlog: VecDeque<MediaSample> , 
+    backlog_duration: u64 , 
+    // watchers get the backlog from the first sample of a segment of this
+    // long ,  the way outputs cut them
+    backlog_alignment: u64 , 
+    // keeps sid increasing across handovers
+    sid_offset: u32 , 
+    last_sid: u32 , 
+    // set when the ingests are backups of each other ,  the samples of a
+    // single uplink are broadcast until it starves for this long
+    failover: Option<Duration> , 
+    // in the order they have joined
+    uplinks: Vec<Uplink> , 
+    live_sid: Option<u32> , 
+    manager: ManagerHandle , 
     closing: bool , 
 }
 
 impl Session {
-    #[allow(clippy::new_without_default)]
-    pub fn new(name: AppName ,  incoming: IncomingBroadcast ,  outgoing: OutgoingBroadcast) -> Self {
+    #[allow(clippy::new_without_default ,  clippy::too_many_arguments)]
+    pub fn new(
+        id: SessionId , 
+        name: AppName , 
+        incoming: IncomingBroadcast , 
+        control: ControlReceiver , 
+        outgoing: OutgoingBroadcast , 
+        filters: FilterChain , 
+        backlog_duration: Duration , 
+        backlog_alignment: Duration , 
+        failover: Option<Duration> , 
+        manager: ManagerHandle , 
+    ) -> Self {
         Self {
+            id , 
             name , 
             incoming , 
+            control , 
             outgoing , 
+            filters , 
             audio_seq_header: None , 
+            backlog: VecDeque::new() , 
+            backlog_duration: backlog_duration.as_millis() as u64 , 
+            backlog_alignment: backlog_alignment.as_millis() as u64 , 
+            sid_offset: 0 , 
+            last_sid: 0 , 
+            failover , 
+            uplinks: Vec::new() , 
+            live_sid: None , 
+            manager , 
             closing: false , 
         }
     }
@@ -30,13 +107,18 @@ impl Session {
     pub async fn run(mut self) {
         log::info!("Create session {}" ,  self.name);
         while !self.closing {
-            match self.incoming.recv().await {
-                Some(message) => {
-                    self.handle_message(message);
-                }
-                None => {
-                    log::warn!("Close session {}" ,  self.name);
-                    self.closing = true;
+            tokio::select! {
+                message = self.incoming.recv() => match message {
+                    Some(message) => {
+                        self.handle_message(message);
+                    }
+                    None => {
+                        log::warn!("Close session {}" ,  self.name);
+                        self.closing = true;
+                    }
+                } , 
+                Some(control) = self.control.recv() => {
+                    self.handle_control(control);
                 }
             }
         }
@@ -44,18 +126,228 @@ impl Session {
     }
 
     fn handle_message(&mut self ,  message: MediaMessage) {
-        match message {
-            MediaMessage::Sample(sample) => {
-                self.set_cache(&sample)
-                    .expect("Failed to set session cache");
-                self.broadcast_sample(sample);
+        match (message ,  self.failover) {
+            (MediaMessage::Sample(sample) ,  Some(timeout)) => {
+                for mut sample in self.select_uplink(sample ,  timeout) {
+                    // outputs see a single stream
+                    sample.sid = 0;
+                    self.process_sample(sample);
+                }
+            }
+            (MediaMessage::Sample(mut sample) ,  None) => {
+                sample.sid += self.sid_offset;
+                self.last_sid = sample.sid;
+                self.process_sample(sample);
+            }
+            // the session is ended by the manager once the last ingest leaves
+            (MediaMessage::EndOfUplink(sid_base) ,  Some(_)) => {
+                self.end_uplink(sid_base);
             }
-            MediaMessage::EndOfSample => {
+            (MediaMessage::EndOfSample ,  _) | (MediaMessage::EndOfUplink(_) ,  None) => {
                 self.closing = true;
             }
         }
     }
 
+    fn process_sample(&mut self ,  sample: MediaSample) {
+        let sample = match self.filters.process(sample) {
+            Some(sample) => sample , 
+            None => return , 
+        };
+        self.set_cache(&sample)
+            .expect("Failed to set session cache");
+        self.push_backlog(&sample);
+        self.broadcast_sample(sample);
+    }
+
+    // the samples to broadcast ,  the first uplink is live until it starves and
+    // a backup which is still fed takes over
+    fn select_uplink(&mut self ,  sample: MediaSample ,  timeout: Duration) -> Vec<MediaSample> {
+        let now = Instant::now();
+        let index = match self
+            .uplinks
+            .iter()
+            .position(|uplink| uplink.sid == sample.sid)
+        {
+            Some(index) => index , 
+            None => {
+                log::info!("Uplink {} joins session {}" ,  sample.sid ,  self.name);
+                self.uplinks.push(Uplink::new(sample.sid ,  now));
+                self.uplinks.len() - 1
+            }
+        };
+        if !sample.filler {
+            self.uplinks[index].last_seen = now;
+        }
+
+        let live_sid = *self.live_sid.get_or_insert(sample.sid);
+        if sample.sid == live_sid {
+            if !sample.filler {
+                return vec![sample];
+            }
+            // the ingest makes up silence ,  a backup has the real media
+            let backup = self
+                .uplinks
+                .iter()
+                .position(|uplink| uplink.sid != live_sid && uplink.is_live(now ,  timeout));
+            return match backup {
+                Some(backup) => {
+                    self.switch_uplink(backup ,  StateReason::new(30008 ,  "live uplink starved"))
+                }
+                None => vec![sample] , 
+            };
+        }
+        if sample.filler {
+            return Vec::new();
+        }
+
+        let live = self.uplinks.iter().find(|uplink| uplink.sid == live_sid);
+        let reason = match live {
+            Some(live) if live.ended => Some(StateReason::new(30009 ,  "live uplink ended")) , 
+            Some(live) if !live.is_live(now ,  timeout) => {
+                Some(StateReason::new(30008 ,  "live uplink starved"))
+            }
+            Some(_) => None , 
+            None => Some(StateReason::new(30009 ,  "live uplink ended")) , 
+        };
+        let last_seen = live.map_or(now ,  |live| live.last_seen);
+        let pending = &mut self.uplinks[index].pending;
+        pending.push_back((now ,  sample));
+        while let Some((arrived ,  _)) = pending.front() {
+            if *arrived >= last_seen {
+                break;
+            }
+            pending.pop_front();
+        }
+
+        match reason {
+            Some(reason) => self.switch_uplink(index ,  reason) , 
+            None => Vec::new() , 
+        }
+    }
+
+    // the backup continues the timeline of the live uplink from where it was
+    // last seen
+    fn switch_uplink(&mut self ,  index: usize ,  reason: StateReason) -> Vec<MediaSample> {
+        let sid = self.uplinks[index].sid;
+        let last_seen = self
+            .uplinks
+            .iter()
+            .find(|uplink| Some(uplink.sid) == self.live_sid)
+            .map(|live| live.last_seen);
+        log::warn!(
+            "Session {} switches from uplink {:?} to {}: {:?}" , 
+            self.name , 
+            self.live_sid , 
+            sid , 
+            reason
+        );
+        self.live_sid = Some(sid);
+        let pending = mem::take(&mut self.uplinks[index].pending);
+        self.uplinks.retain(|uplink| !uplink.ended);
+        self.filters.handover();
+        if self
+            .manager
+            .send(ManageMessage::SwitchUplink(self.id ,  sid ,  reason))
+            .is_err()
+        {
+            log::error!(
+                "Failed to report the uplink switch of session {}" , 
+                self.name
+            );
+        }
+        pending
+            .into_iter()
+            .filter(|(arrived ,  _)| match last_seen {
+                Some(last_seen) => *arrived >= last_seen , 
+                None => true , 
+            })
+            .map(|(_ ,  sample)| sample)
+            .collect()
+    }
+
+    fn end_uplink(&mut self ,  sid_base: u32) {
+        let live_sid = self.live_sid;
+        let ingest = sid_base..sid_base + UPLINK_SIDS;
+        // the live one is kept until a backup takes over
+        self.uplinks
+            .retain(|uplink| !ingest.contains(&uplink.sid) || Some(uplink.sid) == live_sid);
+        for uplink in self.uplinks.iter_mut() {
+            if ingest.contains(&uplink.sid) {
+                uplink.ended = true;
+            }
+        }
+    }
+
+    fn handle_control(&mut self ,  control: ControlMessage) {
+        match control {
+            ControlMessage::Handover(incoming) => {
+                log::info!("Handover session {}" ,  self.name);
+                // samples of the previous ingest still in the queue are dropped
+                self.incoming = incoming;
+                // outputs treat a new sid as a new stream
+                self.sid_offset = self.last_sid + 1;
+                self.filters.handover();
+                // the timeline of the new ingest is unrelated to the backlog
+                self.backlog.clear();
+            }
+            ControlMessage::Watch(responder) => {
+                // the backlog and the subscription are taken between two samples , 
+                // so the watcher sees every sample exactly once
+                let watcher = SessionWatcher::with_backlog(
+                    self.aligned_backlog() , 
+                    self.outgoing.subscribe() , 
+                );
+                if responder.send(Ok(watcher)).is_err() {
+                    log::warn!("Failed to send watcher of session {}" ,  self.name);
+                }
+            }
+        }
+    }
+
+    fn push_backlog(&mut self ,  sample: &MediaSample) {
+        let timestamp = match sample.timestamp {
+            Some(timestamp) if self.backlog_duration != 0 => timestamp.as_millis() , 
+            _ => return , 
+        };
+        // a timestamp going backwards starts a new timeline
+        if let Some(last) = self.backlog.back().and_then(|sample| sample.timestamp) {
+            if timestamp < last.as_millis() {
+                self.backlog.clear();
+            }
+        }
+        self.backlog.push_back(sample.clone());
+        while let Some(first) = self.backlog.front().and_then(|sample| sample.timestamp) {
+            if timestamp - first.as_millis() <= self.backlog_duration {
+                break;
+            }
+            self.backlog.pop_front();
+        }
+    }
+
+    // the backlog from the first sample at or past a multiple of the
+    // alignment ,  all of it if there is none
+    fn aligned_backlog(&self) -> VecDeque<MediaSample> {
+        let alignment = self.backlog_alignment;
+        if alignment == 0 {
+            return self.backlog.clone();
+        }
+        let mut previous = None;
+        let start = self.backlog.iter().position(|sample| {
+            let timestamp = sample.timestamp.map_or(0 ,  |timestamp| timestamp.as_millis());
+            let starts = match previous {
+                Some(previous) => timestamp / alignment != previous / alignment , 
+                None => timestamp % alignment == 0 , 
+            };
+            previous = Some(timestamp);
+            starts
+        });
+        match start {
+            Some(start) => self.backlog.iter().skip(start).cloned().collect() , 
+            None => self.backlog.clone() , 
+        }
+    }
+
     fn broadcast_sample(&self ,  sample: MediaSample) {
         if self.outgoing.receiver_count() != 0 && self.outgoing.send(sample).is_err() {
             log::error!("Failed to broadcast sample");
@@ -1,27 +1,40 @@
 use {
     super::{
+        filter::{FilterChain ,  FilterFactory} , 
         instance::Session , 
+        media::{media_channel ,  SessionHandle ,  SessionWatcher} , 
+        shutdown::{output_guard ,  OutputGuard ,  OutputWaiter} , 
+        store::{FileStore ,  MemoryStore ,  SessionStore} , 
         types::{
-            EventKind ,  EventMessage ,  EventTrigger ,  ManageMessage ,  ManagerHandle ,  MessageReceiver , 
-            OutgoingBroadcast ,  SessionHandle , 
+            ControlMessage ,  EventKind ,  EventMessage ,  EventTrigger ,  IngestHandle ,  IngestMessage , 
+            ManageMessage ,  ManagerHandle ,  MediaMessage ,  MessageReceiver ,  SessionControl , 
+            SessionInfo ,  StateReason , 
         } , 
-        AppName ,  Error as SessError ,  SessionId ,  SessionProps , 
+        AppName ,  Error as SessError ,  SessionId ,  UPLINK_SIDS , 
+    } , 
+    crate::{
+        authorization::Error as AuthError , 
+        config::{Config ,  DuplicatePublishPolicy ,  OverflowPolicy ,  SessionStoreKind} , 
     } , 
-    crate::Config , 
     anyhow::{bail ,  Result} , 
-    lru_time_cache::LruCache , 
+    echo_types::Protocol , 
     rand::{distributions::Alphanumeric ,  thread_rng ,  Rng} , 
     std::{
         collections::HashMap , 
         sync::{
-            atomic::{AtomicU64 ,  Ordering} , 
+            atomic::{AtomicBool ,  AtomicU64 ,  Ordering} , 
             Arc , 
         } , 
-        time::{Duration ,  Instant} , 
+        time::{Duration ,  Instant ,  SystemTime} , 
+    } , 
+    tokio::{
+        sync::{broadcast ,  mpsc ,  oneshot ,  RwLock} , 
+        time , 
     } , 
-    tokio::sync::{broadcast ,  mpsc ,  oneshot ,  RwLock} , 
 };
 
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Debug ,  Default ,  Clone)]
 pub struct IdGenerator {
     value: Arc<AtomicU64> , 
@@ -39,15 +52,61 @@ impl IdGenerator {
     }
 }
 
+// an ingest which is kept ready to take over the session
+struct Backup {
+    id: SessionId , 
+    protocol: Protocol , 
+    ingest: IngestHandle , 
+    sid_base: u32 , 
+}
+
+struct SessionEntry {
+    name: AppName , 
+    protocol: Protocol , 
+    created_at: SystemTime , 
+    // the ingest currently feeding the session
+    owner: SessionId , 
+    ingest: IngestHandle , 
+    // the first sid of the owner
+    sid_base: u32 , 
+    backups: Vec<Backup> , 
+    // ingests which have fed the session so far
+    uplinks: u32 , 
+    handle: SessionHandle , 
+    control: SessionControl , 
+    // the end of the maximum duration
+    expires_at: Option<Instant> , 
+    // expiry warnings sent so far
+    warned: usize , 
+}
+
 pub struct SessionManager {
     id_gen: IdGenerator , 
     handle: ManagerHandle , 
     incoming: MessageReceiver , 
-    sessions: Arc<RwLock<HashMap<SessionId ,  (SessionHandle ,  OutgoingBroadcast)>>> , 
-    session_keys: Arc<RwLock<LruCache<AppName ,  (String ,  Instant)>>> , 
-    session_props: Arc<RwLock<LruCache<AppName ,  SessionProps>>> , 
+    sessions: Arc<RwLock<HashMap<SessionId ,  SessionEntry>>> , 
+    // live session of each name
+    names: HashMap<AppName ,  SessionId> , 
+    // session fed by each ingest ,  differs from the ingest id after a handover
+    ingests: HashMap<SessionId ,  SessionId> , 
+    duplicate_publish_policy: DuplicatePublishPolicy , 
+    queue_size: usize , 
+    overflow_policy: OverflowPolicy , 
+    backlog: Duration , 
+    // the target duration of HLS segments
+    backlog_alignment: Duration , 
+    failover_timeout: Duration , 
+    max_duration: Option<Duration> , 
+    // the longest first
+    expiry_warnings: Vec<Duration> , 
+    filters: Vec<FilterFactory> , 
+    store: Arc<RwLock<Box<dyn SessionStore>>> , 
     triggers: Arc<RwLock<HashMap<EventKind ,  Vec<EventTrigger>>>> , 
     session_ttl: Duration , 
+    shutting_down: Arc<AtomicBool> , 
+    // dropped once the remaining sessions are terminated
+    output_guard: Option<OutputGuard> , 
+    output_waiter: Option<OutputWaiter> , 
 }
 
 impl SessionManager {
@@ -56,23 +115,43 @@ impl SessionManager {
         let (handle ,  incoming) = mpsc::unbounded_channel();
         let sessions = Arc::new(RwLock::new(HashMap::new()));
         let session_ttl = config.ttl_max_duration + (config.ttl_max_duration / 60);
-        let session_keys = Arc::new(RwLock::new(
-            LruCache::<AppName ,  (String ,  Instant)>::with_expiry_duration(session_ttl) , 
-        ));
-        let session_props = Arc::new(RwLock::new(
-            LruCache::<AppName ,  SessionProps>::with_expiry_duration(session_ttl) , 
-        ));
+        let store: Box<dyn SessionStore> = match config.session_store {
+            SessionStoreKind::Memory => Box::new(MemoryStore::new(session_ttl)) , 
+            SessionStoreKind::File => {
+                match FileStore::open(&config.session_store_path ,  session_ttl) {
+                    Ok(store) => Box::new(store) , 
+                    Err(err) => panic!("Failed to open session store: {}" ,  err) , 
+                }
+            }
+        };
+        let store = Arc::new(RwLock::new(store));
         let triggers = Arc::new(RwLock::new(HashMap::new()));
+        let shutting_down = Arc::new(AtomicBool::new(false));
+        let (output_guard ,  output_waiter) = output_guard(shutting_down.clone());
 
         Self {
             id_gen , 
             handle , 
             incoming , 
             sessions , 
-            session_keys , 
-            session_props , 
+            names: HashMap::new() , 
+            ingests: HashMap::new() , 
+            duplicate_publish_policy: config.duplicate_publish_policy , 
+            queue_size: config.session_queue_size , 
+            overflow_policy: config.session_overflow_policy , 
+            backlog: config.session_backlog , 
+            backlog_alignment: config.hls_target_duration , 
+            failover_timeout: config.session_failover_timeout , 
+            max_duration: Some(config.session_max_duration)
+                .filter(|duration| *duration > Duration::from_secs(0)) , 
+            expiry_warnings: config.session_expiry_warnings() , 
+            filters: Vec::new() , 
+            store , 
             triggers , 
             session_ttl , 
+            shutting_down , 
+            output_guard: Some(output_guard) , 
+            output_waiter: Some(output_waiter) , 
         }
     }
 
@@ -84,68 +163,117 @@ impl SessionManager {
         self.id_gen.clone()
     }
 
+    /// Waits for the outputs of all sessions to be flushed after `TerminateSessions`.
+    pub fn take_output_waiter(&mut self) -> Option<OutputWaiter> {
+        self.output_waiter.take()
+    }
+
+    /// Adds a filter to every session created from now on.
+    pub fn add_filter(&mut self ,  factory: FilterFactory) {
+        self.filters.push(factory);
+    }
+
     async fn process_message(&mut self ,  message: ManageMessage) -> Result<()> {
         match message {
             ManageMessage::UpdateSessionProps(name ,  props) => {
-                let mut session_props = self.session_props.write().await;
-                session_props.insert(name ,  props);
+                let mut store = self.store.write().await;
+                if let Err(err) = store.set_props(name.clone() ,  props) {
+                    log::error!("{} failed to store props: {}" ,  name ,  err);
+                }
             }
-            ManageMessage::AuthorizeSession(name ,  authorization ,  responder) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::AuthorizeSession(name ,  authorization ,  props ,  responder) => {
+                let props = match props {
+                    Some(props) => Some(props) , 
+                    None => {
+                        let store = self.store.read().await;
+                        store.props(&name)
+                    }
+                };
 
-                let triggers = self.triggers.read().await;
-                let mut result = Ok(String::default());
-                if let Some(event_triggers) = triggers.get(&EventKind::AuthorizeSession) {
+                let event_triggers = {
+                    let triggers = self.triggers.read().await;
+                    triggers
+                        .get(&EventKind::AuthorizeSession)
+                        .cloned()
+                        .unwrap_or_default()
+                };
+                let store = self.store.clone();
+                let session_ttl = self.session_ttl;
+
+                // authorizers may take a while (e.g. remote endpoints) , 
+                // so don't hold up the manager loop
+                tokio::spawn(async move {
+                    let mut result = Ok(String::default());
                     for trigger in event_triggers {
-                        let (authenticator ,  responder) = oneshot::channel();
-                        trigger.send((
-                            name.clone() , 
-                            EventMessage::AuthorizeSession(
-                                authorization.clone() , 
-                                props.clone() , 
-                                authenticator , 
-                            ) , 
-                        ))?;
-                        if let Err(err) = responder.await.unwrap() {
-                            result = Err(err);
+                        let (authenticator ,  authorized) = oneshot::channel();
+                        if trigger
+                            .send((
+                                name.clone() , 
+                                EventMessage::AuthorizeSession(
+                                    authorization.clone() , 
+                                    props.clone() , 
+                                    authenticator , 
+                                ) , 
+                            ))
+                            .is_err()
+                        {
+                            log::error!("{} failed to send authorization request" ,  name);
+                            result = Err(AuthError::Unauthorized);
                             break;
                         }
+                        match authorized.await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(err)) => {
+                                result = Err(err);
+                                break;
+                            }
+                            Err(_) => {
+                                log::error!("{} authorizer dropped the request" ,  name);
+                                result = Err(AuthError::Unauthorized);
+                                break;
+                            }
+                        }
                     }
-                }
-                if result.is_ok() {
-                    let mut session_keys = self.session_keys.write().await;
-                    let key = rand_string(8);
-                    let exp = Instant::now() + self.session_ttl;
-                    log::info!("{} create key {} valid until {:?}" ,  name ,  key ,  exp);
-                    session_keys.insert(name ,  (key.to_string() ,  exp));
-                    result = Ok(key);
-                }
-                if let Err(_) = responder.send(result) {
-                    bail!("Failed to send response");
-                }
+                    if result.is_ok() {
+                        let mut store = store.write().await;
+                        let key = rand_string(8);
+                        let exp = SystemTime::now() + session_ttl;
+                        log::info!("{} create key {} valid until {:?}" ,  name ,  key ,  exp);
+                        if let Err(err) = store.set_key(name.clone() ,  key.clone() ,  exp) {
+                            log::error!("{} failed to store key: {}" ,  name ,  err);
+                        }
+                        result = Ok(key);
+                    }
+                    if responder.send(result).is_err() {
+                        log::error!("Failed to send authorization response");
+                    }
+                });
             }
             // SRT, RTMP
-            ManageMessage::CreateSession(name ,  id ,  proto ,  key ,  reason ,  responder) => {
-                // unbounded No length limit. -> multi pruducder : single consumer
-                let (handle ,  incoming) = mpsc::unbounded_channel();
-                // brodcast channel -> single pruducer : multi consumer
-                // channel with receiver -> pubSub
-                let (outgoing ,  _watcher) = broadcast::channel(64);
-                let mut sessions = self.sessions.write().await;
-                if sessions.contains_key(&id) {
+            ManageMessage::CreateSession(name ,  id ,  proto ,  key ,  reason ,  ingest ,  responder) => {
+                let output_guard = match self.output_guard {
+                    Some(ref guard) if !guard.is_shutting_down() => guard.clone() , 
+                    _ => {
+                        log::warn!("{} session rejected ,  shutting down" ,  name);
+                        if responder.send(Err(SessError::ShuttingDown)).is_err() {
+                            bail!("Failed to send response");
+                        }
+                        return Ok(());
+                    }
+                };
+                if self.ingests.contains_key(&id) {
                     if let Err(_) = responder.send(Err(SessError::DuplicatedCreation)) {
                         bail!("Failed to send response");
                     }
                 } else {
                     let mut exp_opt = None;
                     let is_matched = if let Some(key) = key {
-                        let session_keys = self.session_keys.read().await;
-                        if let Some((stored_key ,  exp)) = session_keys.peek(&name).cloned() {
+                        let stored = self.store.read().await.key(&name);
+                        if let Some((stored_key ,  exp)) = stored {
                             if key == stored_key {
-                                let now = Instant::now();
-                                if exp > now {
-                                    exp_opt = Some(exp);
+                                let now = SystemTime::now();
+                                if let Ok(remaining) = exp.duration_since(now) {
+                                    exp_opt = Some(Instant::now() + remaining);
                                     true
                                 } else {
                                     log::error!(
@@ -174,17 +302,152 @@ impl SessionManager {
                     };
 
                     if is_matched {
-                        sessions.insert(id ,  (handle.clone() ,  outgoing.clone()));
+                        let props = self.store.read().await.props(&name);
+
+                        if let Some(&live_id) = self.names.get(&name) {
+                            match self.duplicate_publish_policy {
+                                DuplicatePublishPolicy::Reject => {
+                                    log::warn!(
+                                        "{} is already published by {} ,  reject {}" , 
+                                        name , 
+                                        live_id , 
+                                        id
+                                    );
+                                    let reason = StateReason::new(30003 ,  "already publishing");
+                                    let triggers = self.triggers.read().await;
+                                    if let Some(event_triggers) =
+                                        triggers.get(&EventKind::RejectSession)
+                                    {
+                                        for trigger in event_triggers {
+                                            trigger.send((
+                                                name.clone() , 
+                                                EventMessage::RejectSession(
+                                                    id , 
+                                                    proto , 
+                                                    reason.clone() , 
+                                                    props.clone() , 
+                                                ) , 
+                                            ))?;
+                                        }
+                                    }
+                                    if responder.send(Err(SessError::AlreadyPublishing)).is_err() {
+                                        bail!("Failed to send response");
+                                    }
+                                    return Ok(());
+                                }
+                                DuplicatePublishPolicy::Evict => {
+                                    log::warn!(
+                                        "{} is already published by {} ,  evict it for {}" , 
+                                        name , 
+                                        live_id , 
+                                        id
+                                    );
+                                    self.release_session(
+                                        live_id , 
+                                        StateReason::new(30002 ,  "evicted by a new publisher") , 
+                                    )
+                                    .await?;
+                                }
+                                DuplicatePublishPolicy::Handover => {
+                                    if let Some(handle) = self
+                                        .handover_session(live_id ,  id ,  proto ,  ingest.clone())
+                                        .await
+                                    {
+                                        log::info!(
+                                            "{} is handed over from {} to {}" , 
+                                            name , 
+                                            live_id , 
+                                            id
+                                        );
+                                        let reason = StateReason::new(
+                                            30004 , 
+                                            "handed over to a new publisher" , 
+                                        );
+                                        let triggers = self.triggers.read().await;
+                                        if let Some(event_triggers) =
+                                            triggers.get(&EventKind::HandoverSession)
+                                        {
+                                            for trigger in event_triggers {
+                                                trigger.send((
+                                                    name.clone() , 
+                                                    EventMessage::HandoverSession(
+                                                        live_id , 
+                                                        proto , 
+                                                        reason.clone() , 
+                                                        props.clone() , 
+                                                    ) , 
+                                                ))?;
+                                            }
+                                        }
+                                        if responder.send(Ok((handle ,  exp_opt))).is_err() {
+                                            bail!("Failed to send response");
+                                        }
+                                        return Ok(());
+                                    }
+                                    // the live session has just ended ,  start a new one
+                                }
+                                DuplicatePublishPolicy::Backup => {
+                                    if let Some(handle) =
+                                        self.add_backup(live_id ,  id ,  proto ,  ingest.clone()).await
+                                    {
+                                        log::info!(
+                                            "{} is published by {} ,  {} is kept as a backup" , 
+                                            name , 
+                                            live_id , 
+                                            id
+                                        );
+                                        if responder.send(Ok((handle ,  exp_opt))).is_err() {
+                                            bail!("Failed to send response");
+                                        }
+                                        return Ok(());
+                                    }
+                                    // the live session has just ended ,  start a new one
+                                }
+                            }
+                        }
 
-                        let session_props = self.session_props.read().await;
-                        let props = session_props.peek(&name).cloned();
+                        // unbounded No length limit. -> multi pruducder : single consumer
+                        let (handle ,  incoming) =
+                            media_channel(self.queue_size ,  self.overflow_policy);
+                        let (control ,  control_receiver) = mpsc::unbounded_channel();
+                        // brodcast channel -> single pruducer : multi consumer
+                        // channel with receiver -> pubSub
+                        let (outgoing ,  _watcher) = broadcast::channel(64);
+                        self.sessions.write().await.insert(
+                            id , 
+                            SessionEntry {
+                                name: name.clone() , 
+                                protocol: proto , 
+                                created_at: SystemTime::now() , 
+                                owner: id , 
+                                ingest , 
+                                sid_base: 0 , 
+                                backups: Vec::new() , 
+                                uplinks: 1 , 
+                                handle: handle.clone() , 
+                                control , 
+                                expires_at: self
+                                    .max_duration
+                                    .map(|duration| Instant::now() + duration) , 
+                                warned: self.max_duration.map_or(0 ,  |duration| {
+                                    passed_warnings(&self.expiry_warnings ,  duration)
+                                }) , 
+                            } , 
+                        );
+                        self.names.insert(name.clone() ,  id);
+                        self.ingests.insert(id ,  id);
 
                         let triggers = self.triggers.read().await;
                         if let Some(event_triggers) = triggers.get(&EventKind::CreateSession) {
                             for trigger in event_triggers {
                                 trigger.send((
                                     name.clone() , 
-                                    EventMessage::CreateSession(id ,  outgoing.subscribe()) , 
+                                    EventMessage::CreateSession(
+                                        id , 
+                                        SessionWatcher::new(outgoing.subscribe()) , 
+                                        output_guard.clone() , 
+                                        props.clone() , 
+                                    ) , 
                                 ))?;
                             }
                         }
@@ -202,11 +465,31 @@ impl SessionManager {
                             }
                         }
 
+                        let filters = FilterChain::new(&name ,  &self.filters);
+                        let backlog = self.backlog;
+                        let backlog_alignment = self.backlog_alignment;
+                        let failover = Some(self.failover_timeout).filter(|_| {
+                            self.duplicate_publish_policy == DuplicatePublishPolicy::Backup
+                        });
+                        let manager = self.handle.clone();
                         tokio::spawn(async move {
-                            Session::new(name ,  incoming ,  outgoing).run().await;
+                            Session::new(
+                                id , 
+                                name , 
+                                incoming , 
+                                control_receiver , 
+                                outgoing , 
+                                filters , 
+                                backlog , 
+                                backlog_alignment , 
+                                failover , 
+                                manager , 
+                            )
+                            .run()
+                            .await;
                         });
 
-                        if let Err(_) = responder.send(Ok((handle ,  exp_opt))) {
+                        if let Err(_) = responder.send(Ok((handle.uplink(0) ,  exp_opt))) {
                             bail!("Failed to send response");
                         }
                     } else {
@@ -217,8 +500,12 @@ impl SessionManager {
                 }
             }
             ManageMessage::PauseSession(name ,  id ,  reason) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+                // a backup does not stand for the session
+                let id = match self.owned_session(id).await {
+                    Some(live_id) => live_id , 
+                    None => return Ok(()) , 
+                };
+                let props = self.store.read().await.props(&name);
 
                 let triggers = self.triggers.read().await;
                 if let Some(event_triggers) = triggers.get(&EventKind::PauseSession) {
@@ -231,8 +518,12 @@ impl SessionManager {
                 }
             }
             ManageMessage::ResumeSession(name ,  id ,  reason) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+                // a backup does not stand for the session
+                let id = match self.owned_session(id).await {
+                    Some(live_id) => live_id , 
+                    None => return Ok(()) , 
+                };
+                let props = self.store.read().await.props(&name);
 
                 let triggers = self.triggers.read().await;
                 if let Some(event_triggers) = triggers.get(&EventKind::ResumeSession) {
@@ -245,104 +536,466 @@ impl SessionManager {
                 }
             }
             ManageMessage::ReleaseSession(name ,  id ,  reason) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
-
-                let mut sessions = self.sessions.write().await;
-                sessions.remove(&id);
+                // an ingest which has been evicted or handed over no longer owns a session
+                let live_id = match self.ingests.remove(&id) {
+                    Some(live_id) => live_id , 
+                    None => {
+                        log::debug!("{} {} has already been released" ,  name ,  id);
+                        return Ok(());
+                    }
+                };
+                if self.remove_uplink(live_id ,  id).await {
+                    log::info!(
+                        "{} {} has left ,  {} goes on with the backups" , 
+                        name , 
+                        id , 
+                        live_id
+                    );
+                    return Ok(());
+                }
+                self.release_session(live_id ,  reason).await?;
+            }
+            ManageMessage::InputQualityReport(name ,  id ,  quality) => {
+                // an ingest which has been evicted or handed over reports nothing
+                let id = match self.ingests.get(&id) {
+                    Some(live_id) => *live_id , 
+                    None => return Ok(()) , 
+                };
+                let props = self.store.read().await.props(&name);
 
                 let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::ReleaseSession) {
+                if let Some(event_triggers) = triggers.get(&EventKind::InputQualityReport) {
                     for trigger in event_triggers {
                         trigger.send((
                             name.clone() , 
-                            EventMessage::ReleaseSession(id ,  reason.clone() ,  props.clone()) , 
+                            EventMessage::InputQualityReport(id ,  quality ,  props.clone()) , 
                         ))?;
                     }
                 }
             }
-            ManageMessage::ReadyHlsSession(name ,  id ,  path) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::OutputStatus(name ,  id ,  output ,  status) => {
+                let props = self.store.read().await.props(&name);
 
                 let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::ReadyHlsSession) {
+                if let Some(event_triggers) = triggers.get(&EventKind::OutputStatus) {
                     for trigger in event_triggers {
                         trigger.send((
                             name.clone() , 
-                            EventMessage::ReadyHlsSession(id ,  path.clone() ,  props.clone()) , 
+                            EventMessage::OutputStatus(id ,  output ,  status.clone() ,  props.clone()) , 
                         ))?;
                     }
                 }
             }
-            ManageMessage::ReleaseHlsSession(name ,  id) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::SwitchUplink(id ,  sid ,  reason) => {
+                let switched = match self.sessions.write().await.get_mut(&id) {
+                    Some(entry) => {
+                        let sid_base = sid - sid % UPLINK_SIDS;
+                        if let Some(index) = entry
+                            .backups
+                            .iter()
+                            .position(|backup| backup.sid_base == sid_base)
+                        {
+                            let backup = entry.backups.remove(index);
+                            entry.backups.push(Backup {
+                                id: entry.owner , 
+                                protocol: entry.protocol , 
+                                ingest: entry.ingest.clone() , 
+                                sid_base: entry.sid_base , 
+                            });
+                            entry.owner = backup.id;
+                            entry.protocol = backup.protocol;
+                            entry.ingest = backup.ingest;
+                            entry.sid_base = backup.sid_base;
+                        }
+                        Some((entry.name.clone() ,  entry.owner ,  entry.protocol))
+                    }
+                    None => None , 
+                };
+                if let Some((name ,  owner ,  proto)) = switched {
+                    log::info!("{} {} is fed by {} now: {:?}" ,  name ,  id ,  owner ,  reason);
+                    let props = self.store.read().await.props(&name);
 
-                let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::ReleaseHlsSession) {
-                    for trigger in event_triggers {
-                        trigger.send((
-                            name.clone() , 
-                            EventMessage::ReleaseHlsSession(id ,  props.clone()) , 
-                        ))?;
+                    let triggers = self.triggers.read().await;
+                    if let Some(event_triggers) = triggers.get(&EventKind::SwitchSession) {
+                        for trigger in event_triggers {
+                            trigger.send((
+                                name.clone() , 
+                                EventMessage::SwitchSession(
+                                    id , 
+                                    owner , 
+                                    proto , 
+                                    reason.clone() , 
+                                    props.clone() , 
+                                ) , 
+                            ))?;
+                        }
                     }
                 }
             }
-            ManageMessage::StartRecord(name ,  id) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::RegisterTrigger(event ,  trigger) => {
+                log::debug!("Registering trigger for {:?}" ,  event);
+                let mut triggers = self.triggers.write().await;
+                triggers.entry(event).or_insert_with(Vec::new).push(trigger);
+            }
+            ManageMessage::Shutdown(reason) => {
+                log::info!("Shutting down ,  stop accepting new sessions");
+                self.shutting_down.store(true ,  Ordering::SeqCst);
 
                 let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::StartRecord) {
+                if let Some(event_triggers) = triggers.get(&EventKind::Shutdown) {
                     for trigger in event_triggers {
-                        trigger
-                            .send((name.clone() ,  EventMessage::StartRecord(id ,  props.clone())))?;
+                        trigger.send((String::new() ,  EventMessage::Shutdown(reason.clone())))?;
                     }
                 }
             }
-            ManageMessage::CompleteRecord(name ,  id ,  path ,  duration) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::CountSessions(responder) => {
+                let sessions = self.sessions.read().await;
+                if responder.send(sessions.len()).is_err() {
+                    bail!("Failed to send response");
+                }
+            }
+            ManageMessage::TerminateSessions(reason) => {
+                let ids: Vec<_> = self.sessions.read().await.keys().copied().collect();
+                log::info!("Terminate {} sessions: {:?}" ,  ids.len() ,  reason);
+                for id in ids {
+                    self.release_session(id ,  reason.clone()).await?;
+                }
+                self.shutting_down.store(true ,  Ordering::SeqCst);
+                self.output_guard = None;
+            }
+            ManageMessage::ListSessions(responder) => {
+                let store = self.store.read().await;
+                let sessions = self.sessions.read().await;
+                let infos = sessions
+                    .iter()
+                    .map(|(id ,  entry)| SessionInfo {
+                        id: *id , 
+                        name: entry.name.clone() , 
+                        protocol: entry.protocol , 
+                        ingest_id: entry.owner , 
+                        created_at: entry.created_at , 
+                        expires_at: entry.expires_at.map(|expires_at| {
+                            SystemTime::now() + expires_at.saturating_duration_since(Instant::now())
+                        }) , 
+                        props: store.props(&entry.name) , 
+                    })
+                    .collect();
+                if responder.send(infos).is_err() {
+                    bail!("Failed to send response");
+                }
+            }
+            ManageMessage::KickSession(id ,  reason ,  responder) => {
+                let result = if self.sessions.read().await.contains_key(&id) {
+                    log::info!("Kick session {}: {:?}" ,  id ,  reason);
+                    self.release_session(id ,  reason).await?;
+                    Ok(())
+                } else {
+                    Err(SessError::NotFound)
+                };
+                if responder.send(result).is_err() {
+                    bail!("Failed to send response");
+                }
+            }
+            ManageMessage::WatchSession(id ,  responder) => {
+                let message = ControlMessage::Watch(responder);
+                // the session answers with its backlog
+                let message = match self.sessions.read().await.get(&id) {
+                    Some(entry) => match entry.control.send(message) {
+                        Ok(()) => return Ok(()) , 
+                        // the session has just ended
+                        Err(mpsc::error::SendError(message)) => message , 
+                    } , 
+                    None => message , 
+                };
+                if let ControlMessage::Watch(responder) = message {
+                    if responder.send(Err(SessError::NotFound)).is_err() {
+                        bail!("Failed to send response");
+                    }
+                }
+            }
+            ManageMessage::PatchSessionProps(id ,  patch ,  responder) => {
+                let name = match self.sessions.read().await.get(&id) {
+                    Some(entry) => entry.name.clone() , 
+                    None => {
+                        if responder.send(Err(SessError::NotFound)).is_err() {
+                            bail!("Failed to send response");
+                        }
+                        return Ok(());
+                    }
+                };
+                let mut store = self.store.write().await;
+                let mut props = store.props(&name).unwrap_or_default();
+                let mut changed = Vec::new();
+                for (key ,  value) in patch {
+                    let previous = match value {
+                        Some(ref value) => props.insert(key.clone() ,  value.clone()) , 
+                        None => props.remove(&key) , 
+                    };
+                    if previous != value {
+                        changed.push(key);
+                    }
+                }
+                changed.sort();
+                if let Err(err) = store.set_props(name.clone() ,  props.clone()) {
+                    log::error!("{} failed to store props: {}" ,  name ,  err);
+                }
+                drop(store);
 
-                let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::CompleteRecord) {
-                    for trigger in event_triggers {
-                        trigger.send((
-                            name.clone() , 
-                            EventMessage::CompleteRecord(id ,  path.clone() ,  duration ,  props.clone()) , 
-                        ))?;
+                if !changed.is_empty() {
+                    log::info!("{} {} props changed: {:?}" ,  name ,  id ,  changed);
+                    let triggers = self.triggers.read().await;
+                    if let Some(event_triggers) = triggers.get(&EventKind::PropsChanged) {
+                        for trigger in event_triggers {
+                            trigger.send((
+                                name.clone() , 
+                                EventMessage::PropsChanged(
+                                    id , 
+                                    changed.clone() , 
+                                    Some(props.clone()) , 
+                                ) , 
+                            ))?;
+                        }
                     }
                 }
+                if responder.send(Ok(props)).is_err() {
+                    bail!("Failed to send response");
+                }
             }
-            ManageMessage::InputQualityReport(name ,  id ,  quality) => {
-                let session_props = self.session_props.read().await;
-                let props = session_props.peek(&name).cloned();
+            ManageMessage::ExtendSession(id ,  duration ,  responder) => {
+                let result = match self.sessions.write().await.get_mut(&id) {
+                    Some(entry) => match entry.expires_at.as_mut() {
+                        Some(expires_at) => match expires_at.checked_add(duration) {
+                            Some(extended) => {
+                                *expires_at = extended;
+                                let remaining = extended.saturating_duration_since(Instant::now());
+                                // the warnings are sent again for the new end
+                                entry.warned = passed_warnings(&self.expiry_warnings ,  remaining);
+                                Ok((entry.name.clone() ,  remaining))
+                            }
+                            None => Err(SessError::OutOfRange) , 
+                        } , 
+                        None => Err(SessError::Unlimited) , 
+                    } , 
+                    None => Err(SessError::NotFound) , 
+                };
+                if let Ok((name ,  remaining)) = &result {
+                    log::info!(
+                        "{} {} is extended by {:?} ,  {:?} left" , 
+                        name , 
+                        id , 
+                        duration , 
+                        remaining
+                    );
+                    let props = self.store.read().await.props(name);
 
-                let triggers = self.triggers.read().await;
-                if let Some(event_triggers) = triggers.get(&EventKind::InputQualityReport) {
-                    for trigger in event_triggers {
-                        trigger.send((
-                            name.clone() , 
-                            EventMessage::InputQualityReport(id ,  quality ,  props.clone()) , 
-                        ))?;
+                    let triggers = self.triggers.read().await;
+                    if let Some(event_triggers) = triggers.get(&EventKind::ExtendSession) {
+                        for trigger in event_triggers {
+                            trigger.send((
+                                name.clone() , 
+                                EventMessage::ExtendSession(id ,  *remaining ,  props.clone()) , 
+                            ))?;
+                        }
                     }
                 }
+                if responder
+                    .send(result.map(|(_ ,  remaining)| remaining))
+                    .is_err()
+                {
+                    bail!("Failed to send response");
+                }
             }
-            ManageMessage::RegisterTrigger(event ,  trigger) => {
-                log::debug!("Registering trigger for {:?}" ,  event);
-                let mut triggers = self.triggers.write().await;
-                triggers.entry(event).or_insert_with(Vec::new).push(trigger);
+        }
+
+        Ok(())
+    }
+
+    // ends the session and tells the ingests which are still feeding it
+    async fn release_session(&mut self ,  id: SessionId ,  reason: StateReason) -> Result<()> {
+        let entry = match self.sessions.write().await.remove(&id) {
+            Some(entry) => entry , 
+            None => return Ok(()) , 
+        };
+        if entry.handle.send(MediaMessage::EndOfSample).is_err() {
+            log::debug!("session {} has already ended" ,  id);
+        }
+        if entry
+            .ingest
+            .send(IngestMessage::Kick(reason.clone()))
+            .is_err()
+        {
+            log::debug!("ingest {} has already disconnected" ,  entry.owner);
+        }
+        for backup in entry.backups.iter() {
+            if backup
+                .ingest
+                .send(IngestMessage::Kick(reason.clone()))
+                .is_err()
+            {
+                log::debug!("ingest {} has already disconnected" ,  backup.id);
+            }
+        }
+        if self.names.get(&entry.name) == Some(&id) {
+            self.names.remove(&entry.name);
+        }
+        self.ingests.retain(|_ ,  live_id| *live_id != id);
+
+        let props = self.store.read().await.props(&entry.name);
+
+        let triggers = self.triggers.read().await;
+        if let Some(event_triggers) = triggers.get(&EventKind::ReleaseSession) {
+            for trigger in event_triggers {
+                trigger.send((
+                    entry.name.clone() , 
+                    EventMessage::ReleaseSession(id ,  reason.clone() ,  props.clone()) , 
+                ))?;
             }
         }
 
         Ok(())
     }
 
+    // warns the sessions close to their maximum duration and ends the expired ones
+    async fn check_expiry(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut expiring = Vec::new();
+        let mut expired = Vec::new();
+        for (id ,  entry) in self.sessions.write().await.iter_mut() {
+            let remaining = match entry.expires_at {
+                Some(expires_at) => expires_at.saturating_duration_since(now) , 
+                None => continue , 
+            };
+            if remaining == Duration::from_secs(0) {
+                expired.push(*id);
+                continue;
+            }
+            let warned = passed_warnings(&self.expiry_warnings ,  remaining);
+            if warned > entry.warned {
+                entry.warned = warned;
+                expiring.push((*id ,  entry.name.clone() ,  remaining));
+            }
+        }
+
+        for (id ,  name ,  remaining) in expiring {
+            log::info!("{} {} expires in {:?}" ,  name ,  id ,  remaining);
+            let props = self.store.read().await.props(&name);
+
+            let triggers = self.triggers.read().await;
+            if let Some(event_triggers) = triggers.get(&EventKind::ExpiringSession) {
+                for trigger in event_triggers {
+                    trigger.send((
+                        name.clone() , 
+                        EventMessage::ExpiringSession(id ,  remaining ,  props.clone()) , 
+                    ))?;
+                }
+            }
+        }
+        for id in expired {
+            log::info!("session {} reached the maximum duration" ,  id);
+            self.release_session(id ,  StateReason::new(30007 ,  "maximum duration reached"))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // feeds the live session from another ingest ,  the previous one is cut off
+    async fn handover_session(
+        &mut self , 
+        live_id: SessionId , 
+        id: SessionId , 
+        proto: Protocol , 
+        ingest: IngestHandle , 
+    ) -> Option<SessionHandle> {
+        let mut sessions = self.sessions.write().await;
+        let entry = sessions.get_mut(&live_id)?;
+        let (handle ,  incoming) = media_channel(self.queue_size ,  self.overflow_policy);
+        if entry
+            .control
+            .send(ControlMessage::Handover(incoming))
+            .is_err()
+        {
+            return None;
+        }
+        let reason = StateReason::new(30004 ,  "handed over to a new publisher");
+        if entry.ingest.send(IngestMessage::Kick(reason)).is_err() {
+            log::debug!("ingest {} has already disconnected" ,  entry.owner);
+        }
+        self.ingests.remove(&entry.owner);
+        self.ingests.insert(id ,  live_id);
+        entry.protocol = proto;
+        entry.owner = id;
+        entry.ingest = ingest;
+        entry.handle = handle.clone();
+        Some(handle.uplink(0))
+    }
+
+    // feeds the live session from another ingest as well ,  the session picks
+    // one of them
+    async fn add_backup(
+        &mut self , 
+        live_id: SessionId , 
+        id: SessionId , 
+        proto: Protocol , 
+        ingest: IngestHandle , 
+    ) -> Option<SessionHandle> {
+        let mut sessions = self.sessions.write().await;
+        let entry = sessions.get_mut(&live_id)?;
+        let sid_base = entry.uplinks * UPLINK_SIDS;
+        entry.uplinks += 1;
+        entry.backups.push(Backup {
+            id , 
+            protocol: proto , 
+            ingest , 
+            sid_base , 
+        });
+        self.ingests.insert(id ,  live_id);
+        Some(entry.handle.uplink(sid_base))
+    }
+
+    // the session the ingest feeds ,  none unless it is the current owner
+    async fn owned_session(&self ,  id: SessionId) -> Option<SessionId> {
+        let live_id = *self.ingests.get(&id)?;
+        match self.sessions.read().await.get(&live_id) {
+            Some(entry) if entry.owner == id => Some(live_id) , 
+            _ => None , 
+        }
+    }
+
+    // false if the ingest is the last one of the session
+    async fn remove_uplink(&mut self ,  live_id: SessionId ,  id: SessionId) -> bool {
+        let mut sessions = self.sessions.write().await;
+        let entry = match sessions.get_mut(&live_id) {
+            Some(entry) if !entry.backups.is_empty() => entry , 
+            _ => return false , 
+        };
+        if entry.owner == id {
+            // until the session reports the uplink it switched to
+            let backup = entry.backups.remove(0);
+            entry.owner = backup.id;
+            entry.protocol = backup.protocol;
+            entry.ingest = backup.ingest;
+            entry.sid_base = backup.sid_base;
+        } else {
+            entry.backups.retain(|backup| backup.id != id);
+        }
+        true
+    }
+
     pub async fn run(mut self) {
         log::info!("Start session manager");
-        while let Some(message) = self.incoming.recv().await {
-            if let Err(err) = self.process_message(message).await {
+        let mut expiry_check = time::interval(EXPIRY_CHECK_INTERVAL);
+        loop {
+            let message = tokio::select! {
+                message = self.incoming.recv() => Some(message) , 
+                _ = expiry_check.tick() => None , 
+            };
+            let result = match message {
+                Some(Some(message)) => self.process_message(message).await , 
+                Some(None) => break , 
+                None => self.check_expiry().await , 
+            };
+            if let Err(err) = result {
                 log::error!("{}" ,  err);
             };
         }
@@ -350,6 +1003,467 @@ impl SessionManager {
     }
 }
 
+// warnings with a lead time not shorter than the time left are due
+fn passed_warnings(warnings: &[Duration] ,  remaining: Duration) -> usize {
+    warnings
+        .iter()
+        .take_while(|lead| **lead >= remaining)
+        .count()
+}
+
 fn rand_string(len: usize) -> String {
     thread_rng().sample_iter(&Alphanumeric).take(len).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        crate::session::{
+            ingest_channel ,  trigger_channel ,  types::EventWatcher ,  IngestWatcher ,  SessionProps , 
+            Watched , 
+        } , 
+        echo_types::{MediaSample ,  MediaType ,  SampleType ,  Timestamp} , 
+    };
+
+    // an ADTS header and a single byte of payload
+    const ADTS_FRAME: &[u8] = &[0xFF ,  0xF1 ,  0x50 ,  0x80 ,  0x01 ,  0x1F ,  0xFC ,  0x00];
+
+    fn sample(sid: u32) -> MediaMessage {
+        let media_type = MediaType::Audio {
+            sample_rate: 44100 , 
+            channels: 2 , 
+        };
+        MediaMessage::Sample(MediaSample::new(
+            sid , 
+            media_type , 
+            SampleType::AAC , 
+            None , 
+            ADTS_FRAME , 
+        ))
+    }
+
+    async fn create(
+        manager: &ManagerHandle , 
+        id: SessionId , 
+    ) -> Result<(SessionHandle ,  IngestWatcher) ,  SessError> {
+        let (responder ,  response) = oneshot::channel();
+        let (ingest ,  ingest_watcher) = ingest_channel();
+        let reason = StateReason::unknown();
+        let message = ManageMessage::CreateSession(
+            "dj".to_string() , 
+            id , 
+            Protocol::SRT , 
+            None , 
+            reason , 
+            ingest , 
+            responder , 
+        );
+        assert!(manager.send(message).is_ok());
+        let (handle ,  _) = response.await.unwrap()?;
+        Ok((handle ,  ingest_watcher))
+    }
+
+    fn release(manager: &ManagerHandle ,  id: SessionId) {
+        let reason = StateReason::unknown();
+        let message = ManageMessage::ReleaseSession("dj".to_string() ,  id ,  reason);
+        assert!(manager.send(message).is_ok());
+    }
+
+    async fn start(policy: DuplicatePublishPolicy) -> (ManagerHandle ,  EventWatcher) {
+        let config = Config {
+            duplicate_publish_policy: policy , 
+            ..Config::default()
+        };
+        let manager = SessionManager::new(config);
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+
+        let (trigger ,  watcher) = trigger_channel();
+        for kind in [
+            EventKind::CreateSession , 
+            EventKind::ReleaseSession , 
+            EventKind::RejectSession , 
+            EventKind::HandoverSession , 
+            EventKind::SwitchSession , 
+        ]
+        .iter()
+        {
+            let message = ManageMessage::RegisterTrigger(*kind ,  trigger.clone());
+            assert!(handle.send(message).is_ok());
+        }
+        (handle ,  watcher)
+    }
+
+    async fn created(watcher: &mut EventWatcher) -> (SessionId ,  SessionWatcher) {
+        match watcher.recv().await {
+            Some((_ ,  EventMessage::CreateSession(id ,  session_watcher ,  _ ,  _))) => {
+                (id ,  session_watcher)
+            }
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+    }
+
+    async fn next_sid(session_watcher: &mut SessionWatcher) -> u32 {
+        match session_watcher.recv().await {
+            Some(Watched::Sample(sample)) => sample.sid , 
+            _ => panic!("no sample") , 
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_publish_reject() {
+        let (manager ,  mut watcher) = start(DuplicatePublishPolicy::Reject).await;
+        create(&manager ,  1).await.unwrap();
+        assert_eq!(created(&mut watcher).await.0 ,  1);
+
+        assert!(matches!(
+            create(&manager ,  2).await , 
+            Err(SessError::AlreadyPublishing)
+        ));
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::RejectSession(2 ,  _ ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_publish_evict() {
+        let (manager ,  mut watcher) = start(DuplicatePublishPolicy::Evict).await;
+        let (_ ,  mut first_ingest) = create(&manager ,  1).await.unwrap();
+        assert_eq!(created(&mut watcher).await.0 ,  1);
+
+        create(&manager ,  2).await.unwrap();
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(1 ,  _ ,  _)))
+        ));
+        assert!(matches!(
+            first_ingest.recv().await , 
+            Some(IngestMessage::Kick(_))
+        ));
+        assert_eq!(created(&mut watcher).await.0 ,  2);
+
+        // the evicted ingest releasing its session changes nothing
+        release(&manager ,  1);
+        release(&manager ,  2);
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(2 ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_publish_handover() {
+        let (manager ,  mut watcher) = start(DuplicatePublishPolicy::Handover).await;
+        let (first ,  mut first_ingest) = create(&manager ,  1).await.unwrap();
+        let (_ ,  mut session_watcher) = created(&mut watcher).await;
+        first.send(sample(0)).ok().unwrap();
+        assert_eq!(next_sid(&mut session_watcher).await ,  0);
+
+        let (second ,  _) = create(&manager ,  2).await.unwrap();
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::HandoverSession(1 ,  _ ,  reason ,  _))) if reason.code() == 30004
+        ));
+        assert!(matches!(
+            first_ingest.recv().await , 
+            Some(IngestMessage::Kick(_))
+        ));
+        second.send(sample(0)).ok().unwrap();
+        // outputs see the second ingest as a new stream of the same session
+        assert_eq!(next_sid(&mut session_watcher).await ,  1);
+
+        release(&manager ,  1);
+        release(&manager ,  2);
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(1 ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_publish_backup() {
+        let config = Config {
+            duplicate_publish_policy: DuplicatePublishPolicy::Backup , 
+            session_failover_timeout: Duration::from_millis(100) , 
+            ..Config::default()
+        };
+        let manager = SessionManager::new(config);
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        for kind in [
+            EventKind::CreateSession , 
+            EventKind::ReleaseSession , 
+            EventKind::SwitchSession , 
+        ]
+        .iter()
+        {
+            let message = ManageMessage::RegisterTrigger(*kind ,  trigger.clone());
+            assert!(handle.send(message).is_ok());
+        }
+        let timed = |millis: u64| {
+            MediaMessage::Sample(MediaSample::new_aac_audio(
+                0 , 
+                44100 , 
+                2 , 
+                Timestamp::from_millis(millis) , 
+                ADTS_FRAME , 
+            ))
+        };
+        let next_timestamp = |sample: Option<Watched>| match sample {
+            Some(Watched::Sample(sample)) => {
+                assert_eq!(sample.sid ,  0);
+                sample.timestamp.unwrap().as_micros()
+            }
+            _ => panic!("no sample") , 
+        };
+
+        let (primary ,  _primary_ingest) = create(&handle ,  1).await.unwrap();
+        let (_ ,  mut session_watcher) = created(&mut watcher).await;
+        let (backup ,  _backup_ingest) = create(&handle ,  2).await.unwrap();
+        assert!(primary.send(timed(0)).is_ok());
+        assert!(backup.send(timed(5000)).is_ok());
+        assert_eq!(next_timestamp(session_watcher.recv().await) ,  0);
+
+        // the backup takes over from where the primary was last seen
+        time::delay_for(Duration::from_millis(150)).await;
+        assert!(backup.send(timed(5023)).is_ok());
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::SwitchSession(1 ,  2 ,  _ ,  _ ,  _)))
+        ));
+        let first = next_timestamp(session_watcher.recv().await);
+        let second = next_timestamp(session_watcher.recv().await);
+        assert!(first > 0 && second > first);
+
+        // the samples of the starved primary are held back
+        assert!(primary.send(timed(23)).is_ok());
+        assert!(backup.send(timed(5046)).is_ok());
+        assert!(next_timestamp(session_watcher.recv().await) > second);
+
+        // the session ends with the last of its ingests
+        release(&handle ,  2);
+        release(&handle ,  1);
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(1 ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quality_of_evicted() {
+        let (manager ,  mut watcher) = start(DuplicatePublishPolicy::Evict).await;
+        let (trigger ,  mut quality_watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::InputQualityReport ,  trigger);
+        assert!(manager.send(message).is_ok());
+
+        create(&manager ,  1).await.unwrap();
+        assert_eq!(created(&mut watcher).await.0 ,  1);
+        create(&manager ,  2).await.unwrap();
+        for id in [1 ,  2].iter() {
+            let message =
+                ManageMessage::InputQualityReport("dj".to_string() ,  *id ,  Default::default());
+            assert!(manager.send(message).is_ok());
+        }
+        // the evicted ingest is not reported
+        assert!(matches!(
+            quality_watcher.recv().await , 
+            Some((_ ,  EventMessage::InputQualityReport(2 ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pause_of_backup() {
+        let config = Config {
+            duplicate_publish_policy: DuplicatePublishPolicy::Backup , 
+            ..Config::default()
+        };
+        let manager = SessionManager::new(config);
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::PauseSession ,  trigger);
+        assert!(handle.send(message).is_ok());
+
+        let (_primary ,  _primary_ingest) = create(&handle ,  1).await.unwrap();
+        let (_backup ,  _backup_ingest) = create(&handle ,  2).await.unwrap();
+        for id in [3 ,  2 ,  1].iter() {
+            let message =
+                ManageMessage::PauseSession("dj".to_string() ,  *id ,  StateReason::unknown());
+            assert!(handle.send(message).is_ok());
+        }
+        // only the owner pauses the session
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::PauseSession(1 ,  _ ,  _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_kick_session() {
+        let (manager ,  mut watcher) = start(DuplicatePublishPolicy::Evict).await;
+        let (_ ,  mut ingest) = create(&manager ,  1).await.unwrap();
+        assert_eq!(created(&mut watcher).await.0 ,  1);
+
+        let (responder ,  response) = oneshot::channel();
+        let message = ManageMessage::KickSession(1 ,  StateReason::unknown() ,  responder);
+        assert!(manager.send(message).is_ok());
+        assert!(response.await.unwrap().is_ok());
+        assert!(matches!(ingest.recv().await ,  Some(IngestMessage::Kick(_))));
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(1 ,  _ ,  _)))
+        ));
+
+        let (responder ,  response) = oneshot::channel();
+        let message = ManageMessage::KickSession(1 ,  StateReason::unknown() ,  responder);
+        assert!(manager.send(message).is_ok());
+        assert!(matches!(response.await.unwrap() ,  Err(SessError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_watch_session_backlog() {
+        let config = Config {
+            session_backlog: Duration::from_secs(1) , 
+            hls_target_duration: Duration::from_secs(1) , 
+            ..Config::default()
+        };
+        let manager = SessionManager::new(config);
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::CreateSession ,  trigger);
+        assert!(handle.send(message).is_ok());
+
+        let (session ,  _ingest) = create(&handle ,  1).await.unwrap();
+        let (_ ,  mut session_watcher) = created(&mut watcher).await;
+        let timed = |sid: u32| {
+            let timestamp = Timestamp::from_millis(sid as u64 * 500);
+            MediaMessage::Sample(MediaSample::new_aac_audio(
+                sid ,  44100 ,  2 ,  timestamp ,  ADTS_FRAME , 
+            ))
+        };
+        for sid in 0..4 {
+            assert!(session.send(timed(sid)).is_ok());
+            assert_eq!(next_sid(&mut session_watcher).await ,  sid);
+        }
+
+        // only the last second is replayed from the start of a segment ,  then
+        // the live samples follow
+        let (responder ,  response) = oneshot::channel();
+        assert!(handle
+            .send(ManageMessage::WatchSession(1 ,  responder))
+            .is_ok());
+        let mut late_watcher = response.await.unwrap().unwrap();
+        assert!(session.send(timed(4)).is_ok());
+        for sid in 2..5 {
+            assert_eq!(next_sid(&mut late_watcher).await ,  sid);
+        }
+
+        let (responder ,  response) = oneshot::channel();
+        assert!(handle
+            .send(ManageMessage::WatchSession(2 ,  responder))
+            .is_ok());
+        assert!(matches!(response.await.unwrap() ,  Err(SessError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_max_duration() {
+        let config = Config {
+            session_max_duration: Duration::from_secs(1) , 
+            session_expiry_warnings: Some("0.5".to_string()) , 
+            ..Config::default()
+        };
+        let manager = SessionManager::new(config);
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        for kind in [
+            EventKind::ExpiringSession , 
+            EventKind::ExtendSession , 
+            EventKind::ReleaseSession , 
+        ]
+        .iter()
+        {
+            let message = ManageMessage::RegisterTrigger(*kind ,  trigger.clone());
+            assert!(handle.send(message).is_ok());
+        }
+
+        let (_session ,  mut ingest) = create(&handle ,  1).await.unwrap();
+        let (responder ,  response) = oneshot::channel();
+        let message = ManageMessage::ExtendSession(1 ,  Duration::from_secs(u64::MAX) ,  responder);
+        assert!(handle.send(message).is_ok());
+        assert!(matches!(response.await.unwrap() ,  Err(SessError::OutOfRange)));
+
+        let (responder ,  response) = oneshot::channel();
+        let message = ManageMessage::ExtendSession(1 ,  Duration::from_millis(500) ,  responder);
+        assert!(handle.send(message).is_ok());
+        assert!(response.await.unwrap().unwrap() > Duration::from_secs(1));
+
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ExtendSession(1 ,  _ ,  _)))
+        ));
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ExpiringSession(1 ,  _ ,  _)))
+        ));
+        assert!(matches!(
+            watcher.recv().await , 
+            Some((_ ,  EventMessage::ReleaseSession(1 ,  _ ,  _)))
+        ));
+        assert!(matches!(ingest.recv().await ,  Some(IngestMessage::Kick(_))));
+    }
+
+    #[tokio::test]
+    async fn test_patch_session_props() {
+        let manager = SessionManager::new(Config::default());
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::PropsChanged ,  trigger);
+        assert!(handle.send(message).is_ok());
+
+        let props: SessionProps = vec![("stage" ,  "ready") ,  ("country" ,  "kr")]
+            .into_iter()
+            .map(|(key ,  value)| (key.to_string() ,  value.to_string()))
+            .collect();
+        let message = ManageMessage::UpdateSessionProps("dj".to_string() ,  props);
+        assert!(handle.send(message).is_ok());
+        let _session = create(&handle ,  1).await.unwrap();
+
+        let patch = |patch: Vec<(&str ,  Option<&str>)>| {
+            let patch = patch
+                .into_iter()
+                .map(|(key ,  value)| (key.to_string() ,  value.map(String::from)))
+                .collect();
+            let (responder ,  response) = oneshot::channel();
+            let message = ManageMessage::PatchSessionProps(1 ,  patch ,  responder);
+            assert!(handle.send(message).is_ok());
+            response
+        };
+        let props = patch(vec![("stage" ,  Some("live")) ,  ("country" ,  None)])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(props.get("stage").map(String::as_str) ,  Some("live"));
+        assert!(!props.contains_key("country"));
+        match watcher.recv().await {
+            Some((_ ,  EventMessage::PropsChanged(1 ,  changed ,  _))) => {
+                assert_eq!(changed ,  vec!["country" ,  "stage"])
+            }
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+
+        // nothing is changed
+        assert!(patch(vec![("stage" ,  Some("live"))]).await.unwrap().is_ok());
+        release(&handle ,  1);
+        assert!(matches!(
+            patch(vec![("stage" ,  Some("end"))]).await.unwrap() , 
+            Err(SessError::NotFound)
+        ));
+        assert!(watcher.try_recv().is_err());
+    }
+}
@@ -0,0 +1,296 @@
+use {
+    super::types::MediaMessage , 
+    crate::config::OverflowPolicy , 
+    echo_types::MediaSample , 
+    std::{
+        collections::VecDeque , 
+        fmt , 
+        sync::{
+            atomic::{AtomicBool ,  AtomicU64 ,  AtomicUsize ,  Ordering} , 
+            Arc ,  Mutex , 
+        } , 
+    } , 
+    thiserror::Error , 
+    tokio::sync::{
+        broadcast::{self ,  RecvError} , 
+        Notify , 
+    } , 
+};
+
+#[derive(Error ,  Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub enum SendError {
+    #[error("session closed")]
+    Closed , 
+
+    #[error("session queue full")]
+    Full , 
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<MediaMessage>> , 
+    notify: Notify , 
+    capacity: usize , 
+    policy: OverflowPolicy , 
+    senders: AtomicUsize , 
+    closed: AtomicBool , 
+    dropped: AtomicU64 , 
+}
+
+/// Sends the samples of an ingest to its session ,  at most `capacity` of them
+/// are queued and the rest is handled by the overflow policy.
+pub struct SessionHandle {
+    shared: Arc<Shared> , 
+    // the first sid of the ingest ,  none for the handle of the session itself
+    uplink: Option<u32> , 
+}
+
+impl SessionHandle {
+    pub fn send(&self ,  message: MediaMessage) -> Result<() ,  SendError> {
+        if self.shared.closed.load(Ordering::SeqCst) {
+            return Err(SendError::Closed);
+        }
+        let message = match (message ,  self.uplink) {
+            (MediaMessage::Sample(mut sample) ,  Some(sid_base)) => {
+                sample.sid += sid_base;
+                MediaMessage::Sample(sample)
+            }
+            // an ingest only ends its own uplink
+            (MediaMessage::EndOfSample ,  Some(sid_base)) => MediaMessage::EndOfUplink(sid_base) , 
+            (message ,  _) => message , 
+        };
+        {
+            let mut queue = self.shared.queue.lock().unwrap();
+            // the end of samples is never dropped ,  nor is it evicted
+            if queue.len() >= self.shared.capacity {
+                if let MediaMessage::Sample(_) = message {
+                    match self.shared.policy {
+                        OverflowPolicy::DropOldest => {
+                            let oldest = queue
+                                .iter()
+                                .position(|message| matches!(message ,  MediaMessage::Sample(_)));
+                            if let Some(index) = oldest {
+                                queue.remove(index);
+                                self.shared.dropped.fetch_add(1 ,  Ordering::SeqCst);
+                            }
+                        }
+                        OverflowPolicy::Disconnect => {
+                            self.shared.dropped.fetch_add(1 ,  Ordering::SeqCst);
+                            return Err(SendError::Full);
+                        }
+                    }
+                }
+            }
+            queue.push_back(message);
+        }
+        self.shared.notify.notify();
+        Ok(())
+    }
+
+    /// Samples dropped by the overflow policy so far.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::SeqCst)
+    }
+
+    /// A handle for an ingest ,  its sids are counted from `sid_base`.
+    pub(super) fn uplink(&self ,  sid_base: u32) -> Self {
+        let mut handle = self.clone();
+        handle.uplink = Some(sid_base);
+        handle
+    }
+}
+
+impl Clone for SessionHandle {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1 ,  Ordering::SeqCst);
+        Self {
+            shared: self.shared.clone() , 
+            uplink: self.uplink , 
+        }
+    }
+}
+
+impl Drop for SessionHandle {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1 ,  Ordering::SeqCst) == 1 {
+            // wake up the receiver to close
+            self.shared.notify.notify();
+        }
+    }
+}
+
+pub(super) struct MediaReceiver {
+    shared: Arc<Shared> , 
+}
+
+impl MediaReceiver {
+    /// `None` once every handle is dropped and the queue is empty.
+    pub(super) async fn recv(&mut self) -> Option<MediaMessage> {
+        loop {
+            let message = self.shared.queue.lock().unwrap().pop_front();
+            if message.is_some() {
+                return message;
+            }
+            if self.shared.senders.load(Ordering::SeqCst) == 0 {
+                return None;
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for MediaReceiver {
+    fn drop(&mut self) {
+        self.shared.closed.store(true ,  Ordering::SeqCst);
+    }
+}
+
+pub(super) fn media_channel(capacity: usize ,  policy: OverflowPolicy) -> (SessionHandle ,  MediaReceiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(capacity)) , 
+        notify: Notify::new() , 
+        capacity , 
+        policy , 
+        senders: AtomicUsize::new(1) , 
+        closed: AtomicBool::new(false) , 
+        dropped: AtomicU64::new(0) , 
+    });
+    let handle = SessionHandle {
+        shared: shared.clone() , 
+        uplink: None , 
+    };
+    (handle ,  MediaReceiver { shared })
+}
+
+pub enum Watched {
+    Sample(MediaSample) , 
+    // the watcher fell behind and this many samples were skipped
+    Gap(u64) , 
+}
+
+/// Receives the samples of a session ,  a watcher which falls behind skips to
+/// the oldest sample still available instead of being closed.
+pub struct SessionWatcher {
+    // recent samples from before the watcher joined ,  received first
+    backlog: VecDeque<MediaSample> , 
+    receiver: broadcast::Receiver<MediaSample> , 
+}
+
+impl fmt::Debug for SessionWatcher {
+    fn fmt(&self ,  f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionWatcher")
+            .field("backlog" ,  &self.backlog.len())
+            .finish()
+    }
+}
+
+impl SessionWatcher {
+    pub(super) fn new(receiver: broadcast::Receiver<MediaSample>) -> Self {
+        Self::with_backlog(VecDeque::new() ,  receiver)
+    }
+
+    pub(super) fn with_backlog(
+        backlog: VecDeque<MediaSample> , 
+        receiver: broadcast::Receiver<MediaSample> , 
+    ) -> Self {
+        Self { backlog ,  receiver }
+    }
+
+    /// `None` once the session is closed.
+    pub async fn recv(&mut self) -> Option<Watched> {
+        if let Some(sample) = self.backlog.pop_front() {
+            return Some(Watched::Sample(sample));
+        }
+        match self.receiver.recv().await {
+            Ok(sample) => Some(Watched::Sample(sample)) , 
+            Err(RecvError::Lagged(skipped)) => Some(Watched::Gap(skipped)) , 
+            Err(RecvError::Closed) => None , 
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        echo_types::{MediaSample ,  MediaType ,  SampleType} , 
+    };
+
+    fn sample(sid: u32) -> MediaMessage {
+        let media_type = MediaType::Audio {
+            sample_rate: 44100 , 
+            channels: 2 , 
+        };
+        MediaMessage::Sample(MediaSample::new(sid ,  media_type ,  SampleType::AAC ,  None ,  vec![0u8]))
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let (handle ,  mut receiver) = media_channel(2 ,  OverflowPolicy::DropOldest);
+        for sid in 0..4 {
+            assert!(handle.send(sample(sid)).is_ok());
+        }
+        assert!(handle.send(MediaMessage::EndOfSample).is_ok());
+        assert_eq!(handle.dropped() ,  2);
+        drop(handle);
+
+        let mut sids = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            if let MediaMessage::Sample(sample) = message {
+                sids.push(sample.sid);
+            }
+        }
+        assert_eq!(sids ,  vec![2 ,  3]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_keeps_end() {
+        let (handle ,  mut receiver) = media_channel(2 ,  OverflowPolicy::DropOldest);
+        let uplink = handle.uplink(1000);
+        assert!(handle.send(sample(0)).is_ok());
+        assert!(uplink.send(MediaMessage::EndOfSample).is_ok());
+        assert!(handle.send(MediaMessage::EndOfSample).is_ok());
+        assert!(handle.send(sample(1)).is_ok());
+        assert!(handle.send(sample(2)).is_ok());
+        assert_eq!(handle.dropped() ,  2);
+        drop(handle);
+        drop(uplink);
+
+        let mut messages = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            messages.push(match message {
+                MediaMessage::Sample(sample) => format!("sample {}" ,  sample.sid) , 
+                MediaMessage::EndOfUplink(sid) => format!("end of uplink {}" ,  sid) , 
+                MediaMessage::EndOfSample => "end".to_string() , 
+            });
+        }
+        assert_eq!(messages ,  vec!["end of uplink 1000" ,  "end" ,  "sample 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_disconnect() {
+        let (handle ,  receiver) = media_channel(1 ,  OverflowPolicy::Disconnect);
+        assert!(handle.send(sample(0)).is_ok());
+        assert_eq!(handle.send(sample(1)).err() ,  Some(SendError::Full));
+        assert_eq!(handle.dropped() ,  1);
+
+        drop(receiver);
+        assert_eq!(handle.send(sample(2)).err() ,  Some(SendError::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_gap() {
+        let (outgoing ,  receiver) = broadcast::channel(2);
+        let mut watcher = SessionWatcher::new(receiver);
+        for sid in 0..4 {
+            if let MediaMessage::Sample(sample) = sample(sid) {
+                assert!(outgoing.send(sample).is_ok());
+            }
+        }
+        drop(outgoing);
+
+        assert!(matches!(watcher.recv().await ,  Some(Watched::Gap(2))));
+        assert!(matches!(watcher.recv().await ,  Some(Watched::Sample(sample)) if sample.sid == 2));
+        assert!(matches!(watcher.recv().await ,  Some(Watched::Sample(sample)) if sample.sid == 3));
+        assert!(watcher.recv().await.is_none());
+    }
+}
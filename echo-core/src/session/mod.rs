@@ -1,6 +1,11 @@
 mod error;
+pub mod filter;
 mod instance;
 pub mod manager;
+mod media;
+mod output;
+mod shutdown;
+mod store;
 mod types;
 
 use std::collections::HashMap;
@@ -8,6 +13,9 @@ use std::collections::HashMap;
 pub type AppName = String;
 pub type SessionId = u64;
 
+// the sids of an ingest ,  its connections count up from the first one
+const UPLINK_SIDS: u32 = 1 << 16;
+
 pub static SPROP_CLIENT_IP: &str = "client_ip";
 pub static SPROP_COUNTRY: &str = "country";
 pub static SPROP_LIVE_ID: &str = "live_id";
@@ -16,13 +24,24 @@ pub static SPROP_STAGE: &str = "stage";
 pub static SPROP_SDK_VERSION: &str = "sdk_version";
 pub static SPROP_OS: &str = "os";
 pub static SPROP_MODEL_NAME: &str = "model_name";
+// turn an output on or off for the session ,  "true" or "false"
+pub static SPROP_HLS: &str = "hls";
+pub static SPROP_RECORD: &str = "record";
 pub type SessionProps = HashMap<String ,  String>;
 
 pub use self::{
     error::Error , 
+    filter::{FilterFactory ,  MediaFilter} , 
     manager::{IdGenerator ,  SessionManager} , 
+    media::{SendError ,  SessionHandle ,  SessionWatcher ,  Watched} , 
+    output::{
+        OutputFactory ,  OutputKind ,  OutputRegistry ,  OutputReporter ,  OutputSink ,  OutputStatus , 
+    } , 
+    shutdown::{OutputGuard ,  OutputWaiter} , 
+    store::{FileStore ,  MemoryStore ,  SessionStore} , 
     types::{
-        trigger_channel ,  EventKind ,  EventMessage ,  InputQuality ,  ManageMessage ,  ManagerHandle , 
-        MediaMessage ,  SessionHandle ,  SessionWatcher ,  StateReason , 
+        ingest_channel ,  trigger_channel ,  EventKind ,  EventMessage ,  IngestHandle ,  IngestMessage , 
+        IngestWatcher ,  InputQuality ,  LinkQuality ,  ManageMessage ,  ManagerHandle ,  MediaMessage , 
+        SessionInfo ,  StateReason , 
     } , 
 };
@@ -0,0 +1,575 @@
+use {
+    super::{
+        trigger_channel ,  types::EventWatcher ,  AppName ,  EventKind ,  EventMessage ,  ManageMessage , 
+        ManagerHandle ,  OutputGuard ,  SessionId ,  SessionProps ,  SessionWatcher ,  StateReason ,  Watched , 
+    } , 
+    anyhow::Result , 
+    echo_types::{async_trait ,  MediaSample} , 
+    serde::Serialize , 
+    std::{collections::HashMap ,  fmt ,  path::PathBuf ,  time::Duration} , 
+    tokio::{sync::mpsc ,  time} , 
+};
+
+// how often a paused output is told the session is still paused
+const PAUSED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Names an output in events ,  e.g. `hls` or `record`.
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq ,  Hash ,  Serialize)]
+#[serde(transparent)]
+pub struct OutputKind(pub &'static str);
+
+impl OutputKind {
+    pub const HLS: OutputKind = OutputKind("hls");
+    pub const RECORD: OutputKind = OutputKind("record");
+
+    /// Whether a session gets this output ,  the session prop named after the
+    /// kind overrides `default`. `None` if the prop is neither "true" nor "false".
+    pub fn selected(&self ,  props: Option<&SessionProps> ,  default: bool) -> Option<bool> {
+        match props.and_then(|props| props.get(self.0)) {
+            Some(value) => value.parse().ok() , 
+            None => Some(default) , 
+        }
+    }
+}
+
+impl fmt::Display for OutputKind {
+    fn fmt(&self ,  f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+// serialized without the status ,  which is told by `name`
+#[derive(Debug ,  Clone ,  Serialize)]
+#[serde(untagged)]
+pub enum OutputStatus {
+    // the first sample was accepted
+    Started , 
+    // the output can be played ,  e.g. the first HLS segment is written
+    Ready { path: String } , 
+    // the output fell behind the session and skipped samples
+    Lagged { skipped: u64 } , 
+    Completed { path: PathBuf ,  size: u64 } , 
+    // nothing is left of the output
+    Released , 
+    Failed { reason: String } , 
+}
+
+impl OutputStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputStatus::Started => "started" , 
+            OutputStatus::Ready { .. } => "ready" , 
+            OutputStatus::Lagged { .. } => "lagged" , 
+            OutputStatus::Completed { .. } => "completed" , 
+            OutputStatus::Released => "released" , 
+            OutputStatus::Failed { .. } => "failed" , 
+        }
+    }
+}
+
+/// Reports the status of an output of a session to the session manager.
+#[derive(Debug ,  Clone)]
+pub struct OutputReporter {
+    name: AppName , 
+    id: SessionId , 
+    kind: OutputKind , 
+    session_manager: ManagerHandle , 
+}
+
+impl OutputReporter {
+    pub fn new(
+        name: AppName , 
+        id: SessionId , 
+        kind: OutputKind , 
+        session_manager: ManagerHandle , 
+    ) -> Self {
+        Self {
+            name , 
+            id , 
+            kind , 
+            session_manager , 
+        }
+    }
+
+    pub fn report(&self ,  status: OutputStatus) {
+        let message = ManageMessage::OutputStatus(self.name.clone() ,  self.id ,  self.kind ,  status);
+        if self.session_manager.send(message).is_err() {
+            log::error!("Failed to report {} output of {}" ,  self.kind ,  self.name);
+        }
+    }
+}
+
+/// An output of a single session.
+#[async_trait]
+pub trait OutputSink: Send {
+    /// Called with the first sample ,  before it is passed to `sample`.
+    async fn start(&mut self ,  sample: &MediaSample) -> Result<()>;
+
+    async fn sample(&mut self ,  sample: MediaSample) -> Result<()>;
+
+    /// Samples were skipped because the output fell behind the session.
+    async fn gap(&mut self ,  _skipped: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// The publisher has left ,  no samples arrive until the session is resumed.
+    async fn pause(&mut self ,  _reason: &StateReason) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called every second while the session is paused.
+    async fn paused(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn resume(&mut self ,  _reason: &StateReason) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the session ends if the output was started ,  `shutdown` is
+    /// set when the server is going away.
+    async fn stop(&mut self ,  shutdown: bool) -> Result<()>;
+}
+
+/// Makes an output for every new session.
+#[async_trait]
+pub trait OutputFactory: Send {
+    fn kind(&self) -> OutputKind;
+
+    /// Whether sessions get the output when their props don't tell.
+    fn default_selected(&self) -> bool {
+        true
+    }
+
+    /// Called once before the first session.
+    async fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        &mut self , 
+        name: &AppName , 
+        id: SessionId , 
+        reporter: OutputReporter , 
+    ) -> Result<Box<dyn OutputSink>>;
+}
+
+/// Runs an output of every registered kind for each session.
+pub struct OutputRegistry {
+    session_manager: ManagerHandle , 
+    factories: Vec<(Box<dyn OutputFactory> ,  EventWatcher)> , 
+}
+
+impl OutputRegistry {
+    pub fn new(session_manager: ManagerHandle) -> Self {
+        Self {
+            session_manager , 
+            factories: Vec::new() , 
+        }
+    }
+
+    /// Sessions created from now on get an output of this kind ,  they wait in
+    /// a queue until the registry runs.
+    pub fn register(&mut self ,  factory: Box<dyn OutputFactory>) {
+        // every kind has its own trigger to get a watcher of its own
+        let (trigger ,  trigger_watcher) = trigger_channel();
+        for kind in &[
+            EventKind::CreateSession , 
+            EventKind::PauseSession , 
+            EventKind::ResumeSession , 
+            EventKind::ReleaseSession , 
+        ] {
+            if self
+                .session_manager
+                .send(ManageMessage::RegisterTrigger(*kind ,  trigger.clone()))
+                .is_err()
+            {
+                log::error!("Failed to register {:?} trigger" ,  kind);
+                panic!("Failed to register {:?} trigger" ,  kind);
+            }
+        }
+        self.factories.push((factory ,  trigger_watcher));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factories.is_empty()
+    }
+
+    pub async fn run(self) {
+        let session_manager = self.session_manager;
+        let handles: Vec<_> = self
+            .factories
+            .into_iter()
+            .map(|(factory ,  trigger_watcher)| {
+                tokio::spawn(run_factory(
+                    factory , 
+                    trigger_watcher , 
+                    session_manager.clone() , 
+                ))
+            })
+            .collect();
+        for handle in handles {
+            if let Err(err) = handle.await {
+                log::error!("{}" ,  err);
+            }
+        }
+    }
+}
+
+// what the session tells its outputs besides the samples
+enum SinkEvent {
+    Pause(StateReason) , 
+    Resume(StateReason) , 
+}
+
+async fn run_factory(
+    mut factory: Box<dyn OutputFactory> , 
+    mut trigger_watcher: EventWatcher , 
+    session_manager: ManagerHandle , 
+) {
+    let kind = factory.kind();
+    if let Err(err) = factory.init().await {
+        log::error!("Failed to initialize {} output: {:?}" ,  kind ,  err);
+        panic!("Failed to initialize {} output" ,  kind);
+    }
+
+    // the running sinks by their session
+    let mut sinks: HashMap<SessionId ,  mpsc::UnboundedSender<SinkEvent>> = HashMap::new();
+    while let Some((name ,  event)) = trigger_watcher.recv().await {
+        let (id ,  event) = match event {
+            EventMessage::CreateSession(id ,  session_watcher ,  output_guard ,  props) => {
+                let default = factory.default_selected();
+                let selected = kind.selected(props.as_ref() ,  default).unwrap_or_else(|| {
+                    log::warn!(
+                        "{} {} invalid {} prop ,  {} by default" , 
+                        name , 
+                        id , 
+                        kind , 
+                        default
+                    );
+                    default
+                });
+                if !selected {
+                    log::info!("{} {} {} output is turned off" ,  name ,  id ,  kind);
+                    continue;
+                }
+                let reporter =
+                    OutputReporter::new(name.clone() ,  id ,  kind ,  session_manager.clone());
+                match factory.create(&name ,  id ,  reporter.clone()).await {
+                    Ok(sink) => {
+                        let (event_sender ,  events) = mpsc::unbounded_channel();
+                        sinks.insert(id ,  event_sender);
+                        tokio::spawn(run_sink(
+                            sink , 
+                            session_watcher , 
+                            events , 
+                            output_guard , 
+                            reporter , 
+                        ));
+                    }
+                    Err(err) => log::error!(
+                        "{} {} failed to create {} output: {:?}" , 
+                        name , 
+                        id , 
+                        kind , 
+                        err
+                    ) , 
+                }
+                continue;
+            }
+            EventMessage::PauseSession(id ,  reason ,  _) => (id ,  SinkEvent::Pause(reason)) , 
+            EventMessage::ResumeSession(id ,  reason ,  _) => (id ,  SinkEvent::Resume(reason)) , 
+            EventMessage::ReleaseSession(id ,  _ ,  _) => {
+                sinks.remove(&id);
+                continue;
+            }
+            _ => continue , 
+        };
+        if let Some(sender) = sinks.get(&id) {
+            if sender.send(event).is_err() {
+                // the sink has already finished
+                sinks.remove(&id);
+            }
+        }
+    }
+}
+
+async fn run_sink(
+    mut sink: Box<dyn OutputSink> , 
+    mut session_watcher: SessionWatcher , 
+    mut events: mpsc::UnboundedReceiver<SinkEvent> , 
+    output_guard: OutputGuard , 
+    reporter: OutputReporter , 
+) {
+    let mut started = false;
+    let mut paused = false;
+    let mut paused_interval = time::interval(PAUSED_INTERVAL);
+    loop {
+        tokio::select! {
+            watched = session_watcher.recv() => match watched {
+                Some(Watched::Sample(sample)) => {
+                    if !started {
+                        if let Err(err) = sink.start(&sample).await {
+                            log::error!(
+                                "{} {} failed to start {} output: {:?}" , 
+                                reporter.name , 
+                                reporter.id , 
+                                reporter.kind , 
+                                err
+                            );
+                            reporter.report(OutputStatus::Failed {
+                                reason: err.to_string() , 
+                            });
+                            return;
+                        }
+                        started = true;
+                        reporter.report(OutputStatus::Started);
+                    }
+                    if let Err(err) = sink.sample(sample).await {
+                        log::error!("{:?}" ,  err);
+                    }
+                }
+                Some(Watched::Gap(skipped)) => {
+                    log::warn!(
+                        "{} {} {} output skipped {} samples" , 
+                        reporter.name , 
+                        reporter.id , 
+                        reporter.kind , 
+                        skipped
+                    );
+                    reporter.report(OutputStatus::Lagged { skipped });
+                    if started {
+                        if let Err(err) = sink.gap(skipped).await {
+                            log::error!("{:?}" ,  err);
+                        }
+                    }
+                }
+                None => break , 
+            } , 
+            Some(event) = events.recv() => {
+                let res = match event {
+                    SinkEvent::Pause(reason) => {
+                        paused = true;
+                        if started {
+                            sink.pause(&reason).await
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    SinkEvent::Resume(reason) => {
+                        paused = false;
+                        if started {
+                            sink.resume(&reason).await
+                        } else {
+                            Ok(())
+                        }
+                    }
+                };
+                if let Err(err) = res {
+                    log::error!("{:?}" ,  err);
+                }
+            }
+            _ = paused_interval.tick() ,  if paused && started => {
+                if let Err(err) = sink.paused().await {
+                    log::error!("{:?}" ,  err);
+                }
+            }
+        }
+    }
+
+    if started {
+        if let Err(err) = sink.stop(output_guard.is_shutting_down()).await {
+            log::error!(
+                "{} {} failed to stop {} output: {:?}" , 
+                reporter.name , 
+                reporter.id , 
+                reporter.kind , 
+                err
+            );
+            reporter.report(OutputStatus::Failed {
+                reason: err.to_string() , 
+            });
+        }
+    }
+    // the shutdown waits for the sink to be flushed
+    drop(output_guard);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        crate::{
+            session::{
+                ingest_channel ,  MediaMessage ,  SessionManager ,  StateReason ,  SPROP_HLS ,  SPROP_RECORD , 
+            } , 
+            Config , 
+        } , 
+        echo_types::{Protocol ,  Timestamp} , 
+        tokio::{sync::oneshot ,  time::delay_for} , 
+    };
+
+    const ADTS_FRAME: &[u8] = &[0xFF ,  0xF1 ,  0x50 ,  0x80 ,  0x01 ,  0x1F ,  0xFC ,  0x00];
+    const TEST: OutputKind = OutputKind("test");
+
+    struct CountingSink {
+        count: u64 , 
+        paused: bool , 
+        reporter: OutputReporter , 
+    }
+
+    #[async_trait]
+    impl OutputSink for CountingSink {
+        async fn start(&mut self ,  _sample: &MediaSample) -> Result<()> {
+            Ok(())
+        }
+
+        async fn sample(&mut self ,  _sample: MediaSample) -> Result<()> {
+            self.count += 1;
+            Ok(())
+        }
+
+        async fn pause(&mut self ,  _reason: &StateReason) -> Result<()> {
+            self.paused = true;
+            Ok(())
+        }
+
+        async fn stop(&mut self ,  _shutdown: bool) -> Result<()> {
+            self.reporter.report(OutputStatus::Completed {
+                path: PathBuf::from(if self.paused { "paused" } else { "test" }) , 
+                size: self.count , 
+            });
+            Ok(())
+        }
+    }
+
+    struct CountingFactory;
+
+    #[async_trait]
+    impl OutputFactory for CountingFactory {
+        fn kind(&self) -> OutputKind {
+            TEST
+        }
+
+        async fn create(
+            &mut self , 
+            _name: &AppName , 
+            _id: SessionId , 
+            reporter: OutputReporter , 
+        ) -> Result<Box<dyn OutputSink>> {
+            Ok(Box::new(CountingSink {
+                count: 0 , 
+                paused: false , 
+                reporter , 
+            }))
+        }
+    }
+
+    #[test]
+    fn test_selected() {
+        let mut props = SessionProps::new();
+        props.insert(SPROP_RECORD.to_string() ,  "false".to_string());
+        props.insert(SPROP_HLS.to_string() ,  "yes".to_string());
+        assert_eq!(OutputKind::RECORD.selected(Some(&props) ,  true) ,  Some(false));
+        assert_eq!(OutputKind::HLS.selected(Some(&props) ,  true) ,  None);
+        assert_eq!(TEST.selected(Some(&props) ,  false) ,  Some(false));
+        assert_eq!(TEST.selected(None ,  true) ,  Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_registry() {
+        let manager = SessionManager::new(Config::default());
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::OutputStatus ,  trigger);
+        assert!(handle.send(message).is_ok());
+
+        let mut outputs = OutputRegistry::new(handle.clone());
+        outputs.register(Box::new(CountingFactory));
+        tokio::spawn(outputs.run());
+
+        let (responder ,  response) = oneshot::channel();
+        let (ingest ,  _ingest_watcher) = ingest_channel();
+        let message = ManageMessage::CreateSession(
+            "dj".to_string() , 
+            1 , 
+            Protocol::SRT , 
+            None , 
+            StateReason::unknown() , 
+            ingest , 
+            responder , 
+        );
+        assert!(handle.send(message).is_ok());
+        let (session ,  _) = response.await.unwrap().unwrap();
+        for sid in 0..3 {
+            let timestamp = Timestamp::from_millis(sid as u64 * 21);
+            let sample = MediaSample::new_aac_audio(sid ,  44100 ,  2 ,  timestamp ,  ADTS_FRAME);
+            assert!(session.send(MediaMessage::Sample(sample)).is_ok());
+        }
+        assert!(session.send(MediaMessage::EndOfSample).is_ok());
+
+        match watcher.recv().await {
+            Some((_ ,  EventMessage::OutputStatus(1 ,  TEST ,  OutputStatus::Started ,  _))) => {}
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+        match watcher.recv().await {
+            Some((
+                _ , 
+                EventMessage::OutputStatus(1 ,  TEST ,  OutputStatus::Completed { size ,  .. } ,  _) , 
+            )) => {
+                assert_eq!(size ,  3)
+            }
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_pause() {
+        let manager = SessionManager::new(Config::default());
+        let handle = manager.handle();
+        tokio::spawn(manager.run());
+        let (trigger ,  mut watcher) = trigger_channel();
+        let message = ManageMessage::RegisterTrigger(EventKind::OutputStatus ,  trigger);
+        assert!(handle.send(message).is_ok());
+
+        let mut outputs = OutputRegistry::new(handle.clone());
+        outputs.register(Box::new(CountingFactory));
+        tokio::spawn(outputs.run());
+
+        let (responder ,  response) = oneshot::channel();
+        let (ingest ,  _ingest_watcher) = ingest_channel();
+        let message = ManageMessage::CreateSession(
+            "dj".to_string() , 
+            1 , 
+            Protocol::SRT , 
+            None , 
+            StateReason::unknown() , 
+            ingest , 
+            responder , 
+        );
+        assert!(handle.send(message).is_ok());
+        let (session ,  _) = response.await.unwrap().unwrap();
+        let sample = MediaSample::new_aac_audio(0 ,  44100 ,  2 ,  Timestamp::from_millis(0) ,  ADTS_FRAME);
+        assert!(session.send(MediaMessage::Sample(sample)).is_ok());
+        match watcher.recv().await {
+            Some((_ ,  EventMessage::OutputStatus(1 ,  TEST ,  OutputStatus::Started ,  _))) => {}
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+
+        let message = ManageMessage::PauseSession("dj".to_string() ,  1 ,  StateReason::unknown());
+        assert!(handle.send(message).is_ok());
+        delay_for(Duration::from_millis(100)).await;
+        assert!(session.send(MediaMessage::EndOfSample).is_ok());
+
+        match watcher.recv().await {
+            Some((
+                _ , 
+                EventMessage::OutputStatus(1 ,  TEST ,  OutputStatus::Completed { path ,  .. } ,  _) , 
+            )) => {
+                assert_eq!(path ,  PathBuf::from("paused"))
+            }
+            other => panic!("unexpected {:?}" ,  other) , 
+        }
+    }
+}
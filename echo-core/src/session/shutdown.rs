@@ -0,0 +1,43 @@
+use {
+    std::sync::{
+        atomic::{AtomicBool ,  Ordering} , 
+        Arc , 
+    } , 
+    tokio::sync::mpsc , 
+};
+
+/// Held by an output (HLS ,  recording) until it has flushed everything.
+#[derive(Debug ,  Clone)]
+pub struct OutputGuard {
+    _guard: mpsc::Sender<()> , 
+    shutting_down: Arc<AtomicBool> , 
+}
+
+impl OutputGuard {
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+/// Resolves once every `OutputGuard` has been dropped.
+pub struct OutputWaiter {
+    receiver: mpsc::Receiver<()> , 
+}
+
+impl OutputWaiter {
+    pub async fn wait(mut self) {
+        // nothing is sent ,  it only ends when all the senders are gone
+        while self.receiver.recv().await.is_some() {}
+    }
+}
+
+pub(super) fn output_guard(shutting_down: Arc<AtomicBool>) -> (OutputGuard ,  OutputWaiter) {
+    let (sender ,  receiver) = mpsc::channel(1);
+    (
+        OutputGuard {
+            _guard: sender , 
+            shutting_down , 
+        } , 
+        OutputWaiter { receiver } , 
+    )
+}
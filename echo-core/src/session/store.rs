@@ -0,0 +1,331 @@
+use {
+    super::{AppName ,  SessionProps} , 
+    anyhow::{anyhow ,  Result} , 
+    lru_time_cache::LruCache , 
+    serde::{Deserialize ,  Serialize} , 
+    std::{
+        collections::HashMap , 
+        fs::{self ,  File ,  OpenOptions} , 
+        io::{BufRead ,  BufReader ,  Write} , 
+        path::{Path ,  PathBuf} , 
+        sync::mpsc , 
+        time::{Duration ,  SystemTime ,  UNIX_EPOCH} , 
+    } , 
+    tokio::task::{self ,  JoinHandle} , 
+};
+
+/// Where stream keys and session props are kept between publish and session creation.
+pub trait SessionStore: Send + Sync {
+    fn key(&self ,  name: &str) -> Option<(String ,  SystemTime)>;
+    fn set_key(&mut self ,  name: AppName ,  key: String ,  exp: SystemTime) -> Result<()>;
+    fn props(&self ,  name: &str) -> Option<SessionProps>;
+    fn set_props(&mut self ,  name: AppName ,  props: SessionProps) -> Result<()>;
+}
+
+pub struct MemoryStore {
+    keys: LruCache<AppName ,  (String ,  SystemTime)> , 
+    props: LruCache<AppName ,  SessionProps> , 
+}
+
+impl MemoryStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            keys: LruCache::with_expiry_duration(ttl) , 
+            props: LruCache::with_expiry_duration(ttl) , 
+        }
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn key(&self ,  name: &str) -> Option<(String ,  SystemTime)> {
+        self.keys.peek(name).cloned()
+    }
+
+    fn set_key(&mut self ,  name: AppName ,  key: String ,  exp: SystemTime) -> Result<()> {
+        self.keys.insert(name ,  (key ,  exp));
+        Ok(())
+    }
+
+    fn props(&self ,  name: &str) -> Option<SessionProps> {
+        self.props.peek(name).cloned()
+    }
+
+    fn set_props(&mut self ,  name: AppName ,  props: SessionProps) -> Result<()> {
+        self.props.insert(name ,  props);
+        Ok(())
+    }
+}
+
+// one line of the log file
+#[derive(Debug ,  Serialize ,  Deserialize)]
+#[serde(tag = "op" ,  rename_all = "lowercase")]
+enum Record {
+    Key {
+        name: AppName , 
+        key: String , 
+        exp: u64 , 
+    } , 
+    Props {
+        name: AppName , 
+        props: SessionProps , 
+        exp: u64 , 
+    } , 
+}
+
+#[derive(Debug ,  Default ,  Clone)]
+struct Entry {
+    key: Option<(String ,  SystemTime)> , 
+    props: Option<(SessionProps ,  SystemTime)> , 
+}
+
+/// Keeps everything in memory and appends every change to a file ,  which is
+/// replayed and compacted on open.
+pub struct FileStore {
+    ttl: Duration , 
+    entries: HashMap<AppName ,  Entry> , 
+    // the file is written on a blocking thread ,  not by the caller
+    log: mpsc::Sender<Record> , 
+    writer: JoinHandle<()> , 
+    records: usize , 
+}
+
+impl FileStore {
+    /// Reads the file in place ,  it is only opened on startup.
+    pub fn open<P: AsRef<Path>>(path: P ,  ttl: Duration) -> Result<Self> {
+        let log = Log::open(path.as_ref())?;
+        log::info!(
+            "Session store {:?} loaded with {} entries" , 
+            log.path , 
+            log.entries.len()
+        );
+        let entries = log.entries.clone();
+        let (sender ,  receiver) = mpsc::channel();
+        let writer = task::spawn_blocking(move || log.run(receiver));
+
+        Ok(Self {
+            ttl , 
+            entries , 
+            log: sender , 
+            writer , 
+            records: 0 , 
+        })
+    }
+
+    /// Waits for the records appended so far to be written.
+    pub async fn close(self) -> Result<()> {
+        drop(self.log);
+        self.writer.await?;
+        Ok(())
+    }
+
+    fn append(&mut self ,  record: Record) -> Result<()> {
+        self.records += 1;
+        // expired entries are dropped as often as the file is compacted
+        if self.records > 1024 && self.records > self.entries.len() * 4 {
+            expire(&mut self.entries);
+            self.records = 0;
+        }
+        self.log
+            .send(record)
+            .map_err(|_| anyhow!("session store writer is gone"))
+    }
+}
+
+// the file and what it holds
+struct Log {
+    path: PathBuf , 
+    file: File , 
+    entries: HashMap<AppName ,  Entry> , 
+    records: usize , 
+}
+
+impl Log {
+    fn open(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mut entries: HashMap<AppName ,  Entry> = HashMap::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                match serde_json::from_str::<Record>(&line) {
+                    Ok(record) => apply(&mut entries ,  record) , 
+                    Err(err) => log::warn!("Skip broken session store record: {}" ,  err) , 
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut log = Self {
+            path , 
+            file , 
+            entries , 
+            records: 0 , 
+        };
+        log.compact()?;
+
+        Ok(log)
+    }
+
+    // until the store is dropped
+    fn run(mut self ,  records: mpsc::Receiver<Record>) {
+        for record in records {
+            if let Err(err) = self.append(record) {
+                log::error!("Failed to write session store {:?}: {}" ,  self.path ,  err);
+            }
+        }
+    }
+
+    fn append(&mut self ,  record: Record) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        apply(&mut self.entries ,  record);
+        self.file.write_all(line.as_bytes())?;
+        self.records += 1;
+
+        if self.records > 1024 && self.records > self.entries.len() * 4 {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    // drops expired entries and rewrites the file with what is left
+    fn compact(&mut self) -> Result<()> {
+        expire(&mut self.entries);
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        let mut records = 0;
+        for (name ,  entry) in self.entries.iter() {
+            if let Some((ref key ,  exp)) = entry.key {
+                let record = Record::Key {
+                    name: name.clone() , 
+                    key: key.clone() , 
+                    exp: to_secs(exp) , 
+                };
+                writeln!(tmp_file ,  "{}" ,  serde_json::to_string(&record)?)?;
+                records += 1;
+            }
+            if let Some((ref props ,  exp)) = entry.props {
+                let record = Record::Props {
+                    name: name.clone() , 
+                    props: props.clone() , 
+                    exp: to_secs(exp) , 
+                };
+                writeln!(tmp_file ,  "{}" ,  serde_json::to_string(&record)?)?;
+                records += 1;
+            }
+        }
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path ,  &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.records = records;
+        Ok(())
+    }
+}
+
+impl SessionStore for FileStore {
+    fn key(&self ,  name: &str) -> Option<(String ,  SystemTime)> {
+        self.entries.get(name)?.key.clone()
+    }
+
+    fn set_key(&mut self ,  name: AppName ,  key: String ,  exp: SystemTime) -> Result<()> {
+        let record = Record::Key {
+            name: name.clone() , 
+            key: key.clone() , 
+            exp: to_secs(exp) , 
+        };
+        self.entries.entry(name).or_default().key = Some((key ,  exp));
+        self.append(record)
+    }
+
+    fn props(&self ,  name: &str) -> Option<SessionProps> {
+        match self.entries.get(name)?.props {
+            Some((ref props ,  exp)) if exp > SystemTime::now() => Some(props.clone()) , 
+            _ => None , 
+        }
+    }
+
+    fn set_props(&mut self ,  name: AppName ,  props: SessionProps) -> Result<()> {
+        let exp = SystemTime::now() + self.ttl;
+        let record = Record::Props {
+            name: name.clone() , 
+            props: props.clone() , 
+            exp: to_secs(exp) , 
+        };
+        self.entries.entry(name).or_default().props = Some((props ,  exp));
+        self.append(record)
+    }
+}
+
+fn apply(entries: &mut HashMap<AppName ,  Entry> ,  record: Record) {
+    match record {
+        Record::Key { name ,  key ,  exp } => {
+            entries.entry(name).or_default().key = Some((key ,  from_secs(exp)));
+        }
+        Record::Props { name ,  props ,  exp } => {
+            entries.entry(name).or_default().props = Some((props ,  from_secs(exp)));
+        }
+    }
+}
+
+fn expire(entries: &mut HashMap<AppName ,  Entry>) {
+    let now = SystemTime::now();
+    for entry in entries.values_mut() {
+        if matches!(entry.key ,  Some((_ ,  exp)) if exp <= now) {
+            entry.key = None;
+        }
+        if matches!(entry.props ,  Some((_ ,  exp)) if exp <= now) {
+            entry.props = None;
+        }
+    }
+    entries.retain(|_ ,  entry| entry.key.is_some() || entry.props.is_some());
+}
+
+fn to_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn from_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_store_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.log");
+        let ttl = Duration::from_secs(3600);
+        let exp = SystemTime::now() + ttl;
+
+        let mut props = SessionProps::new();
+        props.insert("live_id".to_string() ,  "1234".to_string());
+        {
+            let mut store = FileStore::open(&path ,  ttl).unwrap();
+            store.set_key("dj".into() ,  "old".into() ,  exp).unwrap();
+            store.set_key("dj".into() ,  "abcdefgh".into() ,  exp).unwrap();
+            store.set_props("dj".into() ,  props.clone()).unwrap();
+            store
+                .set_key("gone".into() ,  "expired".into() ,  SystemTime::now())
+                .unwrap();
+            store.close().await.unwrap();
+        }
+
+        let store = FileStore::open(&path ,  ttl).unwrap();
+        let (key ,  stored_exp) = store.key("dj").unwrap();
+        assert_eq!(key ,  "abcdefgh");
+        assert_eq!(to_secs(stored_exp) ,  to_secs(exp));
+        assert_eq!(store.props("dj") ,  Some(props));
+        assert!(store.key("gone").is_none());
+        // compacted on open
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count() ,  2);
+    }
+}
@@ -1,20 +1,47 @@
 use {
-    super::{AppName ,  Error as SessError ,  SessionId ,  SessionProps} , 
+    super::{
+        media::{MediaReceiver ,  SessionHandle ,  SessionWatcher} , 
+        output::{OutputKind ,  OutputStatus} , 
+        shutdown::OutputGuard , 
+        AppName ,  Error as SessError ,  SessionId ,  SessionProps , 
+    } , 
     crate::authorization::{Authorization ,  Error as AuthError} , 
     echo_types::{MediaSample ,  Protocol} , 
     serde::{Deserialize ,  Serialize} , 
-    std::{ops::Add ,  path::PathBuf ,  time::Instant} , 
+    std::{
+        collections::HashMap , 
+        ops::Add , 
+        time::{Duration ,  Instant ,  SystemTime} , 
+    } , 
     tokio::sync::{broadcast ,  mpsc ,  oneshot} , 
 };
 
 pub type Responder<P> = oneshot::Sender<P>;
 
-#[derive(Debug ,  Default ,  Clone ,  Copy ,  Serialize)]
+#[derive(Debug ,  Default ,  Clone ,  Copy ,  Serialize ,  Deserialize)]
 pub struct InputQuality {
     pub total_count: u32 , 
     pub drop_count: u32 , 
     pub bad_count: u32 , 
     pub filler_count: u32 , 
+    // connections which did not match the encryption of the session
+    pub rejected_count: u32 , 
+    // ADTS frames of AAC frames which could not be split ,  passed on whole
+    pub unsplit_count: u32 , 
+    // the network of the latest SRT connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<LinkQuality> , 
+}
+
+#[derive(Debug ,  Default ,  Clone ,  Copy ,  Serialize ,  Deserialize)]
+pub struct LinkQuality {
+    pub rtt_ms: u32 , 
+    pub lost_count: u32 , 
+    pub retransmitted_count: u32 , 
+    // packets which arrived after their play time
+    pub too_late_count: u32 , 
+    // bits per second received since the previous report
+    pub bitrate: u64 , 
 }
 
 impl Add<InputQuality> for InputQuality {
@@ -26,6 +53,9 @@ impl Add<InputQuality> for InputQuality {
             drop_count: self.drop_count + other.drop_count , 
             bad_count: self.bad_count + other.bad_count , 
             filler_count: self.filler_count + other.filler_count , 
+            rejected_count: self.rejected_count + other.rejected_count , 
+            unsplit_count: self.unsplit_count + other.unsplit_count , 
+            link: other.link.or(self.link) , 
         }
     }
 }
@@ -48,6 +78,10 @@ impl StateReason {
             message: "unknown".to_string() , 
         }
     }
+
+    pub fn code(&self) -> u16 {
+        self.code
+    }
 }
 
 #[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq ,  Hash)]
@@ -55,14 +89,18 @@ pub enum EventKind {
     AuthorizeSession , 
     CreateSession , 
     CreateSession0 , 
-    ReadyHlsSession , 
     PauseSession , 
     ResumeSession , 
     ReleaseSession , 
-    ReleaseHlsSession , 
-    StartRecord , 
-    CompleteRecord , 
     InputQualityReport , 
+    OutputStatus , 
+    RejectSession , 
+    HandoverSession , 
+    SwitchSession , 
+    ExpiringSession , 
+    ExtendSession , 
+    PropsChanged , 
+    Shutdown , 
 }
 
 #[derive(Debug)]
@@ -72,39 +110,89 @@ pub enum EventMessage {
         Option<SessionProps> , 
         Responder<Result<() ,  AuthError>> , 
     ) , 
-    CreateSession(SessionId ,  SessionWatcher) , 
+    CreateSession(SessionId ,  SessionWatcher ,  OutputGuard ,  Option<SessionProps>) , 
     CreateSession0(SessionId ,  Protocol ,  StateReason ,  Option<SessionProps>) , 
     PauseSession(SessionId ,  StateReason ,  Option<SessionProps>) , 
     ResumeSession(SessionId ,  StateReason ,  Option<SessionProps>) , 
     ReleaseSession(SessionId ,  StateReason ,  Option<SessionProps>) , 
-    ReadyHlsSession(SessionId ,  String ,  Option<SessionProps>) , 
-    ReleaseHlsSession(SessionId ,  Option<SessionProps>) , 
-    StartRecord(SessionId ,  Option<SessionProps>) , 
-    CompleteRecord(SessionId ,  PathBuf ,  u64 ,  Option<SessionProps>) , 
     InputQualityReport(SessionId ,  InputQuality ,  Option<SessionProps>) , 
+    OutputStatus(SessionId ,  OutputKind ,  OutputStatus ,  Option<SessionProps>) , 
+    // a publish to a name which is already live ,  by duplicate publish policy
+    RejectSession(SessionId ,  Protocol ,  StateReason ,  Option<SessionProps>) , 
+    HandoverSession(SessionId ,  Protocol ,  StateReason ,  Option<SessionProps>) , 
+    // a backup uplink took over ,  with the ingest which feeds the session now
+    SwitchSession(
+        SessionId , 
+        SessionId , 
+        Protocol , 
+        StateReason , 
+        Option<SessionProps> , 
+    ) , 
+    // the session reaches its maximum duration in the given time
+    ExpiringSession(SessionId ,  Duration ,  Option<SessionProps>) , 
+    ExtendSession(SessionId ,  Duration ,  Option<SessionProps>) , 
+    // the keys which were changed ,  added or removed
+    PropsChanged(SessionId ,  Vec<String> ,  Option<SessionProps>) , 
+    Shutdown(StateReason) , 
 }
 
 // session manager
 pub enum ManageMessage {
     UpdateSessionProps(AppName ,  SessionProps) , 
-    AuthorizeSession(AppName ,  Authorization ,  Responder<Result<String ,  AuthError>>) , 
+    AuthorizeSession(
+        AppName , 
+        Authorization , 
+        Option<SessionProps> , 
+        Responder<Result<String ,  AuthError>> , 
+    ) , 
     CreateSession(
         AppName , 
         SessionId , 
         Protocol , 
         Option<String> , 
         StateReason , 
+        IngestHandle , 
         Responder<Result<(SessionHandle ,  Option<Instant>) ,  SessError>> , 
     ) , 
     PauseSession(AppName ,  SessionId ,  StateReason) , 
     ResumeSession(AppName ,  SessionId ,  StateReason) , 
     ReleaseSession(AppName ,  SessionId ,  StateReason) , 
-    ReadyHlsSession(AppName ,  SessionId ,  String) , 
-    ReleaseHlsSession(AppName ,  SessionId) , 
-    StartRecord(AppName ,  SessionId) , 
-    CompleteRecord(AppName ,  SessionId ,  PathBuf ,  u64) , 
     InputQualityReport(AppName ,  SessionId ,  InputQuality) , 
+    OutputStatus(AppName ,  SessionId ,  OutputKind ,  OutputStatus) , 
+    // the session broadcasts another uplink ,  by the sid of its samples
+    SwitchUplink(SessionId ,  u32 ,  StateReason) , 
     RegisterTrigger(EventKind ,  EventTrigger) , 
+    // stop accepting new sessions
+    Shutdown(StateReason) , 
+    CountSessions(Responder<usize>) , 
+    // end all the remaining sessions
+    TerminateSessions(StateReason) , 
+    ListSessions(Responder<Vec<SessionInfo>>) , 
+    // disconnect the ingest of a session
+    KickSession(SessionId ,  StateReason ,  Responder<Result<() ,  SessError>>) , 
+    // subscribe to a live session ,  starting with its recent samples
+    WatchSession(SessionId ,  Responder<Result<SessionWatcher ,  SessError>>) , 
+    // give a session more time ,  answered with the time left
+    ExtendSession(SessionId ,  Duration ,  Responder<Result<Duration ,  SessError>>) , 
+    // set or remove (`None`) props of a live session ,  answered with the new props
+    PatchSessionProps(
+        SessionId , 
+        HashMap<String ,  Option<String>> , 
+        Responder<Result<SessionProps ,  SessError>> , 
+    ) , 
+}
+
+#[derive(Debug ,  Clone)]
+pub struct SessionInfo {
+    pub id: SessionId , 
+    pub name: AppName , 
+    pub protocol: Protocol , 
+    // the connection currently publishing
+    pub ingest_id: SessionId , 
+    pub created_at: SystemTime , 
+    // when the session reaches its maximum duration
+    pub expires_at: Option<SystemTime> , 
+    pub props: Option<SessionProps> , 
 }
 
 pub type ManagerHandle = mpsc::UnboundedSender<ManageMessage>;
@@ -117,13 +205,37 @@ pub fn trigger_channel() -> (EventTrigger ,  EventWatcher) {
     mpsc::unbounded_channel()
 }
 
+// ingest (SRT ,  RTMP)
+#[derive(Debug)]
+pub enum IngestMessage {
+    Kick(StateReason) , 
+}
+
+pub type IngestHandle = mpsc::UnboundedSender<IngestMessage>;
+pub type IngestWatcher = mpsc::UnboundedReceiver<IngestMessage>;
+
+pub fn ingest_channel() -> (IngestHandle ,  IngestWatcher) {
+    mpsc::unbounded_channel()
+}
+
 // session instance
 pub enum MediaMessage {
     Sample(MediaSample) , 
     EndOfSample , 
+    // the ingest of the sids from the given one has ended ,  sent by its
+    // handle in place of `EndOfSample`
+    EndOfUplink(u32) , 
 }
 
-pub type SessionHandle = mpsc::UnboundedSender<MediaMessage>;
-pub(super) type IncomingBroadcast = mpsc::UnboundedReceiver<MediaMessage>;
+pub(super) type IncomingBroadcast = MediaReceiver;
 pub(super) type OutgoingBroadcast = broadcast::Sender<MediaSample>;
-pub type SessionWatcher = broadcast::Receiver<MediaSample>;
+
+pub(super) enum ControlMessage {
+    // the session is continued by another ingest
+    Handover(IncomingBroadcast) , 
+    // a watcher joining the running session
+    Watch(Responder<Result<SessionWatcher ,  SessError>>) , 
+}
+
+pub(super) type SessionControl = mpsc::UnboundedSender<ControlMessage>;
+pub(super) type ControlReceiver = mpsc::UnboundedReceiver<ControlMessage>;
@@ -1,6 +1,7 @@
 mod m3u8;
 pub mod service;
 mod session_cleaner;
+mod slate;
 mod writer;
 
 pub use self::service::Service;
@@ -2,10 +2,11 @@ use {
     crate::{
         service::PREROLE_PATH , 
         session_cleaner::{self ,  CleanerItem} , 
+        slate::SLATE_PATH , 
     } , 
     anyhow::Result , 
     m3u8_rs::playlist::{MediaPlaylist ,  MediaSegment} , 
-    echo_core::session::{AppName ,  ManagerHandle ,  SessionId} , 
+    echo_core::session::OutputReporter , 
     std::{cmp ,  path::PathBuf ,  time::Duration} , 
     tempfile::NamedTempFile , 
     tokio::{
@@ -86,7 +87,7 @@ impl Playlist {
             .iter()
             .filter_map(|seg| {
                 self.current_duration -= seg.duration;
-                if (&seg.uri).starts_with(PREROLE_PATH) {
+                if seg.uri.starts_with(PREROLE_PATH) || seg.uri.starts_with(SLATE_PATH) {
                     None
                 } else {
                     Some(self.file_path.parent().unwrap().join(&seg.uri))
@@ -117,6 +118,19 @@ impl Playlist {
             segment.discontinuity = true;
         }
 
+        self.push_segment(segment).await
+    }
+
+    /// Adds a segment which is not written by the session ,  e.g. of a slate.
+    pub(crate) async fn add_shared_segment(
+        &mut self , 
+        segment: MediaSegment , 
+    ) -> Result<PlaylistState> {
+        self.push_segment(segment).await
+    }
+
+    async fn push_segment(&mut self ,  segment: MediaSegment) -> Result<PlaylistState> {
+        let duration = segment.duration;
         if self.current_duration >= self.playlist_duration {
             self.schedule_for_deletion(1);
         }
@@ -181,12 +195,7 @@ impl Playlist {
         Ok(())
     }
 
-    pub(crate) async fn release(
-        &mut self , 
-        name: AppName , 
-        id: SessionId , 
-        session_manager: ManagerHandle , 
-    ) {
+    pub(crate) async fn release(&mut self ,  reporter: OutputReporter) {
         let current_duration = cmp::min(self.current_duration ,  self.cache_duration);
 
         self.schedule_for_deletion(self.playlist.segments.len()); // remove all TS files
@@ -210,10 +219,13 @@ impl Playlist {
 
         let _ = self
             .session_cleaner
-            .send((
-                current_duration , 
-                CleanerItem::Session(name ,  id ,  session_manager) , 
-            ))
+            .send((current_duration ,  CleanerItem::Session(reporter)))
             .map_err(|_| log::error!("failed to send session to be deleted"));
     }
+
+    /// Marks the playlist as complete with EXT-X-ENDLIST.
+    pub(crate) async fn end(&mut self) -> Result<()> {
+        self.playlist.end_list = true;
+        self.atomic_update().await
+    }
 }
@@ -1,12 +1,17 @@
 use {
-    crate::{session_cleaner ,  writer::Writer} , 
+    crate::{
+        session_cleaner , 
+        slate::{Slates ,  SLATE} , 
+        writer::Writer , 
+    } , 
     anyhow::{bail ,  Result} , 
     m3u8_rs::playlist::{MediaPlaylist ,  Playlist} , 
     echo_core::{
-        session::{self ,  EventKind ,  EventMessage ,  ManageMessage ,  ManagerHandle} , 
+        session::{AppName ,  OutputFactory ,  OutputKind ,  OutputReporter ,  OutputSink ,  SessionId} , 
         Config , 
     } , 
-    std::{collections::HashMap ,  path::Path ,  time::Duration ,  time::SystemTime} , 
+    echo_types::async_trait , 
+    std::{collections::HashMap ,  path::Path ,  sync::Arc ,  time::Duration ,  time::SystemTime} , 
     tokio::{fs ,  io::AsyncReadExt} , 
     warp::{
         http::header::{self ,  HeaderMap ,  HeaderValue} , 
@@ -20,43 +25,58 @@ pub(crate) const PREROLE_PATH: &'static str = "/prerole";
 
 pub struct Service {
     config: Config , 
-    session_manager: ManagerHandle , 
+    prerole_pl: MediaPlaylist , 
+    prerole_dur: Duration , 
+    slates: Arc<Slates> , 
+    sess_cleaner_sender: Option<session_cleaner::Sender> , 
+    // hls ext-x-sequence 처리를 위한 map 정의
+    hs: HashMap<String ,  (u32 ,  SystemTime)> , 
 }
 
 impl Service {
-    pub fn new(session_manager: ManagerHandle ,  config: Config) -> Self {
+    pub fn new(config: Config) -> Self {
         Self {
             config , 
-            session_manager , 
+            prerole_pl: MediaPlaylist::default() , 
+            prerole_dur: Duration::default() , 
+            slates: Arc::new(Slates::default()) , 
+            sess_cleaner_sender: None , 
+            hs: HashMap::new() , 
         }
     }
+}
+
+#[async_trait]
+impl OutputFactory for Service {
+    fn kind(&self) -> OutputKind {
+        OutputKind::HLS
+    }
+
+    fn default_selected(&self) -> bool {
+        self.config.hls_default
+    }
 
-    pub async fn run(self) {
+    async fn init(&mut self) -> Result<()> {
         let hls_root = self.config.hls_root_dir.clone();
         log::info!("HLS directory located at '{}'" ,  hls_root.display());
 
-        if let Err(err) = create_dir(&hls_root).await {
-            panic!("{}" ,  err);
-        }
-
-        if let Err(err) = cleanup_dir(&hls_root).await {
-            log::error!("{}" ,  err);
-            return;
-        }
+        create_dir(&hls_root).await?;
+        cleanup_dir(&hls_root).await?;
 
         let prerole_dir = self.config.hls_prerole_dir.clone();
         let prerole_path = prerole_dir.join("live.m3u8");
-        let prerole_pl = match read_prerole_m3u8(prerole_path).await {
-            Ok(pl) => pl , 
-            Err(err) => panic!("{}" ,  err) , 
-        };
-        let prerole_dur = prerole_pl
+        self.prerole_pl = read_m3u8(prerole_path ,  PREROLE_PATH).await?;
+        self.prerole_dur = self
+            .prerole_pl
             .segments
             .iter()
             .fold(Duration::default() ,  |acc ,  seg| acc + seg.duration);
 
+        let slate_dir = self.config.hls_slate_dir.clone();
+        self.slates = Arc::new(Slates::load(&slate_dir).await?);
+
         let sess_cleaner = session_cleaner::SessionCleaner::new();
-        let sess_cleaner_sender = sess_cleaner.sender();
+        self.sess_cleaner_sender = Some(sess_cleaner.sender());
         tokio::spawn(async move { sess_cleaner.run().await });
 
         if self.config.hls_web_enabled {
@@ -88,6 +108,8 @@ impl Service {
             );
             let routes = warp::path(PREROLE)
                 .and(warp::fs::dir(prerole_dir))
+                .or(warp::path(SLATE).and(warp::fs::dir(slate_dir)))
+                .unify()
                 .or(warp::path(web_path).and(warp::fs::dir(hls_root)))
                 .unify()
                 .map(|reply: warp::fs::File| {
@@ -124,77 +146,65 @@ impl Service {
             });
         }
 
-        let (trigger ,  mut trigger_watcher) = session::trigger_channel();
+        Ok(())
+    }
 
-        if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::CreateSession , 
-            trigger , 
-        )) {
-            log::error!("Failed to register CreateSession trigger");
-            panic!("Failed to register CreateSession trigger");
-        }
+    async fn create(
+        &mut self , 
+        name: &AppName , 
+        id: SessionId , 
+        reporter: OutputReporter , 
+    ) -> Result<Box<dyn OutputSink>> {
+        let begin_time = SystemTime::now();
+
+        // 동일한 name 이면 기존의 seq를 기존 값+100000 부터 시작.
+        // 재연결시 ext-x-sequence 연속성을 위한 부분
+        let seq = match self.hs.get(name) {
+            Some(val) => val.0 + 100000 , 
+            None => 0 , 
+        };
 
-        // hls ext-x-sequence 처리를 위한 map 정의
-        let mut hs: HashMap<String ,  (u32 ,  SystemTime)> = HashMap::new();
-
-        while let Some((name ,  event)) = trigger_watcher.recv().await {
-            match event {
-                EventMessage::CreateSession(id ,  session_watcher) => {
-                    let session_manager = self.session_manager.clone();
-                    let begin_time = SystemTime::now();
-
-                    // 동일한 name 이면 기존의 seq를 기존 값+100000 부터 시작.
-                    // 재연결시 ext-x-sequence 연속성을 위한 부분
-                    let seq = match hs.get(&name) {
-                        Some(val) => val.0 + 100000 , 
-                        None => 0 , 
-                    };
-
-                    hs.insert(name.clone() ,  (seq ,  begin_time));
-
-                    match Writer::create(
-                        name.clone() , 
-                        id , 
-                        session_manager , 
-                        session_watcher , 
-                        sess_cleaner_sender.clone() , 
-                        &self.config , 
-                        &prerole_pl , 
-                        &prerole_dur , 
-                        seq , 
-                    ) {
-                        Ok(writer) => {
-                            tokio::spawn(async move { writer.run().await.unwrap() });
-                        }
-                        Err(err) => log::error!("Failed to create writer: {:?}" ,  err) , 
-                    }
+        self.hs.insert(name.clone() ,  (seq ,  begin_time));
 
-                    // 2시간 넘어서는 hls_sequence 삭제
-                    // writer객체에서 해주는 방행으로 개선되어야 함
-                    let mut delete_list: Vec<String> = vec![];
-                    let mut total_hashmap = 0;
-
-                    for (key ,  value) in &hs {
-                        let difference = begin_time.duration_since(value.1).unwrap().as_secs();
-                        if difference > self.config.ttl_max_duration.as_secs() + 600 {
-                            delete_list.push(String::from(key));
-                        }
-                        total_hashmap += 1;
-                    }
+        // 2시간 넘어서는 hls_sequence 삭제
+        // writer객체에서 해주는 방행으로 개선되어야 함
+        let mut delete_list: Vec<String> = vec![];
+        let mut total_hashmap = 0;
 
-                    for delete_key in delete_list.iter_mut() {
-                        let val = hs.remove(delete_key);
-                        log::info!(
-                            "remove hls-seq {} {:?} ,  total: {}" , 
-                            delete_key , 
-                            val , 
-                            total_hashmap
-                        );
-                    }
-                }
-                _ => {}
+        for (key ,  value) in &self.hs {
+            let difference = begin_time.duration_since(value.1).unwrap().as_secs();
+            if difference > self.config.ttl_max_duration.as_secs() + 600 {
+                delete_list.push(String::from(key));
             }
+            total_hashmap += 1;
+        }
+
+        for delete_key in delete_list.iter_mut() {
+            let val = self.hs.remove(delete_key);
+            log::info!(
+                "remove hls-seq {} {:?} ,  total: {}" , 
+                delete_key , 
+                val , 
+                total_hashmap
+            );
         }
+
+        let sess_cleaner_sender = match self.sess_cleaner_sender {
+            Some(ref sender) => sender.clone() , 
+            None => bail!("HLS output is not initialized") , 
+        };
+        let writer = Writer::create(
+            name.clone() , 
+            id , 
+            reporter , 
+            sess_cleaner_sender , 
+            &self.config , 
+            &self.prerole_pl , 
+            &self.prerole_dur , 
+            self.slates.clone() , 
+            seq , 
+        )?;
+        Ok(Box::new(writer))
     }
 }
 
@@ -237,7 +247,8 @@ async fn cleanup_dir<P: AsRef<Path>>(path: P) -> Result<()> {
     Ok(())
 }
 
-async fn read_prerole_m3u8<P: AsRef<Path>>(path: P) -> Result<MediaPlaylist> {
+/// Reads a media playlist of files served under `uri_prefix`.
+pub(crate) async fn read_m3u8<P: AsRef<Path>>(path: P ,  uri_prefix: &str) -> Result<MediaPlaylist> {
     let path = path.as_ref();
 
     let mut file = fs::File::open(path).await?;
@@ -259,7 +270,7 @@ async fn read_prerole_m3u8<P: AsRef<Path>>(path: P) -> Result<MediaPlaylist> {
         .iter()
         .map(|seg| {
             let mut seg = seg.clone();
-            seg.uri = format!("{}/{}" ,  PREROLE_PATH ,  seg.uri);
+            seg.uri = format!("{}/{}" ,  uri_prefix ,  seg.uri);
             seg
         })
         .collect();
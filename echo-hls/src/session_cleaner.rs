@@ -1,5 +1,5 @@
 use {
-    echo_core::session::{AppName ,  ManageMessage ,  ManagerHandle ,  SessionId} , 
+    echo_core::session::{OutputReporter ,  OutputStatus} , 
     std::path::PathBuf , 
     tokio::{
         fs , 
@@ -14,7 +14,7 @@ pub(crate) enum CleanerItem {
     Chunks(Vec<PathBuf>) , 
     Manifest(PathBuf) , 
     Directory(PathBuf) , 
-    Session(AppName ,  SessionId ,  ManagerHandle) , 
+    Session(OutputReporter) , 
 }
 
 type Batch = CleanerItem;
@@ -91,13 +91,6 @@ async fn remove_item(item: &CleanerItem) {
                 log::error!("Failed to remove directory '{}': {}" ,  path.display() ,  err);
             }
         }
-        CleanerItem::Session(name ,  id ,  session_manager) => {
-            if let Err(_) =
-                session_manager.send(ManageMessage::ReleaseHlsSession(name.clone() ,  *id))
-            {
-                log::error!("Failed to send ReleaseHlsSession");
-                panic!("Failed to send ReleaseHlsSession");
-            }
-        }
+        CleanerItem::Session(reporter) => reporter.report(OutputStatus::Released) , 
     }
 }
@@ -0,0 +1,233 @@
+use {
+    crate::service::read_m3u8 , 
+    anyhow::{bail ,  Result} , 
+    echo_core::session::StateReason , 
+    m3u8_rs::playlist::{MediaPlaylist ,  MediaSegment} , 
+    std::{
+        collections::HashMap , 
+        path::Path , 
+        time::{Duration ,  Instant} , 
+    } , 
+    tokio::fs , 
+};
+
+pub(crate) const SLATE: &str = "slate";
+pub(crate) const SLATE_PATH: &str = "/slate";
+const DEFAULT_SLATE: &str = "default";
+
+/// The "be right back" playlists played while a session is paused ,  by
+/// "<app>/<reason code>" ,  "<app>" and the same under "default".
+#[derive(Default)]
+pub(crate) struct Slates {
+    playlists: HashMap<String ,  MediaPlaylist> , 
+}
+
+impl Slates {
+    pub(crate) async fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut slates = Self::default();
+        if !is_dir(dir).await {
+            log::info!("no slates at {}" ,  dir.display());
+            return Ok(slates);
+        }
+
+        let mut apps = fs::read_dir(dir).await?;
+        while let Some(entry) = apps.next_entry().await? {
+            let app_path = entry.path();
+            if !is_dir(&app_path).await {
+                continue;
+            }
+            let app = entry.file_name().to_string_lossy().to_string();
+            slates.read(dir ,  &app).await?;
+            let mut codes = fs::read_dir(&app_path).await?;
+            while let Some(entry) = codes.next_entry().await? {
+                if is_dir(&entry.path()).await {
+                    let code = entry.file_name().to_string_lossy().to_string();
+                    slates.read(dir ,  &format!("{}/{}" ,  app ,  code)).await?;
+                }
+            }
+        }
+
+        Ok(slates)
+    }
+
+    async fn read(&mut self ,  dir: &Path ,  name: &str) -> Result<()> {
+        let path = dir.join(name).join("live.m3u8");
+        match fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => () , 
+            _ => return Ok(()) , 
+        }
+
+        let playlist = read_m3u8(&path ,  &format!("{}/{}" ,  SLATE_PATH ,  name)).await?;
+        // it's played in a loop
+        if playlist.segments.is_empty()
+            || playlist
+                .segments
+                .iter()
+                .any(|seg| seg.duration == Duration::default())
+        {
+            bail!("slate has an empty segment: {}" ,  path.display());
+        }
+        log::info!("slate {} loaded" ,  name);
+        self.playlists.insert(name.to_string() ,  playlist);
+
+        Ok(())
+    }
+
+    /// The slate of an app ,  the one of the reason if there is.
+    pub(crate) fn select(
+        &self , 
+        name: &str , 
+        reason: Option<&StateReason> , 
+    ) -> Option<&MediaPlaylist> {
+        let mut keys = Vec::new();
+        for app in &[name ,  DEFAULT_SLATE] {
+            if let Some(reason) = reason {
+                keys.push(format!("{}/{}" ,  app ,  reason.code()));
+            }
+            keys.push(app.to_string());
+        }
+        keys.iter().find_map(|key| self.playlists.get(key))
+    }
+}
+
+/// Plays a slate in a loop for as long as it stands in for the session.
+pub(crate) struct SlateCursor {
+    segments: Vec<MediaSegment> , 
+    next: usize , 
+    started: Instant , 
+    played: Duration , 
+}
+
+impl SlateCursor {
+    pub(crate) fn new(playlist: &MediaPlaylist ,  now: Instant) -> Self {
+        Self {
+            segments: playlist.segments.clone() , 
+            next: 0 , 
+            started: now , 
+            played: Duration::default() , 
+        }
+    }
+
+    /// The segments to be added by now ,  one ahead of the time the slate has
+    /// been playing. Each loop of the slate starts with a discontinuity.
+    pub(crate) fn advance(&mut self ,  now: Instant) -> Vec<MediaSegment> {
+        let mut segments = Vec::new();
+        while self.played <= now.duration_since(self.started) {
+            let mut segment = self.segments[self.next].clone();
+            segment.discontinuity = self.next == 0;
+            self.played += segment.duration;
+            self.next = (self.next + 1) % self.segments.len();
+            segments.push(segment);
+        }
+        segments
+    }
+}
+
+async fn is_dir(path: &Path) -> bool {
+    fs::metadata(path)
+        .await
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // a slate of two segments of a second
+    pub(crate) const SLATE_M3U8: &str = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:1
+#EXT-X-MEDIA-SEQUENCE:0
+#EXTINF:1.0,
+brb0.ts
+#EXTINF:1.0,
+brb1.ts
+";
+
+    pub(crate) fn write_slate(dir: &Path ,  name: &str) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("live.m3u8") ,  SLATE_M3U8).unwrap();
+    }
+
+    fn selected(slates: &Slates ,  name: &str ,  code: Option<u16>) -> Option<String> {
+        let reason = code.map(|code| StateReason::new(code ,  "test"));
+        slates
+            .select(name ,  reason.as_ref())
+            .map(|playlist| playlist.segments[0].uri.clone())
+    }
+
+    #[tokio::test]
+    async fn test_select() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Slates::load(dir.path().join("none"))
+            .await
+            .unwrap()
+            .select("dj" ,  None)
+            .is_none());
+
+        for name in &["dj/30008" ,  "dj" ,  "default/30009" ,  "default"] {
+            write_slate(dir.path() ,  name);
+        }
+        let slates = Slates::load(dir.path()).await.unwrap();
+
+        // the reason of the app ,  the app ,  the reason by default ,  the default
+        assert_eq!(
+            selected(&slates ,  "dj" ,  Some(30008)).as_deref() , 
+            Some("/slate/dj/30008/brb0.ts")
+        );
+        assert_eq!(
+            selected(&slates ,  "dj" ,  Some(30009)).as_deref() , 
+            Some("/slate/dj/brb0.ts")
+        );
+        assert_eq!(
+            selected(&slates ,  "dj" ,  None).as_deref() , 
+            Some("/slate/dj/brb0.ts")
+        );
+        assert_eq!(
+            selected(&slates ,  "vj" ,  Some(30009)).as_deref() , 
+            Some("/slate/default/30009/brb0.ts")
+        );
+        assert_eq!(
+            selected(&slates ,  "vj" ,  Some(30008)).as_deref() , 
+            Some("/slate/default/brb0.ts")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_advance() {
+        let dir = tempfile::tempdir().unwrap();
+        write_slate(dir.path() ,  "dj");
+        let slates = Slates::load(dir.path()).await.unwrap();
+
+        let start = Instant::now();
+        let mut cursor = SlateCursor::new(slates.select("dj" ,  None).unwrap() ,  start);
+        let advance = |cursor: &mut SlateCursor ,  millis: u64| {
+            cursor
+                .advance(start + Duration::from_millis(millis))
+                .into_iter()
+                .map(|segment| (segment.uri ,  segment.discontinuity))
+                .collect::<Vec<_>>()
+        };
+
+        // a segment ahead ,  each loop starts with a discontinuity
+        assert_eq!(
+            advance(&mut cursor ,  0) , 
+            vec![("/slate/dj/brb0.ts".to_string() ,  true)]
+        );
+        assert!(advance(&mut cursor ,  500).is_empty());
+        assert_eq!(
+            advance(&mut cursor ,  1000) , 
+            vec![("/slate/dj/brb1.ts".to_string() ,  false)]
+        );
+        assert_eq!(
+            advance(&mut cursor ,  3500) , 
+            vec![
+                ("/slate/dj/brb0.ts".to_string() ,  true) , 
+                ("/slate/dj/brb1.ts".to_string() ,  false) , 
+            ]
+        );
+    }
+}
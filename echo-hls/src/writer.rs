@@ -2,19 +2,21 @@ use {
     crate::{
         m3u8::{Playlist ,  PlaylistState} , 
         session_cleaner , 
+        slate::{SlateCursor ,  Slates} , 
     } , 
     anyhow::{bail ,  Result} , 
     chrono::Utc , 
     m3u8_rs::playlist::MediaPlaylist , 
     echo_codec::mpegts::TransportStream , 
     echo_core::{
-        session::{AppName ,  ManageMessage ,  ManagerHandle ,  SessionId ,  SessionWatcher} , 
+        session::{AppName ,  OutputReporter ,  OutputSink ,  OutputStatus ,  SessionId ,  StateReason} , 
         Config , 
     } , 
-    echo_types::{MediaSample ,  SampleType ,  Timestamp} , 
+    echo_types::{async_trait ,  MediaSample ,  SampleType ,  Timestamp} , 
     std::{
         path::{Path ,  PathBuf} , 
-        time::Duration , 
+        sync::Arc , 
+        time::{Duration ,  Instant} , 
     } , 
     tokio::{fs::File ,  io::AsyncWriteExt} , 
 };
@@ -25,34 +27,41 @@ static INVALID_TIMESTAMP_MS: u64 = std::u64::MAX;
 pub struct Writer {
     name: AppName , 
     id: SessionId , 
-    session_manager: ManagerHandle , 
-    session_watcher: SessionWatcher , 
+    reporter: OutputReporter , 
     write_interval: u64 , 
     next_write: u64 , 
     last_timestamp: u64 , 
     prev_timestamp: u64 , 
+    // the distance between the last two samples ,  the duration of the last one
+    frame_duration: u64 , 
     media_sequence: u32 , 
     discontinuity: bool , 
+    // samples were skipped ,  the next one starts a new segment
+    resync: bool , 
     buffer: TransportStream , 
     playlist: Playlist , 
     stream_path: PathBuf , 
+    slates: Arc<Slates> , 
+    // plays instead of the session while it's paused or starving
+    slate: Option<SlateCursor> , 
+    paused: bool , 
+    // the first of the filler samples in a row
+    filler_since: Option<u64> , 
 }
 
-const AAC_FRAME_DURATION: u64 = 22; // XXX
-
 impl Writer {
     pub(crate) fn create(
         name: AppName , 
         id: SessionId , 
-        session_manager: ManagerHandle , 
-        session_watcher: SessionWatcher , 
+        reporter: OutputReporter , 
         session_cleaner: session_cleaner::Sender , 
         config: &Config , 
         prerole: &MediaPlaylist , 
         prerole_dur: &Duration , 
+        slates: Arc<Slates> , 
         seq: u32 , 
     ) -> Result<Self> {
-        let write_interval = config.hls_target_duration.as_millis() as u64 - AAC_FRAME_DURATION;
+        let write_interval = config.hls_target_duration.as_millis() as u64;
         let next_write = write_interval;
 
         let hls_root = config.hls_root_dir.clone();
@@ -69,51 +78,33 @@ impl Writer {
             session_cleaner , 
         );
 
+        log::info!("{} {} create HLS" ,  name ,  id);
+
         Ok(Self {
             name , 
             id , 
-            session_manager , 
-            session_watcher , 
+            reporter , 
             write_interval , 
             next_write , 
             last_timestamp: 0 , 
             prev_timestamp: INVALID_TIMESTAMP_MS , 
+            frame_duration: 0 , 
             media_sequence: seq , 
             discontinuity: true , 
+            resync: false , 
             buffer: TransportStream::new() , 
             playlist , 
             stream_path , 
+            slates , 
+            slate: None , 
+            paused: false , 
+            filler_since: None , 
         })
     }
 
-    pub async fn run(mut self) -> Result<()> {
-        log::info!("{} {} create HLS" ,  self.name ,  self.id);
-
-        let mut has_recv = false;
-        let mut sid = 0;
-        while let Ok(sample) = self.session_watcher.recv().await {
-            if sample.sid < sid {
-                continue;
-            } else if sample.sid > sid {
-                sid = sample.sid;
-            }
-            if !has_recv {
-                has_recv = true;
-            }
-            if let Err(why) = self.handle_sample(sample).await {
-                log::error!("{:?}" ,  why);
-            }
-        }
-
-        if has_recv {
-            self.playlist
-                .release(self.name.clone() ,  self.id ,  self.session_manager.clone())
-                .await;
-        }
-
-        log::info!("{} {} destroy HLS" ,  self.name ,  self.id);
-
-        Ok(())
+    // where the last sample ends
+    fn segment_end(&self) -> u64 {
+        self.prev_timestamp + self.frame_duration
     }
 
     async fn write_segment(&mut self ,  timestamp: u64 ,  discontinuity: bool) -> Result<()> {
@@ -128,25 +119,32 @@ impl Writer {
         let mut file = File::create(&path).await?;
         file.write_all(&buffer).await?;
 
-        match self
+        let state = self
             .playlist
             .add_media_segment(
                 filename , 
                 Duration::from_millis(duration) , 
                 self.discontinuity , 
             )
-            .await
-        {
+            .await;
+        self.report_state(state);
+
+        if discontinuity {
+            self.next_write = self.write_interval;
+        } else {
+            self.next_write += self.write_interval;
+        }
+        self.media_sequence += 1;
+        self.discontinuity = discontinuity;
+
+        Ok(())
+    }
+
+    fn report_state(&self ,  state: Result<PlaylistState>) {
+        match state {
             Ok(PlaylistState::Ready) => {
-                let hls_path = format!("{}/{}" ,  self.name ,  PLAYLIST_NAME);
-                if let Err(_) = self.session_manager.send(ManageMessage::ReadyHlsSession(
-                    self.name.clone() , 
-                    self.id , 
-                    hls_path , 
-                )) {
-                    log::error!("Failed to send ReadyHlsSession");
-                    panic!("Failed to send ReadyHlsSession");
-                }
+                let path = format!("{}/{}" ,  self.name ,  PLAYLIST_NAME);
+                self.reporter.report(OutputStatus::Ready { path });
             }
             Ok(PlaylistState::NotReady) => {
                 log::warn!("{} HLS not ready" ,  self.name);
@@ -161,35 +159,91 @@ impl Writer {
             }
             _ => {}
         }
+    }
 
-        if discontinuity {
-            self.next_write = self.write_interval;
-        } else {
-            self.next_write += self.write_interval;
+    // replaces the one playing ,  false if the app has no slate
+    async fn start_slate(&mut self ,  reason: Option<&StateReason>) -> Result<bool> {
+        let slates = self.slates.clone();
+        let slate = match slates.select(&self.name ,  reason) {
+            Some(slate) => slate , 
+            None => return Ok(false) , 
+        };
+        log::info!("{} {} HLS slate starts" ,  self.name ,  self.id);
+
+        // close the segment before the slate
+        if !self.buffer.is_empty() {
+            self.write_segment(self.segment_end() ,  false)
+                .await?;
+        }
+        self.slate = Some(SlateCursor::new(slate ,  Instant::now()));
+        self.play_slate().await?;
+
+        Ok(true)
+    }
+
+    async fn play_slate(&mut self) -> Result<()> {
+        let segments = match self.slate {
+            Some(ref mut slate) => slate.advance(Instant::now()) , 
+            None => return Ok(()) , 
+        };
+        for segment in segments {
+            let state = self.playlist.add_shared_segment(segment).await;
+            self.report_state(state);
         }
-        self.media_sequence += 1;
-        self.discontinuity = discontinuity;
 
         Ok(())
     }
 
+    fn end_slate(&mut self) {
+        if self.slate.take().is_some() {
+            log::info!("{} {} HLS slate ends" ,  self.name ,  self.id);
+            // the session starts over after the slate
+            self.discontinuity = true;
+            self.resync = true;
+        }
+    }
+
+    // a few fillers are written as they are ,  the slate takes over once
+    // they fill a segment
+    async fn handle_filler(&mut self ,  timestamp: Timestamp ,  bytes: &[u8]) -> Result<()> {
+        if self.slate.is_some() {
+            return self.play_slate().await;
+        }
+
+        let timestamp_ms = timestamp.as_millis();
+        let since = *self.filler_since.get_or_insert(timestamp_ms);
+        if (timestamp_ms + self.frame_duration).saturating_sub(since) >= self.write_interval
+            && self.start_slate(None).await?
+        {
+            return Ok(());
+        }
+        self.handle_aac_audio(timestamp ,  bytes).await
+    }
+
     async fn handle_aac_audio(&mut self ,  timestamp: Timestamp ,  bytes: &[u8]) -> Result<()> {
         let timestamp_ms: u64 = timestamp.as_millis();
 
         let mut first_frame = false;
-        if timestamp_ms >= self.next_write {
+        if self.resync {
+            self.resync = false;
+            self.next_write = timestamp_ms + self.write_interval;
+            first_frame = true;
+        } else if timestamp_ms + self.frame_duration > self.next_write {
             self.write_segment(timestamp_ms ,  false).await?;
             first_frame = true;
         } else if self.prev_timestamp != INVALID_TIMESTAMP_MS && timestamp_ms < self.prev_timestamp
         {
             log::info!("{} {} HLS discontinuty" ,  self.name ,  self.id);
-            self.write_segment(self.prev_timestamp + AAC_FRAME_DURATION ,  true)
-                .await?; // XXX + duration
+            self.write_segment(self.segment_end() ,  true)
+                .await?;
             first_frame = true;
         }
 
         if first_frame {
             self.last_timestamp = timestamp_ms;
+        } else if timestamp_ms > self.prev_timestamp {
+            // a new segment may follow a gap ,  the distance to it is not a duration
+            self.frame_duration = timestamp_ms - self.prev_timestamp;
         }
 
         if let Err(why) = self
@@ -203,9 +257,35 @@ impl Writer {
         Ok(())
     }
 
+    async fn handle_gap(&mut self) -> Result<()> {
+        if self.prev_timestamp == INVALID_TIMESTAMP_MS {
+            return Ok(());
+        }
+        // close the segment before the gap ,  the next one is marked as a discontinuity
+        if !self.buffer.is_empty() {
+            self.write_segment(self.segment_end() ,  false)
+                .await?;
+        }
+        self.discontinuity = true;
+        self.resync = true;
+
+        Ok(())
+    }
+
     async fn handle_sample(&mut self ,  sample: MediaSample) -> Result<()> {
+        // the slate stands in until the session is resumed
+        if self.paused && self.slate.is_some() {
+            return Ok(());
+        }
+
         match sample.sample_type {
+            SampleType::AAC if sample.filler => {
+                self.handle_filler(sample.timestamp.unwrap() ,  sample.data())
+                    .await
+            }
             SampleType::AAC => {
+                self.filler_since = None;
+                self.end_slate();
                 self.handle_aac_audio(sample.timestamp.unwrap() ,  sample.data())
                     .await
             }
@@ -213,6 +293,72 @@ impl Writer {
     }
 }
 
+#[async_trait]
+impl OutputSink for Writer {
+    async fn start(&mut self ,  _sample: &MediaSample) -> Result<()> {
+        Ok(())
+    }
+
+    async fn sample(&mut self ,  sample: MediaSample) -> Result<()> {
+        self.handle_sample(sample).await
+    }
+
+    async fn gap(&mut self ,  _skipped: u64) -> Result<()> {
+        self.handle_gap().await
+    }
+
+    async fn pause(&mut self ,  reason: &StateReason) -> Result<()> {
+        self.paused = true;
+        // the slate of the reason takes over a starving one
+        self.start_slate(Some(reason)).await?;
+        Ok(())
+    }
+
+    async fn paused(&mut self) -> Result<()> {
+        self.play_slate().await
+    }
+
+    async fn resume(&mut self ,  _reason: &StateReason) -> Result<()> {
+        self.paused = false;
+        self.end_slate();
+        Ok(())
+    }
+
+    async fn stop(&mut self ,  shutdown: bool) -> Result<()> {
+        if shutdown {
+            // the server is going away ,  finish the playlist instead of
+            // waiting for a reconnection
+            if !self.buffer.is_empty() {
+                let timestamp = self.segment_end();
+                if let Err(why) = self.write_segment(timestamp ,  false).await {
+                    log::error!(
+                        "{} {} failed to write final segment: {:?}" , 
+                        self.name , 
+                        self.id , 
+                        why
+                    );
+                }
+            }
+            if let Err(why) = self.playlist.end().await {
+                log::error!(
+                    "{} {} failed to end playlist: {:?}" , 
+                    self.name , 
+                    self.id , 
+                    why
+                );
+            }
+            // files are left as they are ,  they're cleaned up on the next start
+            self.reporter.report(OutputStatus::Released);
+        } else {
+            self.playlist.release(self.reporter.clone()).await;
+        }
+
+        log::info!("{} {} destroy HLS" ,  self.name ,  self.id);
+
+        Ok(())
+    }
+}
+
 impl Drop for Writer {
     fn drop(&mut self) {
         log::info!(
@@ -239,3 +385,75 @@ fn prepare_stream_directory<P: AsRef<Path>>(path: P) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::* , 
+        crate::slate::tests::write_slate , 
+        echo_core::session::OutputKind , 
+        m3u8_rs::playlist::Playlist as M3u8Playlist , 
+        tokio::sync::mpsc , 
+    };
+
+    const ADTS_FRAME: &[u8] = &[0xFF ,  0xF1 ,  0x50 ,  0x80 ,  0x01 ,  0x1F ,  0xFC ,  0x00];
+
+    fn sample(millis: u64) -> MediaSample {
+        MediaSample::new_aac_audio(0 ,  44100 ,  2 ,  Timestamp::from_millis(millis) ,  ADTS_FRAME)
+    }
+
+    #[tokio::test]
+    async fn test_slate_splicing() {
+        let hls_root = tempfile::tempdir().unwrap();
+        let slate_dir = tempfile::tempdir().unwrap();
+        write_slate(slate_dir.path() ,  "dj");
+        let slates = Arc::new(Slates::load(slate_dir.path()).await.unwrap());
+
+        let config = Config {
+            hls_root_dir: hls_root.path().to_path_buf() , 
+            hls_target_duration: Duration::from_secs(1) , 
+            ..Config::default()
+        };
+        let (manager ,  _statuses) = mpsc::unbounded_channel();
+        let reporter = OutputReporter::new("dj".to_string() ,  1 ,  OutputKind::HLS ,  manager);
+        let (session_cleaner ,  _items) = mpsc::unbounded_channel();
+        let mut writer = Writer::create(
+            "dj".to_string() , 
+            1 , 
+            reporter , 
+            session_cleaner , 
+            &config , 
+            &MediaPlaylist::default() , 
+            &Duration::default() , 
+            slates , 
+            0 , 
+        )
+        .unwrap();
+
+        // a segment is written ,  the next one is closed by the slate
+        for frame in 0..48 {
+            writer.sample(sample(frame * 23)).await.unwrap();
+        }
+        let reason = StateReason::new(30008 ,  "live uplink starved");
+        writer.pause(&reason).await.unwrap();
+        writer.resume(&reason).await.unwrap();
+        writer.sample(sample(3000)).await.unwrap();
+        writer.stop(true).await.unwrap();
+
+        let bytes = std::fs::read(hls_root.path().join("dj").join(PLAYLIST_NAME)).unwrap();
+        let playlist = match m3u8_rs::parse_playlist_res(&bytes) {
+            Ok(M3u8Playlist::MediaPlaylist(playlist)) => playlist , 
+            _ => panic!("not a media playlist") , 
+        };
+        let discontinuities: Vec<_> = playlist
+            .segments
+            .iter()
+            .map(|segment| segment.discontinuity)
+            .collect();
+        // the slate and the session after it start with a discontinuity
+        assert_eq!(discontinuities ,  vec![true ,  false ,  true ,  true]);
+        assert_eq!(playlist.segments[2].uri ,  "/slate/dj/brb0.ts");
+        assert!(playlist.segments[3].uri.ends_with(".ts"));
+        assert!(playlist.end_list);
+    }
+}
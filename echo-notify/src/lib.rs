@@ -0,0 +1,6 @@
+mod notification;
+mod queue;
+mod sender;
+pub mod service;
+
+pub use self::service::Service;
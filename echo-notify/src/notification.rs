@@ -0,0 +1,152 @@
+use {
+    chrono::{DateTime ,  Utc} , 
+    echo_core::session::{EventMessage ,  SessionId ,  SessionProps ,  StateReason} , 
+    rand::{distributions::Alphanumeric ,  thread_rng ,  Rng} , 
+    serde::Serialize , 
+    serde_json::{json ,  Value} , 
+};
+
+#[derive(Debug ,  Serialize)]
+pub struct Notification {
+    pub id: String , 
+    pub event: String , 
+    pub name: String , 
+    pub session_id: SessionId , 
+    pub timestamp: DateTime<Utc> , 
+    pub reason: Option<StateReason> , 
+    pub props: Option<SessionProps> , 
+    pub data: Value , 
+}
+
+impl Notification {
+    fn new<E: Into<String>>(
+        event: E , 
+        name: String , 
+        session_id: SessionId , 
+        reason: Option<StateReason> , 
+        props: Option<SessionProps> , 
+        data: Value , 
+    ) -> Self {
+        Self {
+            // lets the receiver drop duplicated deliveries
+            id: thread_rng().sample_iter(&Alphanumeric).take(16).collect() , 
+            event: event.into() , 
+            name , 
+            session_id , 
+            timestamp: Utc::now() , 
+            reason , 
+            props , 
+            data , 
+        }
+    }
+
+    pub fn from_event(name: String ,  event: EventMessage) -> Option<Self> {
+        let notification = match event {
+            EventMessage::CreateSession0(id ,  proto ,  reason ,  props) => Self::new(
+                "session.created" , 
+                name , 
+                id , 
+                Some(reason) , 
+                props , 
+                json!({ "protocol": proto }) , 
+            ) , 
+            EventMessage::ReleaseSession(id ,  reason ,  props) => Self::new(
+                "session.released" , 
+                name , 
+                id , 
+                Some(reason) , 
+                props , 
+                Value::Null , 
+            ) , 
+            EventMessage::InputQualityReport(id ,  quality ,  props) => Self::new(
+                "input.quality" , 
+                name , 
+                id , 
+                None , 
+                props , 
+                json!(quality) , 
+            ) , 
+            EventMessage::RejectSession(id ,  proto ,  reason ,  props) => Self::new(
+                "session.rejected" , 
+                name , 
+                id , 
+                Some(reason) , 
+                props , 
+                json!({ "protocol": proto }) , 
+            ) , 
+            EventMessage::HandoverSession(id ,  proto ,  reason ,  props) => Self::new(
+                "session.handover" , 
+                name , 
+                id , 
+                Some(reason) , 
+                props , 
+                json!({ "protocol": proto }) , 
+            ) , 
+            // the app may count down the seconds left
+            EventMessage::ExpiringSession(id ,  remaining ,  props) => Self::new(
+                "session.expiring" , 
+                name , 
+                id , 
+                None , 
+                props , 
+                json!({ "remaining": remaining.as_secs() }) , 
+            ) , 
+            EventMessage::ExtendSession(id ,  remaining ,  props) => Self::new(
+                "session.extended" , 
+                name , 
+                id , 
+                None , 
+                props , 
+                json!({ "remaining": remaining.as_secs() }) , 
+            ) , 
+            EventMessage::PropsChanged(id ,  changed ,  props) => Self::new(
+                "session.props_changed" , 
+                name , 
+                id , 
+                None , 
+                props , 
+                json!({ "changed": changed }) , 
+            ) , 
+            // e.g. hls.ready or record.completed
+            EventMessage::OutputStatus(id ,  output ,  status ,  props) => Self::new(
+                format!("{}.{}" ,  output ,  status.name()) , 
+                name , 
+                id , 
+                None , 
+                props , 
+                json!(status) , 
+            ) , 
+            _ => return None , 
+        };
+
+        Some(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::* ,  echo_types::Protocol ,  std::time::Duration};
+
+    #[test]
+    fn test_from_event() {
+        let reason = StateReason::new(30004 ,  "handed over to a new publisher");
+        let event = EventMessage::HandoverSession(1 ,  Protocol::SRT ,  reason ,  None);
+        let notification = Notification::from_event("dj".to_string() ,  event).unwrap();
+        assert_eq!(notification.event ,  "session.handover");
+        assert_eq!(notification.name ,  "dj");
+        assert_eq!(notification.session_id ,  1);
+        assert_eq!(notification.reason.unwrap().code() ,  30004);
+        assert_eq!(notification.data ,  json!({ "protocol": "srt" }));
+        assert_eq!(notification.id.len() ,  16);
+
+        let event = EventMessage::ExpiringSession(1 ,  Duration::from_secs(60) ,  None);
+        let notification = Notification::from_event("dj".to_string() ,  event).unwrap();
+        assert_eq!(notification.event ,  "session.expiring");
+        assert!(notification.reason.is_none());
+        assert_eq!(notification.data ,  json!({ "remaining": 60 }));
+
+        // not every event is notified
+        let event = EventMessage::PauseSession(1 ,  StateReason::unknown() ,  None);
+        assert!(Notification::from_event("dj".to_string() ,  event).is_none());
+    }
+}
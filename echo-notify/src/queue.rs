@@ -0,0 +1,234 @@
+use {
+    anyhow::Result , 
+    serde::{Deserialize ,  Serialize} , 
+    std::{
+        collections::HashMap , 
+        path::{Path ,  PathBuf} , 
+        time::{Duration ,  Instant} , 
+    } , 
+    tokio::fs , 
+};
+
+pub type JobId = u64;
+
+#[derive(Debug ,  Clone ,  Serialize ,  Deserialize)]
+pub struct Job {
+    pub url: String , 
+    pub body: String , 
+    pub attempts: u32 , 
+}
+
+struct Entry {
+    job: Job , 
+    // None while a delivery is in flight
+    due: Option<Instant> , 
+}
+
+/// Pending deliveries ,  each one is kept as a file in `dir` until it is done.
+pub struct RetryQueue {
+    dir: PathBuf , 
+    next_id: JobId , 
+    entries: HashMap<JobId ,  Entry> , 
+    retry_min: Duration , 
+    retry_max: Duration , 
+}
+
+impl RetryQueue {
+    pub async fn open(dir: PathBuf ,  retry_min: Duration ,  retry_max: Duration) -> Result<Self> {
+        fs::create_dir_all(&dir).await?;
+
+        let mut entries = HashMap::new();
+        let mut next_id = 0;
+        let now = Instant::now();
+        let mut read_dir = fs::read_dir(&dir).await?;
+        while let Some(dir_entry) = read_dir.next_entry().await? {
+            let path = dir_entry.path();
+            // left by a write the server did not get to rename
+            if path.extension().map(|ext| ext == "tmp").unwrap_or_default() {
+                let _ = fs::remove_file(&path).await;
+                continue;
+            }
+            let id = match job_id(&path) {
+                Some(id) => id , 
+                None => continue , 
+            };
+            let bytes = fs::read(&path).await?;
+            let job = match serde_json::from_slice::<Job>(&bytes) {
+                Ok(job) => job , 
+                Err(_) => {
+                    log::error!("Broken notification {:?} is discarded" ,  path);
+                    let _ = fs::remove_file(&path).await;
+                    continue;
+                }
+            };
+            next_id = next_id.max(id + 1);
+            entries.insert(id ,  Entry { job ,  due: Some(now) });
+        }
+        if !entries.is_empty() {
+            log::info!("{} pending notifications are loaded from {:?}" ,  entries.len() ,  dir);
+        }
+
+        Ok(Self {
+            dir , 
+            next_id , 
+            entries , 
+            retry_min , 
+            retry_max , 
+        })
+    }
+
+    pub async fn push(&mut self ,  job: Job) -> Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let result = self.store(id ,  &job).await;
+        // deliver it anyway even if it couldn't be stored
+        self.entries.insert(
+            id , 
+            Entry {
+                job , 
+                due: Some(Instant::now()) , 
+            } , 
+        );
+        result
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.entries.values().filter_map(|entry| entry.due).min()
+    }
+
+    /// Takes the jobs to be delivered now ,  they stay in the queue until
+    /// `complete` or `retry` is called.
+    pub fn take_due(&mut self) -> Vec<(JobId ,  Job)> {
+        let now = Instant::now();
+        let mut jobs: Vec<_> = self
+            .entries
+            .iter_mut()
+            .filter(|(_ ,  entry)| matches!(entry.due ,  Some(due) if due <= now))
+            .map(|(id ,  entry)| {
+                entry.due = None;
+                (*id ,  entry.job.clone())
+            })
+            .collect();
+        jobs.sort_by_key(|(id ,  _)| *id);
+        jobs
+    }
+
+    pub async fn complete(&mut self ,  id: JobId) -> Result<()> {
+        if self.entries.remove(&id).is_some() {
+            fs::remove_file(self.path(id)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn retry(&mut self ,  id: JobId) -> Result<u32> {
+        let (job ,  delay) = match self.entries.get_mut(&id) {
+            Some(entry) => {
+                entry.job.attempts += 1;
+                let delay = backoff(entry.job.attempts ,  self.retry_min ,  self.retry_max);
+                entry.due = Some(Instant::now() + delay);
+                (entry.job.clone() ,  delay)
+            }
+            None => return Ok(0) , 
+        };
+        log::debug!("retry notification {} in {:?}" ,  id ,  delay);
+        self.store(id ,  &job).await?;
+        Ok(job.attempts)
+    }
+
+    async fn store(&self ,  id: JobId ,  job: &Job) -> Result<()> {
+        // write and rename ,  a crash must not leave a half written job
+        let tmp_path = self.dir.join(format!("{:020}.tmp" ,  id));
+        fs::write(&tmp_path ,  serde_json::to_vec(job)?).await?;
+        fs::rename(&tmp_path ,  self.path(id)).await?;
+        Ok(())
+    }
+
+    fn path(&self ,  id: JobId) -> PathBuf {
+        self.dir.join(format!("{:020}.json" ,  id))
+    }
+}
+
+fn job_id(path: &Path) -> Option<JobId> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+fn backoff(attempts: u32 ,  min: Duration ,  max: Duration) -> Duration {
+    let exp = attempts.saturating_sub(1).min(16);
+    min.checked_mul(1 << exp).unwrap_or(max).min(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(body: &str) -> Job {
+        Job {
+            url: "http://127.0.0.1/hook".to_string() , 
+            body: body.to_string() , 
+            attempts: 0 , 
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(8);
+
+        let mut queue = RetryQueue::open(dir.path().to_path_buf() ,  min ,  max)
+            .await
+            .unwrap();
+        queue.push(job("first")).await.unwrap();
+        queue.push(job("second")).await.unwrap();
+        queue.push(job("third")).await.unwrap();
+
+        let jobs = queue.take_due();
+        assert_eq!(jobs.len() ,  3);
+        assert!(queue.next_due().is_none());
+        queue.complete(jobs[0].0).await.unwrap();
+        assert_eq!(queue.retry(jobs[1].0).await.unwrap() ,  1);
+        assert!(queue.take_due().is_empty());
+
+        // everything not completed survives a restart
+        let mut queue = RetryQueue::open(dir.path().to_path_buf() ,  min ,  max)
+            .await
+            .unwrap();
+        let jobs = queue.take_due();
+        assert_eq!(jobs.len() ,  2);
+        assert_eq!(jobs[0].1.body ,  "second");
+        assert_eq!(jobs[0].1.attempts ,  1);
+        assert_eq!(jobs[1].1.body ,  "third");
+
+        queue.push(job("fourth")).await.unwrap();
+        assert_eq!(queue.take_due()[0].0 ,  jobs[1].0 + 1);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp_path = dir.path().join(format!("{:020}.tmp" ,  0));
+        fs::write(&tmp_path ,  b"{\"url\":").await.unwrap();
+
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(8);
+        let mut queue = RetryQueue::open(dir.path().to_path_buf() ,  min ,  max)
+            .await
+            .unwrap();
+        assert!(queue.take_due().is_empty());
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let min = Duration::from_secs(1);
+        let max = Duration::from_secs(300);
+        assert_eq!(backoff(1 ,  min ,  max) ,  Duration::from_secs(1));
+        assert_eq!(backoff(2 ,  min ,  max) ,  Duration::from_secs(2));
+        assert_eq!(backoff(5 ,  min ,  max) ,  Duration::from_secs(16));
+        assert_eq!(backoff(10 ,  min ,  max) ,  max);
+        assert_eq!(backoff(u32::MAX ,  min ,  max) ,  max);
+    }
+}
@@ -0,0 +1,114 @@
+use {
+    crate::queue::Job , 
+    echo_core::Config , 
+    hmac::{Hmac ,  Mac ,  NewMac} , 
+    hyper::{
+        client::HttpConnector , 
+        header::{CONTENT_TYPE ,  USER_AGENT} , 
+        Body ,  Client ,  Method ,  Request ,  StatusCode , 
+    } , 
+    sha2::Sha256 , 
+    std::time::Duration , 
+    tokio::time::timeout , 
+};
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+pub const SIGNATURE_HEADER: &str = "X-Echo-Signature";
+
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub enum Delivery {
+    Done , 
+    Retry , 
+    Rejected , 
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client<HttpConnector> , 
+    secret: Option<String> , 
+    timeout: Duration , 
+}
+
+impl Sender {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: Client::new() , 
+            secret: config.notify_secret.clone() , 
+            timeout: config.notify_timeout , 
+        }
+    }
+
+    pub async fn deliver(&self ,  job: &Job) -> Delivery {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(&job.url)
+            .header(CONTENT_TYPE ,  "application/json")
+            .header(USER_AGENT ,  format!("Echo/{}" ,  VERSION.unwrap()));
+        if let Some(ref secret) = self.secret {
+            builder = builder.header(SIGNATURE_HEADER ,  sign(secret ,  &job.body));
+        }
+        let req = match builder.body(Body::from(job.body.clone())) {
+            Ok(req) => req , 
+            Err(err) => {
+                log::error!("notification to {} is not deliverable: {}" ,  job.url ,  err);
+                return Delivery::Rejected;
+            }
+        };
+
+        match timeout(self.timeout ,  self.client.request(req)).await {
+            Ok(Ok(res)) => classify(res.status()) , 
+            Ok(Err(err)) => {
+                log::warn!("notification to {} failed: {}" ,  job.url ,  err);
+                Delivery::Retry
+            }
+            Err(_) => {
+                log::warn!("notification to {} timeout {:?}" ,  job.url ,  self.timeout);
+                Delivery::Retry
+            }
+        }
+    }
+}
+
+fn classify(status: StatusCode) -> Delivery {
+    if status.is_success() {
+        Delivery::Done
+    } else if status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+    {
+        // the endpoint will never accept it
+        Delivery::Rejected
+    } else {
+        Delivery::Retry
+    }
+}
+
+/// `sha256=<hex encoded HMAC-SHA256 of the body>`
+pub fn sign(secret: &str ,  body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(body.as_bytes());
+    format!("sha256={}" ,  hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe" ,  "what do ya want for nothing?") , 
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(StatusCode::NO_CONTENT) ,  Delivery::Done);
+        assert_eq!(classify(StatusCode::BAD_REQUEST) ,  Delivery::Rejected);
+        assert_eq!(classify(StatusCode::TOO_MANY_REQUESTS) ,  Delivery::Retry);
+        assert_eq!(classify(StatusCode::BAD_GATEWAY) ,  Delivery::Retry);
+    }
+}
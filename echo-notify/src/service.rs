@@ -0,0 +1,150 @@
+use {
+    crate::{
+        notification::Notification , 
+        queue::{Job ,  JobId ,  RetryQueue} , 
+        sender::{Delivery ,  Sender} , 
+    } , 
+    echo_core::{
+        session::{self ,  EventKind ,  ManageMessage ,  ManagerHandle} , 
+        Config , 
+    } , 
+    std::{future ,  time::Instant} , 
+    tokio::{
+        sync::mpsc , 
+        time::{delay_until ,  Instant as TokioInstant} , 
+    } , 
+};
+
+const EVENT_KINDS: [EventKind; 9] = [
+    EventKind::CreateSession0 , 
+    EventKind::ReleaseSession , 
+    EventKind::InputQualityReport , 
+    EventKind::RejectSession , 
+    EventKind::HandoverSession , 
+    EventKind::OutputStatus , 
+    EventKind::ExpiringSession , 
+    EventKind::ExtendSession , 
+    EventKind::PropsChanged , 
+];
+
+pub struct Service {
+    config: Config , 
+    session_manager: ManagerHandle , 
+}
+
+impl Service {
+    pub fn new(session_manager: ManagerHandle ,  config: Config) -> Self {
+        Self {
+            config , 
+            session_manager , 
+        }
+    }
+
+    pub async fn run(self) {
+        let urls = self.config.notify_urls();
+        let sender = Sender::new(&self.config);
+        let mut queue = match RetryQueue::open(
+            self.config.notify_queue_dir.clone() , 
+            self.config.notify_retry_min , 
+            self.config.notify_retry_max , 
+        )
+        .await
+        {
+            Ok(queue) => queue , 
+            Err(err) => {
+                log::error!("Failed to open notification queue: {}" ,  err);
+                panic!("Failed to open notification queue");
+            }
+        };
+        log::info!("Notify session events to {:?}" ,  urls);
+
+        let (trigger ,  mut trigger_watcher) = session::trigger_channel();
+
+        for kind in EVENT_KINDS.iter() {
+            if self
+                .session_manager
+                .send(ManageMessage::RegisterTrigger(*kind ,  trigger.clone()))
+                .is_err()
+            {
+                log::error!("Failed to register {:?} trigger" ,  kind);
+                panic!("Failed to register {:?} trigger" ,  kind);
+            }
+        }
+        drop(trigger);
+
+        let (delivered ,  mut delivery_watcher) = mpsc::unbounded_channel::<(JobId ,  Delivery)>();
+
+        loop {
+            let next_due = queue.next_due();
+            tokio::select! {
+                event = trigger_watcher.recv() => {
+                    let (name ,  event) = match event {
+                        Some(event) => event , 
+                        None => break , 
+                    };
+                    let notification = match Notification::from_event(name ,  event) {
+                        Some(notification) => notification , 
+                        None => continue , 
+                    };
+                    let body = match serde_json::to_string(&notification) {
+                        Ok(body) => body , 
+                        Err(err) => {
+                            log::error!("Failed to serialize {:?}: {}" ,  notification ,  err);
+                            continue;
+                        }
+                    };
+                    for url in urls.iter() {
+                        let job = Job {
+                            url: url.clone() , 
+                            body: body.clone() , 
+                            attempts: 0 , 
+                        };
+                        if let Err(err) = queue.push(job).await {
+                            log::error!("Failed to store notification {}: {}" ,  notification.id ,  err);
+                        }
+                    }
+                }
+                Some((id ,  delivery)) = delivery_watcher.recv() => {
+                    let result = match delivery {
+                        Delivery::Done => queue.complete(id).await , 
+                        Delivery::Rejected => {
+                            log::error!("notification {} is rejected" ,  id);
+                            queue.complete(id).await
+                        }
+                        Delivery::Retry => match queue.retry(id).await {
+                            Ok(attempts)
+                                if self.config.notify_retry_limit > 0
+                                    && attempts >= self.config.notify_retry_limit =>
+                            {
+                                log::error!("notification {} is dropped after {} attempts" ,  id ,  attempts);
+                                queue.complete(id).await
+                            }
+                            Ok(_) => Ok(()) , 
+                            Err(err) => Err(err) , 
+                        } , 
+                    };
+                    if let Err(err) = result {
+                        log::error!("Failed to update notification {}: {}" ,  id ,  err);
+                    }
+                }
+                _ = wait_until(next_due) => {
+                    for (id ,  job) in queue.take_due() {
+                        let sender = sender.clone();
+                        let delivered = delivered.clone();
+                        tokio::spawn(async move {
+                            let delivery = sender.deliver(&job).await;
+                            let _ = delivered.send((id ,  delivery));
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn wait_until(due: Option<Instant>) {
+    match due {
+        Some(due) => delay_until(TokioInstant::from_std(due)).await , 
+        None => future::pending().await , 
+    }
+}
@@ -2,10 +2,10 @@ use {
     anyhow::{bail ,  Result} , 
     echo_codec::aac::ADTS_FRAME_SAMPLES , 
     echo_core::{
-        session::{AppName ,  ManageMessage ,  ManagerHandle ,  SessionId ,  SessionWatcher} , 
+        session::{AppName ,  OutputReporter ,  OutputSink ,  OutputStatus ,  SessionId} , 
         Config , 
     } , 
-    echo_types::{MediaSample ,  MediaType ,  SampleType ,  Timestamp} , 
+    echo_types::{async_trait ,  MediaSample ,  MediaType ,  SampleType ,  Timestamp} , 
     mp4_rs::{
         AacConfig ,  AudioObjectType ,  Bytes ,  ChannelConfig ,  MediaConfig ,  Mp4AsyncWriter ,  Mp4Config , 
         Mp4Sample ,  SampleFreqIndex ,  TrackConfig ,  TrackType , 
@@ -15,178 +15,80 @@ use {
         path::{Path ,  PathBuf} , 
         time::SystemTime , 
     } , 
+    tempfile::NamedTempFile , 
     tokio::fs::{self ,  File} , 
 };
 
+const MICROS_PER_SEC: u64 = 1_000_000;
+
 pub struct Recorder {
     name: AppName , 
     id: SessionId , 
-    record_root: PathBuf , 
-    session_manager: ManagerHandle , 
-    session_watcher: SessionWatcher , 
+    reporter: OutputReporter , 
+    // removed if the recording never starts
+    tmp_file: NamedTempFile , 
+    begin_time: SystemTime , 
     record_path: PathBuf , 
     mp4_writer: Option<Mp4AsyncWriter<File>> , 
-    timestamp: u64 , 
+    // held back until the next one ,  its duration reaches the timestamp of
+    // the next one so that gaps are kept in the recording
+    pending_sample: Option<Mp4Sample> , 
+    // the timescale of the track ,  the sample rate
+    timescale: u64 , 
+    // the timestamp of the first sample in microseconds
+    base: Option<u64> , 
 }
 
 impl Recorder {
     pub fn create(
         name: AppName , 
         id: SessionId , 
-        session_manager: ManagerHandle , 
-        session_watcher: SessionWatcher , 
+        reporter: OutputReporter , 
         config: &Config , 
     ) -> Result<Self> {
         let record_root = config.record_root_dir.clone();
         prepare_record_directory(&record_root)?;
 
         let record_path = record_root.join(format!("{}.mp4" ,  name));
+        let tmp_file = tempfile::Builder::new()
+            .prefix(&name)
+            .suffix(".tmp")
+            .tempfile_in(&record_root)?;
 
         Ok(Self {
             name , 
             id , 
-            record_root , 
-            session_manager , 
-            session_watcher , 
+            reporter , 
+            tmp_file , 
+            begin_time: SystemTime::now() , 
             record_path , 
             mp4_writer: None , 
-            timestamp: 0 , 
+            pending_sample: None , 
+            timescale: 0 , 
+            base: None , 
         })
     }
 
-    pub async fn run(mut self) -> Result<()> {
-        let tmp_file = tempfile::Builder::new()
-            .prefix(&self.name)
-            .suffix(".tmp")
-            .tempfile_in(&self.record_root)?;
-
-        log::info!(
-            "{} {} start recording to {:?}" , 
-            self.name , 
-            self.id , 
-            &tmp_file.path()
-        );
-
-        let begin_time = SystemTime::now();
-        let mut has_recv = false;
-        let mut sid = 0;
-        while let Ok(sample) = self.session_watcher.recv().await {
-            if sample.sid < sid {
-                continue;
-            } else if sample.sid > sid {
-                sid = sample.sid;
-            }
-            if !has_recv {
-                let mut mp4_writer = Mp4AsyncWriter::async_write_start(
-                    File::create(&tmp_file).await? , 
-                    &Mp4Config {
-                        major_brand: "isom".into() , 
-                        minor_version: 512 , 
-                        compatible_brands: vec![
-                            "isom".into() , 
-                            "iso2".into() , 
-                            "avc1".into() , 
-                            "mp41".into() , 
-                        ] , 
-                        timescale: 1000 , 
-                    } , 
-                )
-                .await?;
-
-                match sample.media_type {
-                    MediaType::Audio {
-                        sample_rate , 
-                        channels , 
-                    } => {
-                        let freq_index = match sample_rate {
-                            48000 => SampleFreqIndex::Freq48000 , 
-                            44100 => SampleFreqIndex::Freq44100 , 
-                            _ => bail!("not supported sampling rate {}" ,  sample_rate) , 
-                        };
-                        let track_conf = TrackConfig {
-                            track_type: TrackType::Audio , 
-                            timescale: sample_rate , 
-                            language: String::from("und") , 
-                            media_conf: MediaConfig::AacConfig(AacConfig {
-                                bitrate: 102_000 ,                            // XXX
-                                profile: AudioObjectType::AacLowComplexity ,  // XXX
-                                freq_index , 
-                                chan_conf: ChannelConfig::try_from(channels)? , 
-                            }) , 
-                        };
-
-                        mp4_writer.add_track(&track_conf)?;
-
-                        self.mp4_writer = Some(mp4_writer);
-                    }
-                }
-
-                if let Err(_) = self
-                    .session_manager
-                    .send(ManageMessage::StartRecord(self.name.clone() ,  self.id))
-                {
-                    log::error!("Failed to send StartRecord");
-                    panic!("Failed to send StartRecord");
-                }
-
-                has_recv = true;
-            }
-            if let Err(why) = self.handle_sample(sample).await {
-                log::error!("{:?}" ,  why);
-            }
-        }
-
-        if has_recv {
-            if let Some(ref mut mp4_writer) = self.mp4_writer {
-                mp4_writer.async_write_end().await?;
-            }
-
-            self.mp4_writer = None;
-
-            fs::rename(&tmp_file.path() ,  &self.record_path).await?;
-            let difference = SystemTime::now()
-                .duration_since(begin_time)
-                .unwrap()
-                .as_secs();
-
-            log::info!(
-                "{} {} renamed {:?} to {:?} {} sec" , 
-                self.name , 
-                self.id , 
-                &tmp_file.path() , 
-                &self.record_path , 
-                difference , 
-            );
-
-            if let Err(_) = self.session_manager.send(ManageMessage::CompleteRecord(
-                self.name.clone() , 
-                self.id , 
-                self.record_path.clone() , 
-                difference , 
-            )) {
-                log::error!("Failed to send CompleteRecord");
-                panic!("Failed to send CompleteRecord");
-            }
-        } else {
-            fs::remove_file(&tmp_file.path()).await?;
-        }
-
-        Ok(())
-    }
-
-    async fn handle_aac_audio(&mut self ,  _timestamp: Timestamp ,  bytes: &[u8]) -> Result<()> {
+    async fn handle_aac_audio(&mut self ,  timestamp: Timestamp ,  bytes: &[u8]) -> Result<()> {
         if bytes.len() > 7 {
             if let Some(ref mut mp4_writer) = self.mp4_writer {
+                let timestamp = timestamp.as_micros();
+                let base = *self.base.get_or_insert(timestamp);
+                let start_time = timestamp.saturating_sub(base) * self.timescale / MICROS_PER_SEC;
                 let sample = Mp4Sample {
-                    start_time: self.timestamp , 
+                    start_time , 
                     duration: ADTS_FRAME_SAMPLES , 
                     rendering_offset: 0 , 
                     is_sync: true , 
                     bytes: Bytes::from(bytes[7..].to_vec()) , 
                 };
                 // log::info!("{:?}" ,  sample);
-                mp4_writer.async_write_sample(1 ,  &sample).await?;
-                self.timestamp += ADTS_FRAME_SAMPLES as u64;
+                if let Some(mut pending_sample) = self.pending_sample.replace(sample) {
+                    if start_time > pending_sample.start_time {
+                        pending_sample.duration = (start_time - pending_sample.start_time) as u32;
+                    }
+                    mp4_writer.async_write_sample(1 ,  &pending_sample).await?;
+                }
             }
         }
         Ok(())
@@ -202,6 +104,111 @@ impl Recorder {
     }
 }
 
+#[async_trait]
+impl OutputSink for Recorder {
+    async fn start(&mut self ,  sample: &MediaSample) -> Result<()> {
+        log::info!(
+            "{} {} start recording to {:?}" , 
+            self.name , 
+            self.id , 
+            self.tmp_file.path()
+        );
+        self.begin_time = SystemTime::now();
+
+        let mut mp4_writer = Mp4AsyncWriter::async_write_start(
+            File::create(self.tmp_file.path()).await? , 
+            &Mp4Config {
+                major_brand: "isom".into() , 
+                minor_version: 512 , 
+                compatible_brands: vec!["isom".into() ,  "iso2".into() ,  "avc1".into() ,  "mp41".into()] , 
+                timescale: 1000 , 
+            } , 
+        )
+        .await?;
+
+        match sample.media_type {
+            MediaType::Audio {
+                sample_rate , 
+                channels , 
+            } => {
+                let freq_index = match sample_rate {
+                    96000 => SampleFreqIndex::Freq96000 , 
+                    88200 => SampleFreqIndex::Freq88200 , 
+                    64000 => SampleFreqIndex::Freq64000 , 
+                    48000 => SampleFreqIndex::Freq48000 , 
+                    44100 => SampleFreqIndex::Freq44100 , 
+                    32000 => SampleFreqIndex::Freq32000 , 
+                    24000 => SampleFreqIndex::Freq24000 , 
+                    22050 => SampleFreqIndex::Freq22050 , 
+                    16000 => SampleFreqIndex::Freq16000 , 
+                    12000 => SampleFreqIndex::Freq12000 , 
+                    11025 => SampleFreqIndex::Freq11025 , 
+                    8000 => SampleFreqIndex::Freq8000 , 
+                    _ => bail!("not supported sampling rate {}" ,  sample_rate) , 
+                };
+                // 7.1 is the last channel configuration
+                let channels = if channels == 8 { 7 } else { channels };
+                let track_conf = TrackConfig {
+                    track_type: TrackType::Audio , 
+                    timescale: sample_rate , 
+                    language: String::from("und") , 
+                    media_conf: MediaConfig::AacConfig(AacConfig {
+                        bitrate: 102_000 ,                            // XXX
+                        profile: AudioObjectType::AacLowComplexity ,  // XXX
+                        freq_index , 
+                        chan_conf: ChannelConfig::try_from(channels)? , 
+                    }) , 
+                };
+
+                mp4_writer.add_track(&track_conf)?;
+
+                self.timescale = sample_rate as u64;
+
+                self.mp4_writer = Some(mp4_writer);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn sample(&mut self ,  sample: MediaSample) -> Result<()> {
+        self.handle_sample(sample).await
+    }
+
+    async fn stop(&mut self ,  _shutdown: bool) -> Result<()> {
+        if let Some(ref mut mp4_writer) = self.mp4_writer {
+            if let Some(sample) = self.pending_sample.take() {
+                mp4_writer.async_write_sample(1 ,  &sample).await?;
+            }
+            mp4_writer.async_write_end().await?;
+        }
+
+        self.mp4_writer = None;
+
+        fs::rename(self.tmp_file.path() ,  &self.record_path).await?;
+        let difference = SystemTime::now()
+            .duration_since(self.begin_time)
+            .unwrap()
+            .as_secs();
+
+        log::info!(
+            "{} {} renamed {:?} to {:?} {} sec" , 
+            self.name , 
+            self.id , 
+            self.tmp_file.path() , 
+            &self.record_path , 
+            difference , 
+        );
+
+        self.reporter.report(OutputStatus::Completed {
+            path: self.record_path.clone() , 
+            size: difference , 
+        });
+
+        Ok(())
+    }
+}
+
 impl Drop for Recorder {
     fn drop(&mut self) {
         log::info!(
@@ -2,58 +2,48 @@ use {
     crate::recorder::Recorder , 
     anyhow::Result , 
     echo_core::{
-        session::{self ,  EventKind ,  EventMessage ,  ManageMessage ,  ManagerHandle} , 
+        session::{AppName ,  OutputFactory ,  OutputKind ,  OutputReporter ,  OutputSink ,  SessionId} , 
         Config , 
     } , 
+    echo_types::async_trait , 
     std::path::Path , 
     tokio::fs , 
 };
 
 pub struct Service {
     config: Config , 
-    session_manager: ManagerHandle , 
 }
 
 impl Service {
-    pub fn new(session_manager: ManagerHandle ,  config: Config) -> Self {
-        Self {
-            config , 
-            session_manager , 
-        }
+    pub fn new(config: Config) -> Self {
+        Self { config }
     }
+}
 
-    pub async fn run(self) {
-        let record_root = self.config.record_root_dir.clone();
-        log::info!("Recording directory located at '{}'" ,  record_root.display());
-        if let Err(err) = create_dir(&record_root).await {
-            panic!("{}" ,  err);
-        }
+#[async_trait]
+impl OutputFactory for Service {
+    fn kind(&self) -> OutputKind {
+        OutputKind::RECORD
+    }
 
-        let (trigger ,  mut trigger_watcher) = session::trigger_channel();
+    fn default_selected(&self) -> bool {
+        self.config.record_default
+    }
 
-        if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::CreateSession , 
-            trigger , 
-        )) {
-            log::error!("Failed to register CreateSession trigger");
-            panic!("Failed to register CreateSession trigger");
-        }
+    async fn init(&mut self) -> Result<()> {
+        let record_root = self.config.record_root_dir.clone();
+        log::info!("Recording directory located at '{}'" ,  record_root.display());
+        create_dir(&record_root).await
+    }
 
-        while let Some((name ,  event)) = trigger_watcher.recv().await {
-            match event {
-                EventMessage::CreateSession(id ,  session_watcher) => {
-                    let session_manager = self.session_manager.clone();
-                    match Recorder::create(name ,  id ,  session_manager ,  session_watcher ,  &self.config)
-                    {
-                        Ok(recorder) => {
-                            tokio::spawn(async move { recorder.run().await.unwrap() });
-                        }
-                        Err(why) => log::error!("Failed to create recorder: {:?}" ,  why) , 
-                    }
-                }
-                _ => {}
-            }
-        }
+    async fn create(
+        &mut self , 
+        name: &AppName , 
+        id: SessionId , 
+        reporter: OutputReporter , 
+    ) -> Result<Box<dyn OutputSink>> {
+        let recorder = Recorder::create(name.clone() ,  id ,  reporter ,  &self.config)?;
+        Ok(Box::new(recorder))
     }
 }
 
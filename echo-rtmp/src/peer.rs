@@ -6,7 +6,8 @@ use {
     futures::SinkExt , 
     echo_core::{
         session::{
-            ManageMessage ,  ManagerHandle ,  MediaMessage ,  SessionHandle ,  SessionId ,  StateReason , 
+            ingest_channel ,  IngestHandle ,  IngestMessage ,  IngestWatcher ,  InputQuality ,  ManageMessage , 
+            ManagerHandle ,  MediaMessage ,  SendError ,  SessionHandle ,  SessionId ,  StateReason , 
         } , 
         Config , 
     } , 
@@ -30,10 +31,13 @@ where
     id: SessionId , 
     bytes_stream: Framed<S ,  BytesCodec> , 
     session_manager: ManagerHandle , 
+    ingest: IngestHandle , 
+    ingest_watcher: IngestWatcher , 
     rtmp_handle: RtmpHandle , 
     config: Config , 
     app_name: Option<String> , 
     exp_time: Option<Instant> , 
+    sample_count: u32 , 
     state: State , 
 }
 
@@ -42,14 +46,18 @@ where
     S: AsyncRead + AsyncWrite + Unpin , 
 {
     pub fn new(id: SessionId ,  stream: S ,  session_manager: ManagerHandle ,  config: Config) -> Self {
+        let (ingest ,  ingest_watcher) = ingest_channel();
         Self {
             id , 
             bytes_stream: Framed::new(stream ,  BytesCodec::new()) , 
             session_manager , 
+            ingest , 
+            ingest_watcher , 
             rtmp_handle: RtmpHandle::new() , 
             config , 
             app_name: None , 
             exp_time: None , 
+            sample_count: 0 , 
             state: State::Initializing , 
         }
     }
@@ -59,7 +67,20 @@ where
             match &mut self.state {
                 State::Initializing | State::Publishing(_) => {
                     let val = self.bytes_stream.try_next();
-                    match timeout(self.config.rtmp_connection_timeout ,  val).await {
+                    let res = tokio::select! {
+                        res = timeout(self.config.rtmp_connection_timeout ,  val) => res , 
+                        Some(IngestMessage::Kick(reason)) = self.ingest_watcher.recv() => {
+                            log::warn!(
+                                "{} {} RTMP session kicked: {:?}" , 
+                                self.app_name.as_deref().unwrap_or("-") , 
+                                self.id , 
+                                reason
+                            );
+                            self.disconnect_with(reason)?;
+                            continue;
+                        }
+                    };
+                    match res {
                         Ok(res) => match res {
                             Ok(Some(data)) => match self.rtmp_handle.handle_bytes(&data) {
                                 Ok(events) => {
@@ -122,9 +143,18 @@ where
             }
             Event::SendSample(sample) => {
                 if let State::Publishing(session) = &mut self.state {
-                    session
-                        .send(MediaMessage::Sample(sample))
-                        .map_err(|_| Error::SessionSendFailed)?;
+                    match session.send(MediaMessage::Sample(sample)) {
+                        Ok(()) => self.sample_count += 1 , 
+                        Err(SendError::Full) => {
+                            log::error!(
+                                "{} {} session queue is full" , 
+                                self.app_name.as_deref().unwrap_or("-") , 
+                                self.id
+                            );
+                            return self.disconnect_with(StateReason::new(30006 ,  "session queue overflow"));
+                        }
+                        Err(SendError::Closed) => return Err(Error::SessionSendFailed) , 
+                    }
                 }
                 if let Some(exp) = self.exp_time {
                     let now = Instant::now();
@@ -153,6 +183,7 @@ where
                         Protocol::RTMP , 
                         Some(stream_key.to_string()) , 
                         StateReason::unknown() , 
+                        self.ingest.clone() , 
                         request , 
                     ))
                     .map_err(|_| Error::SessionCreationFailed)?;
@@ -181,18 +212,28 @@ where
     }
 
     fn disconnect(&mut self) -> Result<() ,  Error> {
+        self.disconnect_with(StateReason::unknown())
+    }
+
+    fn disconnect_with(&mut self ,  reason: StateReason) -> Result<() ,  Error> {
         if let State::Publishing(session) = &mut self.state {
             let app_name = self.app_name.clone().unwrap();
-            session
-                .send(MediaMessage::EndOfSample)
-                .map_err(|_| Error::SessionSendFailed)?;
+            // the core session may have been ended by the manager already
+            if session.send(MediaMessage::EndOfSample).is_err() {
+                log::debug!("{} {} session has already ended" ,  app_name ,  self.id);
+            }
 
+            let quality = InputQuality {
+                total_count: self.sample_count , 
+                drop_count: session.dropped() as u32 , 
+                ..InputQuality::default()
+            };
             self.session_manager
-                .send(ManageMessage::ReleaseSession(
-                    app_name.to_string() , 
-                    self.id , 
-                    StateReason::unknown() , 
-                ))
+                .send(ManageMessage::InputQualityReport(app_name.to_string() ,  self.id ,  quality))
+                .map_err(|_| Error::SessionReleaseFailed)?;
+
+            self.session_manager
+                .send(ManageMessage::ReleaseSession(app_name.to_string() ,  self.id ,  reason))
                 .map_err(|_| Error::SessionReleaseFailed)?;
 
             log::info!("{} {} destroy rtmp session" ,  app_name ,  self.id);
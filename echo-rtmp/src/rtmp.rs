@@ -4,7 +4,7 @@ use {
         aac::{self ,  AacCoder} , 
         flv ,  FormatReader ,  FormatWriter , 
     } , 
-    echo_types::{MediaSample ,  Timestamp} , 
+    echo_types::{Duration ,  MediaSample ,  Timestamp} , 
     rml_rtmp::{
         handshake::{Handshake ,  HandshakeProcessResult ,  PeerType} , 
         sessions::{ServerSession ,  ServerSessionConfig ,  ServerSessionEvent ,  ServerSessionResult} , 
@@ -13,8 +13,6 @@ use {
     thiserror::Error , 
 };
 
-const ADTS_FRAME_SAMPLES: u32 = 1024;
-
 #[derive(Error ,  Debug)]
 pub enum Error {
     #[error("RTMP handshake failed")]
@@ -69,8 +67,12 @@ pub struct RtmpHandle {
     handshake: Handshake , 
     session: Option<ServerSession> , 
     aac_coder: AacCoder , 
+    // of the AAC core the ADTS frames are coded at
     sample_rate: u32 , 
     channels: u8 , 
+    // decoders output ,  doubled by HE-AAC
+    output_rate: u32 , 
+    frame_samples: u32 , 
     frame_count: u32 , 
 }
 
@@ -209,10 +211,10 @@ impl RtmpHandle {
                 if flv.is_sequence_header() {
                     match self.aac_coder.set_asc(flv.body.as_ref()) {
                         Ok(asc) => {
-                            if let Some(freq) = asc.sampling_frequency {
-                                self.sample_rate = freq;
-                            }
-                            self.channels = asc.channel_configuration.into();
+                            self.sample_rate = asc.core_sampling_frequency();
+                            self.channels = asc.channels();
+                            self.output_rate = asc.output_sampling_frequency();
+                            self.frame_samples = asc.frame_samples();
                         }
                         Err(err) => {
                             log::error!("audio configuration error: {}" ,  err);
@@ -229,16 +231,20 @@ impl RtmpHandle {
                 };
 
                 // XXX sid
-                let sample = MediaSample::new_aac_audio(
+                let mut sample = MediaSample::new_aac_audio(
                     0 , 
                     self.sample_rate , 
                     self.channels , 
                     Timestamp::new(
-                        ADTS_FRAME_SAMPLES as u64 * self.frame_count as u64 , 
-                        self.sample_rate as u64 , 
+                        self.frame_samples as u64 * self.frame_count as u64 , 
+                        self.output_rate as u64 , 
                     ) , 
                     aac , 
                 );
+                sample.duration = Some(Duration::new(
+                    self.frame_samples as u64 , 
+                    self.output_rate as u64 , 
+                ));
                 self.emit(Event::SendSample(sample));
                 self.frame_count += 1;
             }
@@ -273,6 +279,8 @@ impl Default for RtmpHandle {
             aac_coder: AacCoder::new() , 
             sample_rate: 48_000 , 
             channels: 2 , 
+            output_rate: 48_000 , 
+            frame_samples: aac::ADTS_FRAME_SAMPLES , 
             frame_count: 0 , 
         }
     }
@@ -1,7 +1,12 @@
+mod shutdown;
+
 use {
     anyhow::Result , 
     git_version::git_version , 
-    echo_core::{session::SessionManager ,  Config} , 
+    echo_core::{
+        session::{OutputRegistry ,  SessionManager} , 
+        Config , 
+    } , 
     echo_transfer , 
 };
 
@@ -14,6 +19,12 @@ async fn main() -> Result<()> {
         Err(err) => return Err(err.into()) , 
     };
 
+    // every publisher would be let in unchecked
+    #[cfg(not(feature = "auth"))]
+    if config.auth_enabled {
+        anyhow::bail!("AUTH_ENABLED is set ,  but echoserver is built without the auth feature");
+    }
+
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
         if args[1] == "-v" {
@@ -34,14 +45,16 @@ async fn main() -> Result<()> {
 
     let mut handles = Vec::new();
 
-    let session_manager = SessionManager::new(config.clone());
+    let mut session_manager = SessionManager::new(config.clone());
+    let output_waiter = session_manager.take_output_waiter();
     let manager_handle = session_manager.handle();
     let id_gen = session_manager.id_generator();
     handles.push(tokio::spawn(session_manager.run()));
 
-    if config.hls_enabled {
+    #[cfg(feature = "auth")]
+    if config.auth_enabled {
         handles.push(tokio::spawn({
-            echo_hls::Service::new(manager_handle.clone() ,  config.clone()).run()
+            echo_auth::Service::new(manager_handle.clone() ,  config.clone()).run()
         }));
     }
 
@@ -52,10 +65,32 @@ async fn main() -> Result<()> {
         }));
     }
 
+    let mut outputs = OutputRegistry::new(manager_handle.clone());
+
+    if config.hls_enabled {
+        outputs.register(Box::new(echo_hls::Service::new(config.clone())));
+    }
+
     #[cfg(feature = "record")]
     if config.record_enabled {
+        outputs.register(Box::new(echo_record::Service::new(config.clone())));
+    }
+
+    if !outputs.is_empty() {
+        handles.push(tokio::spawn(outputs.run()));
+    }
+
+    #[cfg(feature = "notify")]
+    if config.notify_enabled {
+        handles.push(tokio::spawn({
+            echo_notify::Service::new(manager_handle.clone() ,  config.clone()).run()
+        }))
+    }
+
+    #[cfg(feature = "admin")]
+    if config.admin_enabled {
         handles.push(tokio::spawn({
-            echo_record::Service::new(manager_handle.clone() ,  config.clone()).run()
+            echo_admin::Service::new(manager_handle.clone() ,  config.clone()).run()
         }))
     }
 
@@ -66,17 +101,16 @@ async fn main() -> Result<()> {
     //     }))
     // }
 
-    echo_transfer::Service::new(manager_handle ,  config.clone() ,  id_gen)
-        .run()
-        .await?;
+    let transfer = echo_transfer::Service::new(manager_handle.clone() ,  config.clone() ,  id_gen).run();
+    tokio::select! {
+        result = transfer => result? , 
+        _ = shutdown::signal_received() => {
+            log::info!("START GRACEFUL SHUTDOWN");
+            shutdown::drain(manager_handle ,  output_waiter ,  &config).await;
+        }
+    }
 
     log::info!("STOPPING SERVER");
 
-    // TODO: graceful shutdown
-    // wait for all spawned processes to complete
-    // for handle in handles {
-    //     handle.await?;
-    // }
-
     Ok(())
 }
@@ -0,0 +1,77 @@
+use {
+    echo_core::{
+        session::{ManageMessage ,  ManagerHandle ,  OutputWaiter ,  StateReason} , 
+        Config , 
+    } , 
+    std::time::{Duration ,  Instant} , 
+    tokio::{
+        signal::unix::{signal ,  SignalKind} , 
+        sync::oneshot , 
+        time::{delay_for ,  timeout} , 
+    } , 
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Resolves on SIGTERM or SIGINT.
+pub async fn signal_received() {
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to register SIGTERM handler");
+    let mut sigint = signal(SignalKind::interrupt()).expect("Failed to register SIGINT handler");
+
+    tokio::select! {
+        _ = sigterm.recv() => log::info!("SIGTERM received") , 
+        _ = sigint.recv() => log::info!("SIGINT received") , 
+    }
+}
+
+/// Stops accepting new sessions ,  waits for the live ones to end up to
+/// `shutdown_timeout` ,  and then for the outputs to be flushed.
+pub async fn drain(session_manager: ManagerHandle ,  outputs: Option<OutputWaiter> ,  config: &Config) {
+    let reason = StateReason::new(30001 ,  "server shutdown");
+    if session_manager
+        .send(ManageMessage::Shutdown(reason.clone()))
+        .is_err()
+    {
+        log::error!("Failed to send Shutdown");
+        return;
+    }
+
+    let deadline = Instant::now() + config.shutdown_timeout;
+    loop {
+        let (responder ,  count) = oneshot::channel();
+        if session_manager
+            .send(ManageMessage::CountSessions(responder))
+            .is_err()
+        {
+            break;
+        }
+        match count.await {
+            Ok(0) | Err(_) => break , 
+            Ok(count) if Instant::now() >= deadline => {
+                log::warn!("{} sessions are still alive after {:?}" ,  count ,  config.shutdown_timeout);
+                break;
+            }
+            Ok(count) => {
+                log::info!("Waiting for {} sessions to end" ,  count);
+                delay_for(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    if session_manager
+        .send(ManageMessage::TerminateSessions(reason))
+        .is_err()
+    {
+        log::error!("Failed to send TerminateSessions");
+        return;
+    }
+
+    if let Some(outputs) = outputs {
+        if timeout(config.shutdown_flush_timeout ,  outputs.wait())
+            .await
+            .is_err()
+        {
+            log::error!("Outputs are not flushed within {:?}" ,  config.shutdown_flush_timeout);
+        }
+    }
+}
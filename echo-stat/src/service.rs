@@ -8,7 +8,10 @@ use {
         sysinfo::sys_usage , 
     } , 
     echo_core::{
-        session::{self ,  EventKind ,  EventMessage ,  ManageMessage ,  ManagerHandle ,  SessionId} , 
+        session::{
+            self ,  EventKind ,  EventMessage ,  ManageMessage ,  ManagerHandle ,  OutputKind ,  OutputStatus , 
+            SessionId , 
+        } , 
         Config , 
     } , 
     echo_types::Protocol , 
@@ -112,51 +115,51 @@ impl Service {
         }
 
         if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::ReadyHlsSession , 
+            EventKind::InputQualityReport , 
             trigger.clone() , 
         )) {
-            log::error!("Failed to register ReadyHlsSession trigger");
-            panic!("Failed to register ReadyHlsSession trigger");
+            log::error!("Failed to register InputQualityReport trigger");
+            panic!("Failed to register InputQualityReport trigger");
         }
 
         if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::ReleaseHlsSession , 
+            EventKind::HandoverSession , 
             trigger.clone() , 
         )) {
-            log::error!("Failed to register ReleaseHlsSession trigger");
-            panic!("Failed to register ReleaseHlsSession trigger");
+            log::error!("Failed to register HandoverSession trigger");
+            panic!("Failed to register HandoverSession trigger");
         }
 
         if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::StartRecord , 
+            EventKind::SwitchSession , 
             trigger.clone() , 
         )) {
-            log::error!("Failed to register StartRecord trigger");
-            panic!("Failed to register StartRecord trigger");
+            log::error!("Failed to register SwitchSession trigger");
+            panic!("Failed to register SwitchSession trigger");
         }
 
         if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::CompleteRecord , 
+            EventKind::PropsChanged , 
             trigger.clone() , 
         )) {
-            log::error!("Failed to register CompleteRecord trigger");
-            panic!("Failed to register CompleteRecord trigger");
+            log::error!("Failed to register PropsChanged trigger");
+            panic!("Failed to register PropsChanged trigger");
         }
 
         if let Err(_) = self.session_manager.send(ManageMessage::RegisterTrigger(
-            EventKind::InputQualityReport , 
+            EventKind::OutputStatus , 
             trigger , 
         )) {
-            log::error!("Failed to register InputQualityReport trigger");
-            panic!("Failed to register InputQualityReport trigger");
+            log::error!("Failed to register OutputStatus trigger");
+            panic!("Failed to register OutputStatus trigger");
         }
 
         while let Some((name ,  event)) = trigger_watcher.recv().await {
             match event {
-                EventMessage::CreateSession0(id ,  proto ,  _ ,  _) => {
+                EventMessage::CreateSession0(id ,  proto ,  _ ,  props) => {
                     let mut sessions = sessions.write().await;
                     if !sessions.contains_key(&id) {
-                        let session = Session::new(name ,  id ,  proto);
+                        let session = Session::new(name ,  id ,  proto ,  props);
                         sessions.insert(id ,  session);
 
                         let mut session_count = session_count.write().await;
@@ -168,6 +171,50 @@ impl Service {
                         session_count.total += 1;
                     }
                 }
+                EventMessage::HandoverSession(id ,  proto ,  _ ,  _) => {
+                    let mut sessions = sessions.write().await;
+                    let mut session_count = session_count.write().await;
+
+                    if let Some(ref mut session) = sessions.get_mut(&id) {
+                        match session.protocol {
+                            Protocol::SRT => session_count.input.srt -= 1 , 
+                            Protocol::RTMP => session_count.input.rtmp -= 1 , 
+                        }
+                        match proto {
+                            Protocol::SRT => session_count.input.srt += 1 , 
+                            Protocol::RTMP => session_count.input.rtmp += 1 , 
+                        }
+                        session.protocol = proto;
+                    } else {
+                        log::warn!("HandoverSession: session not found {}({})" ,  name ,  id);
+                    }
+                }
+                EventMessage::SwitchSession(id ,  ingest_id ,  proto ,  reason ,  _) => {
+                    let mut sessions = sessions.write().await;
+                    let mut session_count = session_count.write().await;
+
+                    if let Some(ref mut session) = sessions.get_mut(&id) {
+                        match session.protocol {
+                            Protocol::SRT => session_count.input.srt -= 1 , 
+                            Protocol::RTMP => session_count.input.rtmp -= 1 , 
+                        }
+                        match proto {
+                            Protocol::SRT => session_count.input.srt += 1 , 
+                            Protocol::RTMP => session_count.input.rtmp += 1 , 
+                        }
+                        session.switch_log(id ,  ingest_id ,  proto ,  reason);
+                    } else {
+                        log::warn!("SwitchSession: session not found {}({})" ,  name ,  id);
+                    }
+                }
+                EventMessage::PropsChanged(id ,  changed ,  props) => {
+                    let mut sessions = sessions.write().await;
+                    if let Some(ref mut session) = sessions.get_mut(&id) {
+                        session.change_props(changed ,  props);
+                    } else {
+                        log::warn!("PropsChanged: session not found {}({})" ,  name ,  id);
+                    }
+                }
                 EventMessage::ReleaseSession(id ,  _ ,  _) => {
                     let mut sessions = sessions.write().await;
                     let mut session_count = session_count.write().await;
@@ -192,7 +239,12 @@ impl Service {
                         sessions.remove(&id);
                     }
                 }
-                EventMessage::ReadyHlsSession(id ,  path ,  _) => {
+                EventMessage::OutputStatus(
+                    id , 
+                    OutputKind::HLS , 
+                    OutputStatus::Ready { path } , 
+                    _ , 
+                ) => {
                     let mut sessions = sessions.write().await;
                     let mut session_count = session_count.write().await;
 
@@ -212,7 +264,7 @@ impl Service {
                         log::warn!("ReadyHlsSession: session not found {}({})" ,  name ,  id);
                     }
                 }
-                EventMessage::ReleaseHlsSession(id ,  _) => {
+                EventMessage::OutputStatus(id ,  OutputKind::HLS ,  OutputStatus::Released ,  _) => {
                     let mut sessions = sessions.write().await;
                     let mut session_count = session_count.write().await;
 
@@ -239,7 +291,7 @@ impl Service {
                         sessions.remove(&id);
                     }
                 }
-                EventMessage::StartRecord(id ,  _) => {
+                EventMessage::OutputStatus(id ,  OutputKind::RECORD ,  OutputStatus::Started ,  _) => {
                     let mut sessions = sessions.write().await;
                     let mut session_count = session_count.write().await;
 
@@ -255,7 +307,12 @@ impl Service {
                         log::warn!("StartRecord: session not found {}({})" ,  name ,  id);
                     }
                 }
-                EventMessage::CompleteRecord(id ,  path ,  _ ,  _) => {
+                EventMessage::OutputStatus(
+                    id , 
+                    OutputKind::RECORD , 
+                    OutputStatus::Completed { path ,  .. } , 
+                    _ , 
+                ) => {
                     let mut sessions = sessions.write().await;
                     let mut session_count = session_count.write().await;
 
@@ -288,6 +345,22 @@ impl Service {
                         session.quality_log(id ,  quality);
                     }
                 }
+                EventMessage::OutputStatus(id ,  output ,  OutputStatus::Started ,  _) => {
+                    let mut sessions = sessions.write().await;
+                    if let Some(ref mut session) = sessions.get_mut(&id) {
+                        session.start_output(output);
+                    } else {
+                        log::warn!("OutputStatus: session not found {}({})" ,  name ,  id);
+                    }
+                }
+                EventMessage::OutputStatus(id ,  output ,  OutputStatus::Lagged { skipped } ,  _) => {
+                    let mut sessions = sessions.write().await;
+                    if let Some(ref mut session) = sessions.get_mut(&id) {
+                        session.output_lag(output ,  skipped);
+                    } else {
+                        log::warn!("OutputStatus: session not found {}({})" ,  name ,  id);
+                    }
+                }
                 _ => {}
             }
         }
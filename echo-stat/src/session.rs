@@ -1,6 +1,6 @@
 use {
     chrono::{DateTime ,  Utc} , 
-    echo_core::session::{AppName ,  InputQuality ,  SessionId} , 
+    echo_core::session::{AppName ,  InputQuality ,  OutputKind ,  SessionId ,  SessionProps ,  StateReason} , 
     echo_types::Protocol , 
     serde::Serialize , 
     std::{collections::HashMap ,  convert::Infallible ,  path::PathBuf ,  sync::Arc} , 
@@ -24,10 +24,41 @@ pub(crate) struct Session {
     pub(crate) record_complete_time: Option<DateTime<Utc>> , 
     pub(crate) record_path: Option<PathBuf> , 
     pub(crate) ingest_quality: Option<InputQuality> , 
+    // reported periodically while an SRT connection is live
+    pub(crate) ingest_quality_time: Option<DateTime<Utc>> , 
+    pub(crate) hls_skipped: u64 , 
+    pub(crate) record_skipped: u64 , 
+    // outputs which were started for the session
+    pub(crate) outputs: Vec<OutputKind> , 
+    pub(crate) props: Option<SessionProps> , 
+    // changes of the props while the session was live
+    pub(crate) props_history: Vec<PropsChange> , 
+    // backup uplinks which took over
+    pub(crate) switches: Vec<UplinkSwitch> , 
+}
+
+#[derive(Serialize)]
+pub(crate) struct UplinkSwitch {
+    pub(crate) time: DateTime<Utc> , 
+    pub(crate) ingest_id: SessionId , 
+    pub(crate) protocol: Protocol , 
+    pub(crate) reason: StateReason , 
+}
+
+#[derive(Serialize)]
+pub(crate) struct PropsChange {
+    pub(crate) time: DateTime<Utc> , 
+    // null for a removed prop
+    pub(crate) props: HashMap<String ,  Option<String>> , 
 }
 
 impl Session {
-    pub(crate) fn new(name: AppName ,  id: SessionId ,  protocol: Protocol) -> Self {
+    pub(crate) fn new(
+        name: AppName , 
+        id: SessionId , 
+        protocol: Protocol , 
+        props: Option<SessionProps> , 
+    ) -> Self {
         let ret = Self {
             name , 
             protocol , 
@@ -40,6 +71,13 @@ impl Session {
             record_complete_time: None , 
             record_path: None , 
             ingest_quality: None , 
+            ingest_quality_time: None , 
+            hls_skipped: 0 , 
+            record_skipped: 0 , 
+            outputs: Vec::new() , 
+            props , 
+            props_history: Vec::new() , 
+            switches: Vec::new() , 
         };
         log::info!(
             "{{\"session_id\":{} , \"session_event\":\"created\" , \"session_info\":{}}}" , 
@@ -72,7 +110,14 @@ impl Session {
         }
     }
 
+    pub(crate) fn start_output(&mut self ,  output: OutputKind) {
+        if !self.outputs.contains(&output) {
+            self.outputs.push(output);
+        }
+    }
+
     pub(crate) fn start_record(&mut self) -> bool {
+        self.start_output(OutputKind::RECORD);
         if self.record_start_time.is_none() {
             self.record_start_time = Some(Utc::now());
             true
@@ -107,8 +152,54 @@ impl Session {
         }
     }
 
+    pub(crate) fn change_props(&mut self ,  changed: Vec<String> ,  props: Option<SessionProps>) {
+        let props = props.unwrap_or_default();
+        let change = changed
+            .into_iter()
+            .map(|key| {
+                let value = props.get(&key).cloned();
+                (key ,  value)
+            })
+            .collect();
+        self.props_history.push(PropsChange {
+            time: Utc::now() , 
+            props: change , 
+        });
+        self.props = Some(props);
+    }
+
+    pub(crate) fn output_lag(&mut self ,  output: OutputKind ,  skipped: u64) {
+        match output {
+            OutputKind::HLS => self.hls_skipped += skipped , 
+            OutputKind::RECORD => self.record_skipped += skipped , 
+            _ => {}
+        }
+    }
+
+    pub(crate) fn switch_log(
+        &mut self , 
+        id: SessionId , 
+        ingest_id: SessionId , 
+        protocol: Protocol , 
+        reason: StateReason , 
+    ) {
+        self.protocol = protocol;
+        self.switches.push(UplinkSwitch {
+            time: Utc::now() , 
+            ingest_id , 
+            protocol , 
+            reason , 
+        });
+        log::info!(
+            "{{\"session_id\":{} , \"session_event\":\"switch\" , \"session_info\":{}}}" , 
+            id , 
+            serde_json::to_string(self).unwrap()
+        );
+    }
+
     pub(crate) fn quality_log(&mut self ,  id: SessionId ,  quality: InputQuality) {
         self.ingest_quality = Some(quality);
+        self.ingest_quality_time = Some(Utc::now());
         log::info!(
             "{{\"session_id\":{} , \"session_event\":\"report\" , \"session_info\":{}}}" , 
             id , 
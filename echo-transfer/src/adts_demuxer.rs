@@ -1,7 +1,12 @@
 use {
     crate::demuxer::Demuxer , 
-    adts_reader::{AdtsHeader ,  AdtsHeaderError ,  ChannelConfiguration} , 
-    echo_codec::aac::ADTS_FRAME_SAMPLES , 
+    echo_codec::{
+        aac::{
+            config::AudioSpecificConfiguration ,  silent_raw_data_block ,  Aac ,  AacError , 
+            AudioDataTransportStream ,  ADTS_FRAME_SAMPLES , 
+        } , 
+        WriteFormat , 
+    } , 
     echo_core::session::InputQuality , 
     echo_types::{Duration ,  MediaSample ,  Timestamp} , 
     std::{collections::VecDeque ,  time::Instant} , 
@@ -9,37 +14,29 @@ use {
 
 const ADTS_FILLER_THRESHOD: u32 = 20;
 const ADTS_FRAME_DURATION: u32 = ADTS_FRAME_SAMPLES * 1000 / 48000;
-
-const ADTS_44100_MONO_FRAME: &'static [u8] = &[
-    0xff ,  0xf1 ,  0x50 ,  0x40 ,  0x01 ,  0x7f ,  0xfc ,  0x01 ,  0x18 ,  0x20 ,  0x07 , 
-];
-
-const ADTS_44100_STEREO_FRAME: &'static [u8] = &[
-    0xff ,  0xf1 ,  0x50 ,  0x80 ,  0x01 ,  0xbf ,  0xfc ,  0x21 ,  0x10 ,  0x04 ,  0x60 ,  0x8c ,  0x1c , 
-];
-
-const ADTS_48000_MONO_FRAME: &'static [u8] = &[
-    0xff ,  0xf1 ,  0x4c ,  0x40 ,  0x01 ,  0x7f ,  0xfc ,  0x01 ,  0x18 ,  0x20 ,  0x07 , 
-];
-
-const ADTS_48000_STEREO_FRAME: &'static [u8] = &[
-    0xff ,  0xf1 ,  0x4c ,  0x80 ,  0x01 ,  0xbf ,  0xfc ,  0x21 ,  0x10 ,  0x04 ,  0x60 ,  0x8c ,  0x1c , 
-];
+// about a second ,  a larger PTS jump is a restart of the sender
+const PTS_RESYNC_FRAMES: u64 = 50;
 
 pub struct AdtsDemuxer {
     sid: u32 , 
     name: String , 
-    current_config: [u8; 3] , 
+    current_config: Option<AudioSpecificConfiguration> , 
     start_ts: Option<Instant> , 
     media_ts: Timestamp , 
     frame_dur: Duration , 
     frame_count: u32 , 
     filler_count: u32 , 
     bad_count: u32 , 
+    // ADTS frames passed on whole ,  their AAC frames could not be split
+    unsplit_count: u32 , 
     sample_freq: u32 , 
     channels: u8 , 
+    // an ADTS frame of silence in the current configuration
+    silent_frame: Option<Vec<u8>> , 
+    // (PTS ,  frame count) the PTS of later frames are counted from
+    pts_base: Option<(u64 ,  u32)> , 
 
-    sample_queue: VecDeque<MediaSample> , 
+    pub(crate) sample_queue: VecDeque<MediaSample> , 
 }
 
 impl AdtsDemuxer {
@@ -47,159 +44,153 @@ impl AdtsDemuxer {
         Self {
             sid , 
             name: name.to_string() , 
-            current_config: [0; 3] , 
+            current_config: None , 
             start_ts: None , 
             media_ts: Timestamp::new(0 ,  0) , 
             frame_dur: Duration::new(ADTS_FRAME_DURATION as u64 ,  48_000) ,  // XXX
             frame_count: 0 , 
             filler_count: 0 , 
             bad_count: 0 , 
+            unsplit_count: 0 , 
             sample_freq: 0 , 
             channels: 0 , 
+            silent_frame: None , 
+            pts_base: None , 
 
             sample_queue: VecDeque::with_capacity(8) , 
         }
     }
 
-    fn is_new_config(&self ,  header_data: &[u8]) -> bool {
-        self.current_config != header_data[0..3]
+    fn is_new_config(&self ,  asc: &AudioSpecificConfiguration) -> bool {
+        self.current_config.as_ref() != Some(asc)
     }
 
-    fn set_config(&mut self ,  h: &AdtsHeader<'_> ,  frame_buffer: &[u8]) {
-        self.current_config.copy_from_slice(&frame_buffer[0..3]);
+    fn set_config(&mut self ,  asc: AudioSpecificConfiguration) {
+        self.sample_freq = asc.core_sampling_frequency();
+        self.channels = asc.channels();
+        self.frame_dur = Duration::new(ADTS_FRAME_SAMPLES as u64 ,  self.sample_freq as u64);
+        self.pts_base = None;
 
-        self.sample_freq = match h.sampling_frequency().freq() {
-            Some(n) => n , 
-            None => 0 , 
+        self.silent_frame = match silent_frame(&asc) {
+            Ok(frame) => Some(frame) , 
+            Err(err) => {
+                log::error!(
+                    "{} no silence for the audio configuration: {}" , 
+                    self.name , 
+                    err
+                );
+                None
+            }
         };
-        self.channels = match h.channel_configuration() {
-            ChannelConfiguration::Mono => 1 , 
-            ChannelConfiguration::Stereo => 2 , 
-            ChannelConfiguration::Three => 3 , 
-            ChannelConfiguration::Four => 4 , 
-            ChannelConfiguration::Five => 5 , 
-            ChannelConfiguration::FiveOne => 6 , 
-            ChannelConfiguration::SevenOne => 7 , 
-            _ => 0 , 
+        self.current_config = Some(asc);
+    }
+
+    // moves the frame count to a 90kHz PTS ,  but never back behind the fillers
+    fn sync_pts(&mut self ,  pts: u64) {
+        let (base_pts ,  base_frame) = match self.pts_base {
+            Some(base) => base , 
+            None => {
+                self.pts_base = Some((pts ,  self.frame_count));
+                return;
+            }
         };
-        self.frame_dur = Duration::new(ADTS_FRAME_SAMPLES as u64 ,  self.sample_freq as u64);
+        let samples = pts.saturating_sub(base_pts) * self.sample_freq as u64 / 90_000;
+        let frame = base_frame as u64
+            + (samples + ADTS_FRAME_SAMPLES as u64 / 2) / ADTS_FRAME_SAMPLES as u64;
+        let count = self.frame_count as u64;
+        if pts < base_pts || frame > count + PTS_RESYNC_FRAMES || frame + PTS_RESYNC_FRAMES < count
+        {
+            log::warn!("{} pts jump to {}" ,  self.name ,  pts);
+            self.pts_base = Some((pts ,  self.frame_count));
+        } else if frame > count {
+            self.frame_count = frame as u32;
+        }
     }
 
-    fn push_payload(&mut self ,  payload_buffer: &[u8]) {
+    fn push_payload(&mut self ,  payload_buffer: &[u8] ,  filler: bool) {
         let payload = payload_buffer.to_vec();
         self.media_ts = Timestamp::new(
             ADTS_FRAME_SAMPLES as u64 * self.frame_count as u64 , 
             self.sample_freq as u64 , 
         );
-        let sample = MediaSample::new_aac_audio(
+        let mut sample = MediaSample::new_aac_audio(
             self.sid , 
             self.sample_freq , 
             self.channels , 
             self.media_ts , 
             payload , 
         );
+        sample.filler = filler;
+        sample.duration = Some(self.frame_dur);
         self.sample_queue.push_back(sample);
         self.frame_count += 1;
     }
 
     fn is_valid_config(&self) -> bool {
-        match (self.sample_freq ,  self.channels) {
-            (48000 ,  2) => true , 
-            (48000 ,  1) => true , 
-            (44100 ,  2) => true , 
-            (44100 ,  1) => true , 
-            _ => false , 
-        }
+        (8000..=96000).contains(&self.sample_freq) && (1..=8).contains(&self.channels)
     }
 
     fn push_empty_payload(&mut self) {
-        let payload = match (self.sample_freq ,  self.channels) {
-            (48000 ,  2) => ADTS_48000_STEREO_FRAME , 
-            (48000 ,  1) => ADTS_48000_MONO_FRAME , 
-            (44100 ,  2) => ADTS_44100_STEREO_FRAME , 
-            (44100 ,  1) => ADTS_44100_MONO_FRAME , 
-            _ => {
-                log::error!(
-                    "{} unsupported audio configuration: {} ,  {}" , 
-                    self.name , 
-                    self.sample_freq , 
-                    self.channels
-                );
-                return;
-            }
-        };
-        self.push_payload(payload);
+        if let Some(frame) = self.silent_frame.clone() {
+            self.push_payload(&frame ,  true);
+        }
     }
-}
 
-impl Demuxer for AdtsDemuxer {
-    fn init(&mut self) {}
-
-    fn handle_bytes(&mut self ,  input: &[u8]) -> Vec<MediaSample> {
-        let buf = input;
-        let mut pos = 0;
-
-        let is_starving = buf.len() == 0; //XXX
-
-        while pos < buf.len() {
-            let remaining_data = &buf[pos..];
-            let h = match AdtsHeader::from_bytes(remaining_data) {
-                Ok(header) => header , 
+    /// Queues the AAC frames of the buffer ,  the first of them presented at
+    /// the 90kHz PTS if there is one.
+    pub fn push_frames(&mut self ,  buf: &[u8] ,  mut pts: Option<u64>) {
+        for frame in AudioDataTransportStream.read_frames(buf) {
+            let frame = match frame {
+                Ok(frame) => frame , 
                 Err(err) => {
-                    match err {
-                        AdtsHeaderError::BadSyncWord(n) => {
-                            log::error!("{} adts bad sync word: {:#04x}" ,  self.name ,  n);
-                        }
-                        AdtsHeaderError::BadFrameLength { minimum ,  actual } => {
-                            log::error!(
-                                "{} adts bad frame length: minimum {} but {}" , 
-                                self.name , 
-                                minimum , 
-                                actual
-                            );
-                        }
-                        AdtsHeaderError::NotEnoughData { expected ,  actual } => {
-                            log::error!(
-                                "{} adts not enough header data: expected {} but {}" , 
-                                self.name , 
-                                expected , 
-                                actual
-                            );
-                        }
-                    }
+                    log::error!("{} adts {}" ,  self.name ,  err);
                     self.bad_count += 1;
-                    break;
+                    continue;
                 }
             };
 
-            let len = h.frame_length() as usize;
-            let new_pos = pos + len;
-            if new_pos > buf.len() {
-                log::error!(
-                    "{} adts not enough payload data: expected {} but {}" , 
-                    self.name , 
-                    len , 
-                    buf.len() - pos
-                );
-                self.bad_count += 1;
-                break;
-            }
-
-            if self.is_new_config(remaining_data) {
-                self.set_config(&h ,  remaining_data);
+            let asc = frame.header.audio_specific_config();
+            if self.is_new_config(&asc) {
+                self.set_config(asc.clone());
             }
 
             if self.is_valid_config() {
                 if self.start_ts.is_none() {
                     self.start_ts = Some(Instant::now());
                 }
+                if let Some(pts) = pts.take() {
+                    self.sync_pts(pts);
+                }
 
-                self.push_payload(&buf[pos..new_pos]);
+                // an ADTS frame of each AAC frame ,  or of all of them if they
+                // could not be split
+                let unsplit = !frame.is_split();
+                let more_blocks = frame.header.number_of_raw_data_blocks;
+                if unsplit {
+                    self.unsplit_count += 1;
+                }
+                for block in frame.blocks {
+                    match AudioDataTransportStream.write_format(block ,  &asc) {
+                        Ok(mut payload) if unsplit => {
+                            // the last 2 bits of the header
+                            payload[6] |= more_blocks;
+                            self.push_payload(&payload ,  false);
+                            // the next frames are timed after all of them
+                            self.frame_count += more_blocks as u32;
+                        }
+                        Ok(payload) => self.push_payload(&payload ,  false) , 
+                        Err(err) => {
+                            log::error!("{} adts {}" ,  self.name ,  err);
+                            self.bad_count += 1;
+                        }
+                    }
+                }
             }
-
-            pos = new_pos;
         }
+    }
 
+    /// Takes the frames which are due ,  filling the gaps with silence.
+    pub fn pop_samples(&mut self ,  is_starving: bool) -> Vec<MediaSample> {
         let mut ret_queue = Vec::new();
         if let Some(start_ts) = self.start_ts {
             let system_ts = Instant::now().duration_since(start_ts).as_micros() as u64;
@@ -236,13 +227,76 @@ impl Demuxer for AdtsDemuxer {
 
         ret_queue
     }
+}
+
+// an ADTS frame with a silent payload
+fn silent_frame(asc: &AudioSpecificConfiguration) -> Result<Vec<u8> ,  AacError> {
+    let payload = silent_raw_data_block(asc)?;
+    AudioDataTransportStream.write_format(Aac::from(payload.as_slice()) ,  asc)
+}
+
+impl Demuxer for AdtsDemuxer {
+    fn init(&mut self) {}
+
+    fn handle_bytes(&mut self ,  input: &[u8]) -> Vec<MediaSample> {
+        self.push_frames(input ,  None);
+        self.pop_samples(input.is_empty())
+    }
 
     fn quality(&self) -> InputQuality {
         InputQuality {
             total_count: self.frame_count , 
-            drop_count: 0 ,  // XXX
+            drop_count: 0 ,  // counted by the session queue
             bad_count: self.bad_count , 
             filler_count: self.filler_count , 
+            unsplit_count: self.unsplit_count , 
+            ..InputQuality::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // silent frames of voice optimised apps
+    const ADTS_32000_MONO_FRAME: &[u8] = &[
+        0xff ,  0xf1 ,  0x54 ,  0x40 ,  0x01 ,  0x7f ,  0xfc ,  0x01 ,  0x18 ,  0x20 ,  0x07 , 
+    ];
+    const ADTS_22050_STEREO_FRAME: &[u8] = &[
+        0xff ,  0xf1 ,  0x5c ,  0x80 ,  0x01 ,  0xbf ,  0xfc ,  0x21 ,  0x10 ,  0x04 ,  0x60 ,  0x8c ,  0x1c , 
+    ];
+
+    #[test]
+    fn test_fillers_of_config() {
+        for frame in &[ADTS_32000_MONO_FRAME ,  ADTS_22050_STEREO_FRAME] {
+            let mut demuxer = AdtsDemuxer::new(0 ,  "dj");
+            demuxer.push_frames(frame ,  None);
+            demuxer.push_empty_payload();
+
+            let samples: Vec<_> = demuxer.sample_queue.drain(..).collect();
+            assert_eq!(samples.len() ,  2);
+            assert!(!samples[0].filler);
+            assert!(samples[1].filler);
+            assert_eq!(samples[1].data() ,  *frame);
+            assert_eq!(demuxer.quality().bad_count ,  0);
         }
     }
+
+    #[test]
+    fn test_unsplit_frames() {
+        let mut frame = ADTS_32000_MONO_FRAME.to_vec();
+        // two AAC frames without their positions
+        frame[6] |= 0x01;
+        let mut demuxer = AdtsDemuxer::new(0 ,  "dj");
+        demuxer.push_frames(&frame ,  None);
+        demuxer.push_frames(ADTS_32000_MONO_FRAME ,  None);
+
+        let samples: Vec<_> = demuxer.sample_queue.drain(..).collect();
+        assert_eq!(samples.len() ,  2);
+        assert_eq!(samples[0].data() ,  &frame[..]);
+        assert_eq!(samples[1].timestamp ,  Some(Timestamp::new(2048 ,  32000)));
+        assert_eq!(demuxer.quality().unsplit_count ,  1);
+        assert_eq!(demuxer.quality().bad_count ,  0);
+    }
 }
@@ -26,6 +26,15 @@ pub enum Error {
     #[error("invalid session cookie")]
     InvalidSessionCookie , 
 
+    #[error("invalid control token")]
+    InvalidControlToken , 
+
+    #[error("invalid request: {0}")]
+    InvalidRequest(String) , 
+
+    #[error("invalid session prop: {0}")]
+    InvalidSessionProp(String) , 
+
     #[error("session not found: {0} {1}")]
     SessionNotFound(SessionId ,  AppName) , 
 
@@ -41,6 +50,9 @@ pub enum Error {
     #[error("Failed to send to session")]
     SessionSendFailed , 
 
+    #[error("Session queue is full")]
+    SessionQueueFull , 
+
     #[error("Failed to forward session")]
     SessionForwardFailed , 
 
@@ -59,6 +71,9 @@ pub enum Error {
     #[error("SRT session not found")]
     SrtSessionNotFound , 
 
+    #[error("shutting down")]
+    ShuttingDown , 
+
     #[error("internal system error: {0}")]
     OtherString(String) , 
 
@@ -74,7 +89,11 @@ impl ResponseError for Error {
             Error::ExpiredToken => 460 , 
             Error::Unauthorized => 401 , 
             Error::InvalidSessionCookie => 400 , 
+            Error::InvalidControlToken => 401 , 
+            Error::InvalidRequest(_) => 400 , 
+            Error::InvalidSessionProp(_) => 400 , 
             Error::SessionNotFound(_ ,  _) => 404 , 
+            Error::ShuttingDown => 503 , 
             _ => 500 , 
         };
         web::HttpResponse::build(StatusCode::from_u16(status).unwrap())
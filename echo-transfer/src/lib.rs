@@ -2,8 +2,11 @@ mod adts_demuxer;
 mod demuxer;
 mod error;
 mod message;
+mod mpegts_demuxer;
 mod receiver;
+mod router;
 mod session;
+mod token;
 
 pub mod service;
 
@@ -1,5 +1,5 @@
 use {
-    echo_core::session::StateReason , 
+    echo_core::session::{InputQuality ,  StateReason} , 
     serde::{Deserialize ,  Serialize} , 
     tokio::sync::mpsc , 
 };
@@ -26,6 +26,8 @@ pub(crate) struct SessionEvent {
     pub id: u64 , 
     pub name: String , 
     pub state: SessionState , 
+    // the latest quality report
+    pub quality: Option<InputQuality> , 
 }
 
 pub(crate) fn message_channel() -> (MessageRequester ,  MessageAccepter) {
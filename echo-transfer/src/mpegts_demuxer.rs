@@ -0,0 +1,85 @@
+use {
+    crate::{adts_demuxer::AdtsDemuxer ,  demuxer::Demuxer} , 
+    echo_codec::mpegts::TsReader , 
+    echo_core::session::InputQuality , 
+    echo_types::MediaSample , 
+};
+
+/// Demuxes the AAC stream of MPEG-TS ,  as sent by encoders ,  OBS and ffmpeg.
+pub struct MpegTsDemuxer {
+    name: String , 
+    reader: TsReader , 
+    // the PES packets carry ADTS frames
+    adts: AdtsDemuxer , 
+    bad_count: u32 , 
+}
+
+impl MpegTsDemuxer {
+    pub fn new(sid: u32 ,  name: &str) -> Self {
+        Self {
+            name: name.to_string() , 
+            reader: TsReader::new() , 
+            adts: AdtsDemuxer::new(sid ,  name) , 
+            bad_count: 0 , 
+        }
+    }
+}
+
+impl Demuxer for MpegTsDemuxer {
+    fn init(&mut self) {}
+
+    fn handle_bytes(&mut self ,  input: &[u8]) -> Vec<MediaSample> {
+        self.reader.push(input);
+        while let Some(res) = self.reader.read_pes() {
+            match res {
+                Ok(pes) => self.adts.push_frames(&pes.data ,  pes.pts) , 
+                Err(err) => {
+                    log::error!("{} mpegts {}" ,  self.name ,  err);
+                    self.bad_count += 1;
+                }
+            }
+        }
+
+        self.adts.pop_samples(input.is_empty())
+    }
+
+    fn quality(&self) -> InputQuality {
+        let mut quality = self.adts.quality();
+        quality.bad_count += self.bad_count;
+        quality
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::* ,  echo_codec::mpegts::TransportStream ,  echo_types::Timestamp};
+
+    const ADTS_48000_STEREO_FRAME: &[u8] = &[
+        0xff ,  0xf1 ,  0x4c ,  0x80 ,  0x01 ,  0xbf ,  0xfc ,  0x21 ,  0x10 ,  0x04 ,  0x60 ,  0x8c ,  0x1c , 
+    ];
+
+    #[test]
+    fn test_pts_timestamps() {
+        let mut ts = TransportStream::new();
+        // the sender skipped the frames between
+        for (i ,  frame) in [0u64 ,  1 ,  5].iter().enumerate() {
+            let timestamp = Timestamp::new(1024 * frame ,  48_000);
+            ts.push_audio(timestamp ,  i == 0 ,  ADTS_48000_STEREO_FRAME.to_vec())
+                .unwrap();
+        }
+        let mut stream = Vec::new();
+        ts.write(&mut stream).unwrap();
+
+        let mut demuxer = MpegTsDemuxer::new(0 ,  "dj");
+        let mut samples = demuxer.handle_bytes(&stream);
+        // the frames not yet due are still queued
+        samples.extend(demuxer.adts.sample_queue.drain(..));
+
+        let timestamps: Vec<_> = samples
+            .iter()
+            .map(|sample| sample.timestamp.unwrap().timestamp())
+            .collect();
+        assert_eq!(timestamps ,  vec![0 ,  1024 ,  5 * 1024]);
+        assert_eq!(demuxer.quality().bad_count ,  0);
+    }
+}
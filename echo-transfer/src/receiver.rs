@@ -1,6 +1,12 @@
 use {
-    crate::{adts_demuxer::AdtsDemuxer ,  demuxer::Demuxer ,  Error} , 
-    echo_core::session::{AppName ,  InputQuality ,  MediaMessage ,  SessionHandle} , 
+    crate::{
+        adts_demuxer::AdtsDemuxer ,  demuxer::Demuxer ,  mpegts_demuxer::MpegTsDemuxer , 
+        session::SessionSettings ,  Error , 
+    } , 
+    echo_core::session::{
+        AppName ,  InputQuality ,  LinkQuality ,  MediaMessage ,  SendError ,  SessionHandle , 
+    } , 
+    echo_types::MediaFormat , 
     srt_tokio::SrtSocket , 
     std::{
         sync::Arc , 
@@ -22,10 +28,18 @@ pub(crate) fn close_request_channel() -> (CloseRequester ,  CloseAccepter) {
     oneshot::channel()
 }
 
-pub(crate) type CloseSender = mpsc::UnboundedSender<Option<InputQuality>>;
-pub(crate) type CloseReceiver = mpsc::UnboundedReceiver<Option<InputQuality>>;
+/// The counters of a connection so far.
+#[derive(Debug)]
+pub(crate) enum ReceiverReport {
+    Live(InputQuality) , 
+    // none if the connection never sent anything
+    Closed(Option<InputQuality>) , 
+}
+
+pub(crate) type ReportSender = mpsc::UnboundedSender<ReceiverReport>;
+pub(crate) type ReportReceiver = mpsc::UnboundedReceiver<ReceiverReport>;
 
-pub(crate) fn close_event_channel() -> (CloseSender ,  CloseReceiver) {
+pub(crate) fn report_channel() -> (ReportSender ,  ReportReceiver) {
     mpsc::unbounded_channel()
 }
 
@@ -34,32 +48,44 @@ pub(crate) struct SrtReceiver {
     port: u16 , 
     socket: SrtSocket , 
     read_timeout: Duration , 
+    // 0 reports on close only
+    stats_interval: Duration , 
+    // (time ,  bytes received) of the previous report
+    last_report: (Instant ,  u64) , 
     demuxer: Arc<RwLock<Box<dyn Demuxer + Send + Sync + 'static>>> , 
     session_handle: SessionHandle , 
+    // the session queue is shared with the previous connections
+    dropped_at_start: u64 , 
     close_accepter: CloseAccepter , 
-    close_sender: CloseSender , 
+    report_sender: ReportSender , 
 }
 
 impl SrtReceiver {
     pub(crate) fn new(
         sid: u32 , 
         name: &str , 
-        port: u16 , 
         socket: SrtSocket , 
-        read_timeout: Duration , 
+        settings: &SessionSettings , 
         session_handle: SessionHandle , 
         close_accepter: CloseAccepter , 
-        close_sender: CloseSender , 
+        report_sender: ReportSender , 
     ) -> Self {
+        let demuxer: Box<dyn Demuxer + Send + Sync + 'static> = match settings.format {
+            MediaFormat::MP2T => Box::new(MpegTsDemuxer::new(sid ,  name)) , 
+            _ => Box::new(AdtsDemuxer::new(sid ,  name)) , 
+        };
         Self {
             name: name.to_string() , 
-            port , 
+            port: settings.port , 
             socket , 
-            read_timeout , 
-            demuxer: Arc::new(RwLock::new(Box::new(AdtsDemuxer::new(sid ,  name)))) , 
+            read_timeout: settings.read_timeout , 
+            stats_interval: settings.stats_interval , 
+            last_report: (Instant::now() ,  0) , 
+            demuxer: Arc::new(RwLock::new(demuxer)) , 
+            dropped_at_start: session_handle.dropped() , 
             session_handle , 
             close_accepter , 
-            close_sender , 
+            report_sender , 
         }
     }
 
@@ -140,15 +166,29 @@ impl SrtReceiver {
                     }
                 }
             }
+
+            if has_started
+                && !is_closed
+                && self.stats_interval > Duration::from_secs(0)
+                && self.last_report.0.elapsed() >= self.stats_interval
+            {
+                let quality = self.quality().await;
+                if self
+                    .report_sender
+                    .send(ReceiverReport::Live(quality))
+                    .is_err()
+                {
+                    log::error!("{} {} quality report sending error" ,  self.name ,  self.port);
+                }
+            }
         }
 
         let quality = if has_started {
-            let demuxer = self.demuxer.read().await;
-            Some(demuxer.quality())
+            Some(self.quality().await)
         } else {
             None
         };
-        if let Err(err) = self.close_sender.send(quality) {
+        if let Err(err) = self.report_sender.send(ReceiverReport::Closed(quality)) {
             log::error!(
                 "{} {} close event sending error {}" , 
                 self.name , 
@@ -158,6 +198,30 @@ impl SrtReceiver {
         }
     }
 
+    async fn quality(&mut self) -> InputQuality {
+        let mut quality = self.demuxer.read().await.quality();
+        quality.drop_count = (self.session_handle.dropped() - self.dropped_at_start) as u32;
+
+        let stats = self.socket.statistics();
+        let (last_time ,  last_bytes) = self.last_report;
+        let elapsed = last_time.elapsed().as_secs_f64();
+        let bitrate = if elapsed > 0.0 {
+            ((stats.rx_bytes - last_bytes) as f64 * 8.0 / elapsed) as u64
+        } else {
+            0
+        };
+        self.last_report = (Instant::now() ,  stats.rx_bytes);
+
+        quality.link = Some(LinkQuality {
+            rtt_ms: stats.rtt.as_millis() as u32 , 
+            lost_count: stats.rx_lost as u32 , 
+            retransmitted_count: stats.rx_retransmitted as u32 , 
+            too_late_count: stats.rx_dropped as u32 , 
+            bitrate , 
+        });
+        quality
+    }
+
     async fn handle_socket(&mut self ,  drop_packet: bool) -> Result<() ,  Error> {
         if let Some((_instant ,  bytes)) = self.socket.try_next().await? {
             log::debug!("{} {} src packets received" ,  self.name ,  self.port);
@@ -175,7 +239,10 @@ impl SrtReceiver {
         for sample in demuxer.handle_bytes(input) {
             self.session_handle
                 .send(MediaMessage::Sample(sample))
-                .map_err(|_| Error::SessionSendFailed)?;
+                .map_err(|err| match err {
+                    SendError::Full => Error::SessionQueueFull , 
+                    SendError::Closed => Error::SessionSendFailed , 
+                })?;
         }
 
         Ok(())
@@ -0,0 +1,241 @@
+use {
+    crate::session::SrtCrypto , 
+    echo_core::session::{AppName ,  SessionId} , 
+    srt_tokio::{Connection ,  CryptoOptions ,  PackChan ,  RejectReason ,  SrtSocketBuilder} , 
+    std::{
+        collections::HashMap , 
+        io , 
+        net::SocketAddr , 
+        sync::{Arc ,  RwLock} , 
+        time::Duration , 
+    } , 
+    tokio::{stream::StreamExt ,  sync::mpsc} , 
+};
+
+pub(crate) type RouteSender = mpsc::UnboundedSender<(Connection ,  PackChan)>;
+pub(crate) type RouteReceiver = mpsc::UnboundedReceiver<(Connection ,  PackChan)>;
+
+/// The stream id of a caller ,  `#!::r=<name>,m=publish,s=<key>`.
+#[derive(Debug)]
+pub(crate) struct StreamId {
+    pub name: AppName , 
+    pub key: String , 
+}
+
+impl StreamId {
+    pub fn parse(stream_id: &str) -> Option<Self> {
+        let mut name = None;
+        let mut key = None;
+        for pair in stream_id.strip_prefix("#!::")?.split(',') {
+            let mut pair = pair.splitn(2 ,  '=');
+            // a pair without a value is skipped
+            let (k ,  value) = match (pair.next() ,  pair.next()) {
+                (Some(k) ,  Some(value)) => (k ,  value) , 
+                _ => continue , 
+            };
+            match (k.trim() ,  value.trim()) {
+                ("r" ,  value) => name = Some(value.to_string()) , 
+                ("s" ,  value) => key = Some(value.to_string()) , 
+                // only publishing is routed
+                ("m" ,  value) if value != "publish" => return None , 
+                _ => {}
+            }
+        }
+        match (name ,  key) {
+            (Some(name) ,  Some(key)) if !name.is_empty() => Some(Self { name ,  key }) , 
+            _ => None , 
+        }
+    }
+
+    pub fn format(name: &str ,  key: &str) -> String {
+        format!("#!::r={},m=publish,s={}" ,  name ,  key)
+    }
+}
+
+struct Route {
+    id: SessionId , 
+    key: String , 
+    // the passphrase of the session ,  the handshake of its caller is answered with it
+    crypto: Option<SrtCrypto> , 
+    sender: RouteSender , 
+}
+
+/// Accepts the SRT connections of every session on a single port and hands
+/// them to the session their stream id names.
+#[derive(Clone)]
+pub(crate) struct SrtRouter {
+    addr: SocketAddr , 
+    // also read during the handshake ,  which can not wait
+    routes: Arc<RwLock<HashMap<AppName ,  Route>>> , 
+}
+
+impl SrtRouter {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr , 
+            routes: Arc::new(RwLock::new(HashMap::new())) , 
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Replaces the route of a previous session of the same name.
+    pub async fn add(
+        &self , 
+        name: &str , 
+        id: SessionId , 
+        key: String , 
+        crypto: Option<SrtCrypto> , 
+    ) -> RouteReceiver {
+        let (sender ,  receiver) = mpsc::unbounded_channel();
+        let mut routes = self.routes.write().unwrap();
+        let route = Route {
+            id , 
+            key , 
+            crypto , 
+            sender , 
+        };
+        routes.insert(name.to_string() ,  route);
+        receiver
+    }
+
+    pub async fn remove(&self ,  name: &str ,  id: SessionId) {
+        let mut routes = self.routes.write().unwrap();
+        if matches!(routes.get(name) ,  Some(route) if route.id == id) {
+            routes.remove(name);
+        }
+    }
+
+    // a caller is refused during the handshake so that it fails at once
+    fn accept(&self ,  stream_id: Option<&str>) -> Result<Option<CryptoOptions> ,  RejectReason> {
+        let stream_id = stream_id
+            .and_then(StreamId::parse)
+            .ok_or(RejectReason::BadRequest)?;
+        match self.routes.read().unwrap().get(&stream_id.name) {
+            Some(route) if route.key == stream_id.key => {
+                Ok(route.crypto.as_ref().map(|crypto| CryptoOptions {
+                    size: crypto.key_length , 
+                    passphrase: crypto.passphrase.clone() , 
+                }))
+            }
+            Some(_) => Err(RejectReason::Unauthorized) , 
+            None => Err(RejectReason::NotFound) , 
+        }
+    }
+
+    pub async fn run(self ,  latency: Duration) {
+        let port = self.addr.port();
+        let router = self.clone();
+        let acceptor = Arc::new(move |stream_id: Option<&str>| router.accept(stream_id));
+        let binding = match SrtSocketBuilder::new_listen()
+            .local_addr(self.addr.ip())
+            .local_port(port)
+            .latency(latency)
+            .build_multiplexed_with_acceptor(acceptor)
+            .await
+        {
+            Ok(binding) => binding , 
+            Err(err) => {
+                log::error!("srt router {} listen error: {}" ,  port ,  err);
+                return;
+            }
+        };
+
+        tokio::pin!(binding);
+
+        log::info!("srt router {} listen ..." ,  port);
+
+        while let Some(res) = binding.next().await {
+            match res {
+                Ok((conn ,  pack_chan)) => self.route(conn ,  pack_chan) , 
+                // a caller with the wrong passphrase
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    log::warn!("srt router {} connection rejected: {}" ,  port ,  err);
+                }
+                Err(err) => {
+                    log::error!("srt router {} listen error: {:?}" ,  port ,  err);
+                    break;
+                }
+            }
+        }
+    }
+
+    // the route may be gone since the handshake ,  the connection is dropped
+    // and times out then
+    fn route(&self ,  conn: Connection ,  pack_chan: PackChan) {
+        let remote = conn.settings.remote;
+        let stream_id = match conn.settings.stream_id.as_deref().and_then(StreamId::parse) {
+            Some(stream_id) => stream_id , 
+            None => {
+                log::warn!(
+                    "{} srt router invalid stream id {:?}" , 
+                    remote , 
+                    conn.settings.stream_id
+                );
+                return;
+            }
+        };
+
+        let routes = self.routes.read().unwrap();
+        match routes.get(&stream_id.name) {
+            Some(route) if route.key == stream_id.key => {
+                log::info!("{} srt router {} {}" ,  remote ,  stream_id.name ,  route.id);
+                if route.sender.send((conn ,  pack_chan)).is_err() {
+                    log::warn!("{} srt router {} session closed" ,  remote ,  stream_id.name);
+                }
+            }
+            Some(_) => log::warn!("{} srt router {} invalid key" ,  remote ,  stream_id.name) , 
+            None => log::warn!("{} srt router {} session not found" ,  remote ,  stream_id.name) , 
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_id() {
+        let stream_id = StreamId::parse(&StreamId::format("dj" ,  "abc")).unwrap();
+        assert_eq!(stream_id.name ,  "dj");
+        assert_eq!(stream_id.key ,  "abc");
+
+        assert!(StreamId::parse("#!::s=abc,r=dj,u=guest").is_some());
+        // a malformed pair does not reject the rest
+        let stream_id = StreamId::parse("#!::r=dj,,guest,s=abc").unwrap();
+        assert_eq!(stream_id.name ,  "dj");
+        assert_eq!(stream_id.key ,  "abc");
+        assert!(StreamId::parse("#!::r=dj,m=request,s=abc").is_none());
+        assert!(StreamId::parse("#!::r=dj").is_none());
+        assert!(StreamId::parse("r=dj,s=abc").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_accept() {
+        let router = SrtRouter::new(SocketAddr::from(([127 ,  0 ,  0 ,  1] ,  0)));
+        let crypto = SrtCrypto {
+            key_length: 16 , 
+            passphrase: "passphrase".to_string() , 
+        };
+        let _receiver = router.add("dj" ,  1 ,  "abc".to_string() ,  Some(crypto)).await;
+
+        let stream_id = StreamId::format("dj" ,  "abc");
+        let crypto = CryptoOptions {
+            size: 16 , 
+            passphrase: "passphrase".to_string() , 
+        };
+        assert_eq!(router.accept(Some(&stream_id)) ,  Ok(Some(crypto)));
+        let stream_id = StreamId::format("dj" ,  "xyz");
+        assert_eq!(router.accept(Some(&stream_id)) ,  Err(RejectReason::Unauthorized));
+        let stream_id = StreamId::format("vj" ,  "abc");
+        assert_eq!(router.accept(Some(&stream_id)) ,  Err(RejectReason::NotFound));
+        assert_eq!(router.accept(Some("dj")) ,  Err(RejectReason::BadRequest));
+        assert_eq!(router.accept(None) ,  Err(RejectReason::BadRequest));
+
+        router.remove("dj" ,  1).await;
+        let stream_id = StreamId::format("dj" ,  "abc");
+        assert_eq!(router.accept(Some(&stream_id)) ,  Err(RejectReason::NotFound));
+    }
+}
@@ -4,7 +4,9 @@ use {
             event_channel ,  message_channel ,  EventAccepter ,  EventResponder ,  MessageRequester , 
             SessionMessage ,  SessionState , 
         } , 
-        session::EchoSession , 
+        router::{SrtRouter ,  StreamId} , 
+        session::{EchoSession ,  SessionSettings ,  SrtCrypto} , 
+        token::ControlToken , 
         Error , 
     } , 
     actix_cors::Cors , 
@@ -18,18 +20,21 @@ use {
     anyhow::Result , 
     echo_core::{
         authorization::{Authorization as EchoAuthorization ,  Error as AuthError} , 
+        config::{DuplicatePublishPolicy ,  TransportCandidate} , 
         session::{
-            AppName ,  IdGenerator ,  ManageMessage ,  ManagerHandle ,  SessionId ,  SessionProps , 
-            StateReason ,  SPROP_CLIENT_IP , 
+            self ,  AppName ,  EventKind ,  EventMessage ,  IdGenerator ,  InputQuality ,  ManageMessage , 
+            ManagerHandle ,  OutputKind ,  SessionId ,  SessionProps ,  StateReason ,  SPROP_CLIENT_IP , 
         } , 
         Config , 
     } , 
     echo_types::{MediaFormat ,  Protocol} , 
     public_ip::{dns ,  http ,  BoxToResolver ,  ToResolver} , 
+    rand::{distributions::Alphanumeric ,  thread_rng ,  Rng} , 
     serde::{Deserialize ,  Serialize} , 
     std::{
         collections::{HashMap ,  VecDeque} , 
-        net::IpAddr , 
+        iter , 
+        net::{IpAddr ,  SocketAddr} , 
         sync::Arc , 
     } , 
     tokio::sync::{oneshot ,  RwLock} , 
@@ -37,9 +42,11 @@ use {
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 const TEST_VERSION: u8 = 0;
+const PASSPHRASE_LENGTH: usize = 32;
 static OPTION_V4: &'static str =
     // r###"{"versions":[3 , 4] , "protocols":["srt" , "rtmp"] , "formats":["aac"]}"###; // XXX When the SDK bug is fixed , 
-    r###"{"versions":[1 , 2 , 3 , 4] , "protocols":["srt" , "rtmp"] , "formats":["aac"]}"###; // XXX SDK bug
+    r###"{"versions":[1 , 2 , 3 , 4 , 5] , "protocols":["srt" , "rtmp"] , "formats":["aac" , "mp2t"]}"###; // XXX SDK bug
+static OPENAPI_V5: &'static str = include_str!("../openapi.json");
 
 async fn option(_req: HttpRequest) -> HttpResponse {
     HttpResponse::Ok()
@@ -48,12 +55,27 @@ async fn option(_req: HttpRequest) -> HttpResponse {
         .body(OPTION_V4)
 }
 
+async fn openapi(_req: HttpRequest) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .header(header::SERVER ,  format!("Echo/{}" ,  VERSION.unwrap()))
+        .body(OPENAPI_V5)
+}
+
 #[derive(Debug ,  Clone ,  Serialize ,  Deserialize)]
 pub struct Transport {
     #[serde(rename = "type")]
     pub addr_type: String , 
     pub address: String , 
     pub port: u16 , 
+    // set when the sessions share a single port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String> , 
+    // set when the session is encrypted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String> , 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pbkeylen: Option<u8> , 
 }
 
 #[derive(Debug ,  Clone ,  Copy ,  Serialize ,  Deserialize)]
@@ -83,7 +105,7 @@ struct PublishRequestV4 {
     props: Option<SessionProps> , 
 }
 
-#[derive(Debug ,  Serialize ,  Deserialize)]
+#[derive(Clone ,  Debug ,  Serialize ,  Deserialize)]
 struct RtmpTransport {
     url: String , 
     name: AppName , 
@@ -96,7 +118,9 @@ enum PublishResponse {
         name: AppName , 
         transports: Vec<Transport> , 
         media: Media , 
+        // the first of the RTMP candidates
         rtmp: RtmpTransport , 
+        rtmp_transports: Vec<RtmpTransport> , 
     } , 
     #[serde(rename = "publish")]
     V4 {
@@ -104,10 +128,32 @@ enum PublishResponse {
         control: String , 
         transports: Vec<Transport> , 
         media: Media , 
+        // the first of the RTMP candidates
         rtmp: RtmpTransport , 
+        rtmp_transports: Vec<RtmpTransport> , 
+    } , 
+    #[serde(rename = "publish")]
+    V5 {
+        name: AppName , 
+        control: String , 
+        // sent back in the x-echo-token header ,  only SRT sessions have one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        token: Option<String> , 
+        transports: Vec<Transport> , 
+        media: Media , 
+        // the first of the RTMP candidates
+        rtmp: RtmpTransport , 
+        rtmp_transports: Vec<RtmpTransport> , 
     } , 
 }
 
+// the SRT session started by a publish request
+struct PublishedSession {
+    id: SessionId , 
+    port: u16 , 
+    crypto: Option<SrtCrypto> , 
+}
+
 #[derive(Debug ,  Deserialize)]
 struct CommonRequestV4 {
     reason: StateReason , 
@@ -137,24 +183,32 @@ struct ResumeResponse {
 struct StateResult {
     name: AppName , 
     state: SessionState , 
+    // the latest report of the SRT connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<InputQuality> , 
 }
 
 #[derive(Debug ,  Serialize ,  Deserialize)]
 #[serde(rename = "teardown")]
 struct StateResponse {
     state: StateResult , 
+    // set while the server is shutting down
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shutdown: Option<StateReason> , 
 }
 
 pub(crate) struct EchoSessionAvatar {
     name: AppName , 
     port: u16 , 
     state: SessionState , 
+    quality: Option<InputQuality> , 
     requester: MessageRequester , 
 }
 
 static SESSION_ID: &'static str = "id";
 static SESSION_NAME: &'static str = "name";
 static SERVER_ADDR: &'static str = "addr";
+static CONTROL_TOKEN: &'static str = "x-echo-token";
 
 fn set_session(
     session: Session , 
@@ -194,9 +248,50 @@ fn get_session(session: Session) -> Result<Option<(SessionId ,  AppName ,  IpAdd
     Ok(Some((id ,  name ,  addr)))
 }
 
+// the session a control request is for ,  and the server it runs on
+type ControlTarget = (SessionId ,  AppName ,  IpAddr);
+
+// v3 and v4 keep the session in a cookie
+fn cookie_target(session: Session ,  action: &str) -> Result<ControlTarget ,  Error> {
+    match get_session(session) {
+        Ok(opt) => match opt {
+            Some(v) => Ok(v) , 
+            None => {
+                log::error!("??? {} error - session id not found" ,  action);
+                Err(Error::InvalidSessionCookie)
+            }
+        } , 
+        Err(err) => {
+            log::error!("??? {} error - {}" ,  action ,  err);
+            Err(Error::InvalidSessionCookie)
+        }
+    }
+}
+
+// v5 keeps it in a token signed by any server sharing ECHO_PRIV_KEY
+fn token_target(
+    req: &HttpRequest , 
+    service: &ServiceInner , 
+    action: &str , 
+) -> Result<ControlTarget ,  Error> {
+    let token = req
+        .headers()
+        .get(CONTROL_TOKEN)
+        .and_then(|token| token.to_str().ok())
+        .and_then(|token| ControlToken::verify(token ,  &service.config.echo_priv_key));
+    match token {
+        Some(token) => Ok((token.id ,  token.name ,  token.addr)) , 
+        None => {
+            log::error!("??? {} error - invalid control token" ,  action);
+            Err(Error::InvalidControlToken)
+        }
+    }
+}
+
 async fn authorize(
     req: &HttpRequest , 
     name: String , 
+    props: Option<SessionProps> , 
     service: Arc<ServiceInner> , 
 ) -> Result<String ,  Error> {
     match Authorization::<Bearer>::parse(req) {
@@ -211,6 +306,7 @@ async fn authorize(
                 .send(ManageMessage::AuthorizeSession(
                     name.to_string() , 
                     auth , 
+                    props , 
                     authenticator , 
                 ))
                 .map_err(|_| Error::SessionCreationFailed)?;
@@ -218,6 +314,10 @@ async fn authorize(
                 Ok(key) => {
                     return Ok(key);
                 }
+                Err(AuthError::ExpiredToken) => {
+                    log::warn!("{} \"bearer expired token\"" ,  name);
+                    return Err(Error::ExpiredToken);
+                }
                 Err(err) => {
                     log::warn!("{} \"bearer {}\"" ,  name ,  err);
                     return Err(Error::Unauthorized);
@@ -240,6 +340,7 @@ async fn authorize(
                     .send(ManageMessage::AuthorizeSession(
                         name.to_string() , 
                         auth , 
+                        props , 
                         authenticator , 
                     ))
                     .map_err(|_| Error::SessionCreationFailed)?;
@@ -266,28 +367,109 @@ async fn authorize(
     }
 }
 
+// in the order they are advertised
+fn transport_ips(service: &ServiceInner) -> Vec<IpAddr> {
+    let priv_ip = service.config.echo_srt_priv_ip;
+    let public = match service.config.echo_srt_pub_ip {
+        Some(pub_ip) => vec![pub_ip] , 
+        None => service.public_ips.clone() , 
+    };
+    let candidates = service.config.echo_srt_transports();
+    if candidates.is_empty() {
+        return public
+            .into_iter()
+            .chain(iter::once(priv_ip))
+            .take(1)
+            .collect();
+    }
+
+    let mut ips = Vec::new();
+    for candidate in candidates {
+        let candidate_ips = match candidate {
+            TransportCandidate::Public => public.clone() , 
+            TransportCandidate::Private => vec![priv_ip] , 
+            TransportCandidate::Addr(ip) => vec![ip] , 
+        };
+        for ip in candidate_ips {
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+    if ips.is_empty() {
+        // no public address was found
+        ips.push(priv_ip);
+    }
+    ips
+}
+
+// the transport addresses a listener bound to addr can be reached on ,  the
+// first one if there is none ,  an unspecified IPv6 address is dual-stack
+fn reachable_ips(ips: &[IpAddr] ,  addr: SocketAddr) -> Vec<IpAddr> {
+    let reachable: Vec<_> = ips
+        .iter()
+        .copied()
+        .filter(|ip| match addr.ip() {
+            IpAddr::V6(bind) if bind.is_unspecified() => true , 
+            IpAddr::V4(bind) if bind.is_unspecified() => ip.is_ipv4() , 
+            bind => bind == *ip , 
+        })
+        .collect();
+    if reachable.is_empty() {
+        ips.iter().copied().take(1).collect()
+    } else {
+        reachable
+    }
+}
+
+fn url_host(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string() , 
+        IpAddr::V6(ip) => format!("[{}]" ,  ip) , 
+    }
+}
+
 fn publish_response(
     ver: u8 , 
     name: &str , 
     media: &Media , 
-    port: u16 , 
     key: String , 
+    session: Option<PublishedSession> , 
     service: Arc<ServiceInner> , 
 ) -> Result<PublishResponse ,  Error> {
-    let conn_ip = if let Some(pub_ip) = service.config.echo_srt_pub_ip {
-        pub_ip
-    } else if let Some(pub_ip) = &service.public_ip {
-        *pub_ip
-    } else {
-        service.config.echo_srt_priv_ip
-    };
-
-    let transports = match media.protocol {
-        Protocol::SRT => vec![Transport {
-            addr_type: "IPv4".to_string() , 
-            address: conn_ip.to_string() , 
-            port , 
-        }] , 
+    let ips = transport_ips(&service);
+    let conn_ip = url_host(reachable_ips(&ips ,  service.config.echo_addr)[0]);
+    let rtmp_transports: Vec<_> = reachable_ips(&ips ,  service.config.rtmp_addr)
+        .into_iter()
+        .map(|ip| RtmpTransport {
+            url: format!("rtmp://{}/{}" ,  url_host(ip) ,  name) , 
+            name: key.clone() , 
+        })
+        .collect();
+    let rtmp = rtmp_transports[0].clone();
+
+    let transports = match (media.protocol ,  &session) {
+        (Protocol::SRT ,  Some(session)) => {
+            let stream_id = service
+                .router
+                .as_ref()
+                .map(|_| StreamId::format(name ,  &key));
+            let addr = SocketAddr::new(service.config.echo_srt_bind_ip ,  session.port);
+            reachable_ips(&ips ,  addr)
+                .into_iter()
+                .map(|ip| Transport {
+                    addr_type: if ip.is_ipv6() { "IPv6" } else { "IPv4" }.to_string() , 
+                    address: ip.to_string() , 
+                    port: session.port , 
+                    stream_id: stream_id.clone() , 
+                    passphrase: session
+                        .crypto
+                        .as_ref()
+                        .map(|crypto| crypto.passphrase.clone()) , 
+                    pbkeylen: session.crypto.as_ref().map(|crypto| crypto.key_length) , 
+                })
+                .collect()
+        }
         _ => vec![] , 
     };
 
@@ -296,10 +478,28 @@ fn publish_response(
             name: name.to_string() , 
             transports , 
             media: media.clone() , 
-            rtmp: RtmpTransport {
-                url: format!("rtmp://{}/{}" ,  conn_ip ,  name) , 
-                name: key , 
-            } , 
+            rtmp , 
+            rtmp_transports , 
+        })
+    } else if ver >= 5 {
+        // a session never outlives its ttl
+        let token = session.map(|session| {
+            ControlToken::new(
+                session.id , 
+                name.to_string() , 
+                service.config.echo_srt_priv_ip , 
+                service.config.ttl_max_duration , 
+            )
+            .sign(&service.config.echo_priv_key)
+        });
+        Ok(PublishResponse::V5 {
+            name: name.to_string() , 
+            control: format!("http://{}:5021/echo/{}" ,  conn_ip ,  ver) , 
+            token , 
+            transports , 
+            media: media.clone() , 
+            rtmp , 
+            rtmp_transports , 
         })
     } else {
         Ok(PublishResponse::V4 {
@@ -307,10 +507,8 @@ fn publish_response(
             control: format!("http://{}:5021/echo/{}" ,  conn_ip ,  ver) , 
             transports , 
             media: media.clone() , 
-            rtmp: RtmpTransport {
-                url: format!("rtmp://{}/{}" ,  conn_ip ,  name) , 
-                name: key , 
-            } , 
+            rtmp , 
+            rtmp_transports , 
         })
     }
 }
@@ -324,14 +522,18 @@ async fn publish_test(
 ) -> Result<HttpResponse ,  Error> {
     let service = service.as_ref();
     let name = path.into_inner();
-    let port = service.config.echo_srt_min_port;
+    let session = PublishedSession {
+        id: 0 , 
+        port: service.config.echo_srt_min_port , 
+        crypto: None , 
+    };
 
     match publish_response(
         TEST_VERSION , 
         &name , 
         &pub_req.0.media , 
-        port , 
         "key".to_string() , 
+        Some(session) , 
         service.clone() , 
     ) {
         Ok(res) => Ok(HttpResponse::Ok()
@@ -351,49 +553,100 @@ async fn publish(
     service: Arc<ServiceInner> , 
 ) -> Result<HttpResponse ,  Error> {
     log::info!("{} publish request {:?}" ,  name ,  pub_req);
-    let key = authorize(&http_req ,  name.clone() ,  service.clone()).await?;
-    if let Some(mut props) = pub_req.props {
+    if service.shutdown.read().await.is_some() {
+        log::warn!("{} publish rejected - shutting down" ,  name);
+        return Err(Error::ShuttingDown);
+    }
+    if matches!(pub_req.media.protocol ,  Protocol::SRT)
+        && matches!(pub_req.media.format ,  MediaFormat::FLV)
+    {
+        log::warn!("{} publish rejected - srt does not carry flv" ,  name);
+        return Err(Error::InvalidRequest("srt does not carry flv".to_string()));
+    }
+    let props = pub_req.props.map(|mut props| {
         props.insert(
             SPROP_CLIENT_IP.to_string() , 
             http_req.peer_addr().unwrap().ip().to_string() , 
         );
+        props
+    });
+    for output in [OutputKind::HLS ,  OutputKind::RECORD].iter() {
+        if output.selected(props.as_ref() ,  true).is_none() {
+            log::warn!("{} publish rejected - invalid {} prop" ,  name ,  output);
+            return Err(Error::InvalidSessionProp(output.to_string()));
+        }
+    }
+    let key = authorize(&http_req ,  name.clone() ,  props.clone() ,  service.clone()).await?;
+    if let Some(props) = props {
         service
             .session_manager
             .send(ManageMessage::UpdateSessionProps(name.clone() ,  props))
             .map_err(|_| Error::SessionCreationFailed)?;
     }
 
-    let mut port = 0;
+    let mut published = None;
     match pub_req.media.protocol {
         Protocol::SRT => {
             let session_id = service.id_gen.fetch_next();
-            port = {
-                let mut ports = service.ports.write().await;
-                if ports.is_empty() {
-                    log::error!("{} publish error - no available port" ,  name , );
-                    return Err(Error::OtherStr("no available port"));
+            let port;
+            let mut crypto = None;
+            // v3 clients can not get a passphrase
+            if ver >= 4 && service.config.echo_srt_key_length > 0 {
+                crypto = Some(SrtCrypto {
+                    key_length: service.config.echo_srt_key_length , 
+                    passphrase: thread_rng()
+                        .sample_iter(&Alphanumeric)
+                        .take(PASSPHRASE_LENGTH)
+                        .collect() , 
+                });
+            }
+            let route = match service.router {
+                Some(ref router) => {
+                    port = router.port();
+                    Some(router.add(&name ,  session_id ,  key.clone() ,  crypto.clone()).await)
+                }
+                None => {
+                    port = {
+                        let mut ports = service.ports.write().await;
+                        if ports.is_empty() {
+                            log::error!("{} publish error - no available port" ,  name , );
+                            return Err(Error::OtherStr("no available port"));
+                        }
+                        ports.pop_front().unwrap()
+                    };
+                    None
                 }
-                ports.pop_front().unwrap()
             };
 
             let responder = service.responder.clone();
             let (requester ,  accepter) = message_channel();
+            let settings = SessionSettings {
+                bind_ip: service.config.echo_srt_bind_ip , 
+                port , 
+                crypto: crypto.clone() , 
+                format: pub_req.media.format , 
+                connection_timeout: service.config.echo_srt_connection_timeout , 
+                read_timeout: service.config.echo_srt_read_timeout , 
+                latency: service.config.echo_srt_latency , 
+                stats_interval: service.config.echo_srt_stats_interval , 
+                failover: service.config.duplicate_publish_policy == DuplicatePublishPolicy::Backup , 
+            };
             let echo_session = EchoSession::new(
                 session_id , 
                 &name , 
-                port , 
-                service.config.echo_srt_connection_timeout , 
-                service.config.echo_srt_read_timeout , 
-                service.config.echo_srt_latency , 
+                settings , 
+                route , 
                 accepter , 
                 responder , 
                 service.session_manager.clone() , 
-                pub_req.reason , 
             );
+            // ahead of any later request ,  the session holds the accepter so it can not fail
+            let _ = requester.send(SessionMessage::Init(pub_req.reason));
             let session_avatar = EchoSessionAvatar {
                 name: name.to_string() , 
                 port , 
                 state: SessionState::Init , 
+                quality: None , 
                 requester , 
             };
 
@@ -401,22 +654,29 @@ async fn publish(
                 let mut sessions = service.sessions.write().await;
                 sessions.insert(session_id ,  session_avatar);
 
-                if let Err(err) =
-                    set_session(session ,  session_id ,  &name ,  service.config.echo_srt_priv_ip)
-                {
-                    let mut ports = service.ports.write().await;
-                    ports.push_back(port);
-                    log::error!("{} publish error - {}\"" ,  name ,  err);
-                    return Err(Error::OtherString(err.to_string()));
+                // v5 identifies the session by the control token instead
+                if ver < 5 {
+                    if let Err(err) =
+                        set_session(session ,  session_id ,  &name ,  service.config.echo_srt_priv_ip)
+                    {
+                        release_port(&service ,  &name ,  session_id ,  port).await;
+                        log::error!("{} publish error - {}\"" ,  name ,  err);
+                        return Err(Error::OtherString(err.to_string()));
+                    }
                 }
             }
 
             tokio::spawn(echo_session.run());
+            published = Some(PublishedSession {
+                id: session_id , 
+                port , 
+                crypto , 
+            });
         }
         _ => {}
     }
 
-    match publish_response(ver ,  &name ,  &pub_req.media ,  port ,  key ,  service.clone()) {
+    match publish_response(ver ,  &name ,  &pub_req.media ,  key ,  published ,  service.clone()) {
         Ok(res) => Ok(HttpResponse::Ok()
             .content_type("application/json")
             .header(header::SERVER ,  format!("Echo/{}" ,  VERSION.unwrap()))
@@ -456,6 +716,19 @@ async fn publish_v4(
     publish(4 ,  name ,  pub_req ,  http_req ,  session ,  service).await
 }
 
+async fn publish_v5(
+    path: web::Path<String> , 
+    pub_req: web::Json<PublishRequestV4> , 
+    http_req: HttpRequest , 
+    session: Session , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let name = path.into_inner();
+    let pub_req = pub_req.into_inner();
+    let service = service.get_ref().clone();
+    publish(5 ,  name ,  pub_req ,  http_req ,  session ,  service).await
+}
+
 async fn forward(
     req: &HttpRequest , 
     server_ip: IpAddr , 
@@ -511,22 +784,10 @@ async fn teardown_test(
 async fn teardown(
     req: CommonRequestV4 , 
     http_req: HttpRequest , 
-    session: Session , 
+    target: ControlTarget , 
     service: Arc<ServiceInner> , 
 ) -> Result<HttpResponse ,  Error> {
-    let (session_id ,  session_name ,  server_addr) = match get_session(session) {
-        Ok(opt) => match opt {
-            Some(v) => v , 
-            None => {
-                log::error!("??? teardown error - session id not found");
-                return Err(Error::InvalidSessionCookie);
-            }
-        } , 
-        Err(err) => {
-            log::error!("??? teardown error - {}" ,  err);
-            return Err(Error::InvalidSessionCookie);
-        }
-    };
+    let (session_id ,  session_name ,  server_addr) = target;
 
     if service.config.echo_srt_priv_ip == server_addr {
         let mut sessions = service.sessions.write().await;
@@ -586,8 +847,9 @@ async fn teardown_v3(
     let req = CommonRequestV4 {
         reason: StateReason::unknown() , 
     };
+    let target = cookie_target(session ,  "teardown")?;
     let service = service.get_ref().clone();
-    teardown(req ,  http_req ,  session ,  service).await
+    teardown(req ,  http_req ,  target ,  service).await
 }
 
 async fn teardown_v4(
@@ -597,29 +859,29 @@ async fn teardown_v4(
     service: web::Data<Arc<ServiceInner>> , 
 ) -> Result<HttpResponse ,  Error> {
     let req = req.into_inner();
+    let target = cookie_target(session ,  "teardown")?;
     let service = service.get_ref().clone();
-    teardown(req ,  http_req ,  session ,  service).await
+    teardown(req ,  http_req ,  target ,  service).await
 }
 
-async fn pause_v4(
+async fn teardown_v5(
     req: web::Json<CommonRequestV4> , 
     http_req: HttpRequest , 
-    session: Session , 
     service: web::Data<Arc<ServiceInner>> , 
 ) -> Result<HttpResponse ,  Error> {
-    let (session_id ,  session_name ,  server_addr) = match get_session(session) {
-        Ok(opt) => match opt {
-            Some(v) => v , 
-            None => {
-                log::error!("??? pause error - session id not found");
-                return Err(Error::InvalidSessionCookie);
-            }
-        } , 
-        Err(err) => {
-            log::error!("??? pause error - {}" ,  err);
-            return Err(Error::InvalidSessionCookie);
-        }
-    };
+    let req = req.into_inner();
+    let target = token_target(&http_req ,  &service ,  "teardown")?;
+    let service = service.get_ref().clone();
+    teardown(req ,  http_req ,  target ,  service).await
+}
+
+async fn pause(
+    req: CommonRequestV4 , 
+    http_req: HttpRequest , 
+    target: ControlTarget , 
+    service: Arc<ServiceInner> , 
+) -> Result<HttpResponse ,  Error> {
+    let (session_id ,  session_name ,  server_addr) = target;
 
     if service.config.echo_srt_priv_ip == server_addr {
         let mut sessions = service.sessions.write().await;
@@ -627,7 +889,7 @@ async fn pause_v4(
             let name = session_avatar.name.to_string();
             if let Err(_) = session_avatar
                 .requester
-                .send(SessionMessage::Pause(req.into_inner().reason))
+                .send(SessionMessage::Pause(req.reason))
             {
                 log::error!("{} pause error - session shutdown send error" ,  name);
             }
@@ -664,25 +926,34 @@ async fn pause_v4(
     }
 }
 
-async fn resume_v4(
+async fn pause_v4(
     req: web::Json<CommonRequestV4> , 
     http_req: HttpRequest , 
     session: Session , 
     service: web::Data<Arc<ServiceInner>> , 
 ) -> Result<HttpResponse ,  Error> {
-    let (session_id ,  session_name ,  server_addr) = match get_session(session) {
-        Ok(opt) => match opt {
-            Some(v) => v , 
-            None => {
-                log::error!("??? resume error - session id not found");
-                return Err(Error::InvalidSessionCookie);
-            }
-        } , 
-        Err(err) => {
-            log::error!("??? resume error - {}" ,  err);
-            return Err(Error::InvalidSessionCookie);
-        }
-    };
+    let target = cookie_target(session ,  "pause")?;
+    let service = service.get_ref().clone();
+    pause(req.into_inner() ,  http_req ,  target ,  service).await
+}
+
+async fn pause_v5(
+    req: web::Json<CommonRequestV4> , 
+    http_req: HttpRequest , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let target = token_target(&http_req ,  &service ,  "pause")?;
+    let service = service.get_ref().clone();
+    pause(req.into_inner() ,  http_req ,  target ,  service).await
+}
+
+async fn resume(
+    req: CommonRequestV4 , 
+    http_req: HttpRequest , 
+    target: ControlTarget , 
+    service: Arc<ServiceInner> , 
+) -> Result<HttpResponse ,  Error> {
+    let (session_id ,  session_name ,  server_addr) = target;
 
     if service.config.echo_srt_priv_ip == server_addr {
         let mut sessions = service.sessions.write().await;
@@ -690,7 +961,7 @@ async fn resume_v4(
             let name = session_avatar.name.to_string();
             if let Err(_) = session_avatar
                 .requester
-                .send(SessionMessage::Resume(req.into_inner().reason))
+                .send(SessionMessage::Resume(req.reason))
             {
                 log::error!("{} resume error - session shutdown send error" ,  name);
             }
@@ -727,6 +998,27 @@ async fn resume_v4(
     }
 }
 
+async fn resume_v4(
+    req: web::Json<CommonRequestV4> , 
+    http_req: HttpRequest , 
+    session: Session , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let target = cookie_target(session ,  "resume")?;
+    let service = service.get_ref().clone();
+    resume(req.into_inner() ,  http_req ,  target ,  service).await
+}
+
+async fn resume_v5(
+    req: web::Json<CommonRequestV4> , 
+    http_req: HttpRequest , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let target = token_target(&http_req ,  &service ,  "resume")?;
+    let service = service.get_ref().clone();
+    resume(req.into_inner() ,  http_req ,  target ,  service).await
+}
+
 async fn state_test(
     _req: HttpRequest , 
     _session: Session , 
@@ -739,30 +1031,28 @@ async fn state_test(
         .content_type("application/json")
         .header(header::SERVER ,  format!("Echo/{}" ,  VERSION.unwrap()))
         .json(StateResponse {
-            state: StateResult { name ,  state } , 
+            state: StateResult {
+                name , 
+                state , 
+                quality: None , 
+            } , 
+            shutdown: None , 
         }))
 }
 
-async fn state_v4(
+async fn state(
     req: HttpRequest , 
-    session: Session , 
-    service: web::Data<Arc<ServiceInner>> , 
+    target: ControlTarget , 
+    service: Arc<ServiceInner> , 
 ) -> Result<HttpResponse ,  Error> {
-    let (session_id ,  session_name ,  server_addr) = match get_session(session) {
-        Ok(opt) => match opt {
-            Some(v) => v , 
-            None => {
-                log::error!("??? state error - session id not found");
-                return Err(Error::InvalidSessionCookie);
-            }
-        } , 
-        Err(err) => {
-            log::error!("??? state error - {}" ,  err);
-            return Err(Error::InvalidSessionCookie);
-        }
-    };
+    let (session_id ,  session_name ,  server_addr) = target;
 
     if service.config.echo_srt_priv_ip == server_addr {
+        let shutdown = if service.config.shutdown_notify_clients {
+            service.shutdown.read().await.clone()
+        } else {
+            None
+        };
         let sessions = service.sessions.read().await;
         if let Some(ref session_avatar) = sessions.get(&session_id) {
             Ok(HttpResponse::Ok()
@@ -772,7 +1062,9 @@ async fn state_v4(
                     state: StateResult {
                         name: session_avatar.name.to_string() , 
                         state: session_avatar.state , 
+                        quality: session_avatar.quality , 
                     } , 
+                    shutdown , 
                 }))
         } else {
             log::error!("{} state error - session not found" ,  session_name);
@@ -800,15 +1092,36 @@ async fn state_v4(
     }
 }
 
+async fn state_v4(
+    req: HttpRequest , 
+    session: Session , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let target = cookie_target(session ,  "state")?;
+    let service = service.get_ref().clone();
+    state(req ,  target ,  service).await
+}
+
+async fn state_v5(
+    req: HttpRequest , 
+    service: web::Data<Arc<ServiceInner>> , 
+) -> Result<HttpResponse ,  Error> {
+    let target = token_target(&req ,  &service ,  "state")?;
+    let service = service.get_ref().clone();
+    state(req ,  target ,  service).await
+}
+
 #[derive(Clone)]
 pub(crate) struct ServiceInner {
     pub config: Config , 
     pub session_manager: ManagerHandle , 
     pub id_gen: IdGenerator , 
-    public_ip: Option<IpAddr> , 
+    public_ips: Vec<IpAddr> , 
     sessions: Arc<RwLock<HashMap<SessionId ,  EchoSessionAvatar>>> , 
     ports: Arc<RwLock<VecDeque<u16>>> , 
+    router: Option<SrtRouter> , 
     responder: EventResponder , 
+    shutdown: Arc<RwLock<Option<StateReason>>> , 
 }
 
 pub struct Service {
@@ -817,6 +1130,14 @@ pub struct Service {
     id_gen: IdGenerator , 
 }
 
+// the single port stays open ,  only the route of the session goes away
+async fn release_port(service: &ServiceInner ,  name: &str ,  id: SessionId ,  port: u16) {
+    match service.router {
+        Some(ref router) => router.remove(name ,  id).await , 
+        None => service.ports.write().await.push_back(port) , 
+    }
+}
+
 async fn background_service(service: Arc<ServiceInner> ,  mut accepter: EventAccepter) -> Result<()> {
     while let Some(event) = accepter.recv().await {
         let session_id = event.id;
@@ -826,14 +1147,18 @@ async fn background_service(service: Arc<ServiceInner> ,  mut accepter: EventAcc
             SessionState::Terminated => {
                 if let Some(ref mut session_avatar) = sessions.remove(&session_id) {
                     log::info!("{} session terminated" ,  session_name);
-                    let mut ports = service.ports.write().await;
-                    ports.push_back(session_avatar.port);
+                    release_port(&service ,  &session_name ,  session_id ,  session_avatar.port).await;
                 }
             }
             state => {
                 if let Some(ref mut session_avatar) = sessions.get_mut(&session_id) {
-                    log::info!("{} session state changed {:?}" ,  session_name ,  state);
-                    session_avatar.state = state;
+                    if session_avatar.state != state {
+                        log::info!("{} session state changed {:?}" ,  session_name ,  state);
+                        session_avatar.state = state;
+                    }
+                    if event.quality.is_some() {
+                        session_avatar.quality = event.quality;
+                    }
                 }
             }
         }
@@ -841,6 +1166,26 @@ async fn background_service(service: Arc<ServiceInner> ,  mut accepter: EventAcc
     Ok(())
 }
 
+async fn shutdown_watcher(service: Arc<ServiceInner>) {
+    let (trigger ,  mut trigger_watcher) = session::trigger_channel();
+    if service
+        .session_manager
+        .send(ManageMessage::RegisterTrigger(EventKind::Shutdown ,  trigger))
+        .is_err()
+    {
+        log::error!("Failed to register Shutdown trigger");
+        panic!("Failed to register Shutdown trigger");
+    }
+
+    while let Some((_ ,  event)) = trigger_watcher.recv().await {
+        if let EventMessage::Shutdown(reason) = event {
+            log::info!("Stop accepting publish requests");
+            let mut shutdown = service.shutdown.write().await;
+            *shutdown = Some(reason);
+        }
+    }
+}
+
 impl Service {
     pub fn new(session_manager: ManagerHandle ,  config: Config ,  id_gen: IdGenerator) -> Self {
         Self {
@@ -851,24 +1196,40 @@ impl Service {
     }
 
     pub async fn run(self) -> Result<()> {
-        let public_ip = public_ip().await;
+        let public_ips = if self.config.echo_public_ip_discovery {
+            public_ips(self.config.echo_srt_bind_ip.is_ipv6()).await
+        } else {
+            vec![]
+        };
         let sessions = Arc::new(RwLock::new(HashMap::new()));
         let ports = Arc::new(RwLock::new(
             (self.config.echo_srt_min_port..=self.config.echo_srt_max_port).collect() , 
         ));
+        let bind_ip = self.config.echo_srt_bind_ip;
+        let router = self
+            .config
+            .echo_srt_port
+            .map(|port| SrtRouter::new(SocketAddr::new(bind_ip ,  port)));
         let (responder ,  accepter) = event_channel();
 
+        if let Some(ref router) = router {
+            tokio::spawn(router.clone().run(self.config.echo_srt_latency));
+        }
+
         let service = Arc::new(ServiceInner {
             config: self.config.clone() , 
             session_manager: self.session_manager.clone() , 
             id_gen: self.id_gen.clone() , 
-            public_ip , 
+            public_ips , 
             sessions , 
             ports , 
+            router , 
             responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
         });
 
         let _bg_svc = tokio::spawn(background_service(service.clone() ,  accepter));
+        let _shutdown_svc = tokio::spawn(shutdown_watcher(service.clone()));
 
         let listen = self.config.echo_addr;
         HttpServer::new(move || {
@@ -883,7 +1244,11 @@ impl Service {
                 )
                 .wrap(Cors::permissive()) // XXX for quick development
                 .wrap(middleware::Logger::default())
-                .data(web::JsonConfig::default().limit(4096))
+                .data(
+                    web::JsonConfig::default()
+                        .limit(4096)
+                        .error_handler(|err ,  _| Error::InvalidRequest(err.to_string()).into()) , 
+                )
                 .data(service)
                 .service(web::resource("/echo/option").to(option))
                 // for testing
@@ -902,25 +1267,41 @@ impl Service {
                 .service(web::resource("/echo/4/resume").route(web::put().to(resume_v4)))
                 .service(web::resource("/echo/4/teardown").route(web::put().to(teardown_v4)))
                 .service(web::resource("/echo/4/state").to(state_v4))
+                // v5 identifies the session by a control token instead of the cookie
+                .service(web::resource("/echo/5/openapi.json").route(web::get().to(openapi)))
+                .service(web::resource("/echo/5/publish/{name}").route(web::post().to(publish_v5)))
+                .service(web::resource("/echo/5/pause").route(web::put().to(pause_v5)))
+                .service(web::resource("/echo/5/resume").route(web::put().to(resume_v5)))
+                .service(web::resource("/echo/5/teardown").route(web::put().to(teardown_v5)))
+                .service(web::resource("/echo/5/state").route(web::get().to(state_v5)))
         })
+        // signals are handled by the server to drain the sessions first
+        .disable_signals()
         .bind(listen)?
         .run()
         .await?;
 
-        // XXX graceful shutdown
-
         Ok(())
     }
 }
 
-async fn public_ip() -> Option<IpAddr> {
+async fn public_ips(ipv6: bool) -> Vec<IpAddr> {
     // List of resolvers to try and get an IP address from
     let resolver = vec![
-        BoxToResolver::new(dns::OPENDNS_RESOLVER) , 
+        BoxToResolver::new(dns::OPENDNS_RESOLVER_V4) , 
         BoxToResolver::new(http::HTTP_IPIFY_ORG_RESOLVER) , 
     ]
     .to_resolver();
-    public_ip::resolve_address(resolver).await
+    let mut ips: Vec<_> = public_ip::resolve_address(resolver)
+        .await
+        .into_iter()
+        .collect();
+    if ipv6 {
+        // listening on both
+        let resolver = vec![BoxToResolver::new(dns::OPENDNS_RESOLVER_V6)].to_resolver();
+        ips.extend(public_ip::resolve_address(resolver).await);
+    }
+    ips
 }
 
 #[cfg(test)]
@@ -979,10 +1360,12 @@ mod tests {
             config: config.clone() , 
             session_manager: manager_handle , 
             id_gen , 
-            public_ip: None , 
+            public_ips: vec![] , 
             sessions , 
             ports , 
+            router: None , 
             responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
         });
 
         let mut app =
@@ -1021,12 +1404,105 @@ mod tests {
 
         assert_eq!(
             resp_body , 
-            r###"{"publish":{"name":"echotester" , "control":"http://127.0.0.1:5021/echo/0" , "transports":[{"type":"IPv4" , "address":"127.0.0.1" , "port":30000}] , "media":{"type":"audio" , "protocol":"srt" , "format":"aac"} , "rtmp":{"url":"rtmp://127.0.0.1/echotester" , "name":"key"}}}"###
+            r###"{"publish":{"name":"echotester" , "control":"http://127.0.0.1:5021/echo/0" , "transports":[{"type":"IPv4" , "address":"127.0.0.1" , "port":30000}] , "media":{"type":"audio" , "protocol":"srt" , "format":"aac"} , "rtmp":{"url":"rtmp://127.0.0.1/echotester" , "name":"key"} , "rtmp_transports":[{"url":"rtmp://127.0.0.1/echotester" , "name":"key"}]}}"###
+        );
+
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn test_publish_candidates() -> Result<() ,  Error> {
+        let config = Config {
+            echo_srt_transports: Some("::1 ,  private ,  public ,  ::1".to_string()) , 
+            ..Config::default()
+        };
+
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let ports = Arc::new(RwLock::new(
+            (config.echo_srt_min_port..=config.echo_srt_max_port).collect() , 
+        ));
+        let (responder ,  _accepter) = event_channel();
+
+        let session_manager = SessionManager::new(config.clone());
+        let manager_handle = session_manager.handle();
+        let id_gen = IdGenerator::new();
+
+        let service = Arc::new(ServiceInner {
+            config: config.clone() , 
+            session_manager: manager_handle , 
+            id_gen , 
+            public_ips: vec![IpAddr::from([10 ,  0 ,  0 ,  1])] , 
+            sessions , 
+            ports , 
+            router: None , 
+            responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
+        });
+
+        let mut app =
+            test::init_service(App::new().data(service).service(
+                web::resource("/echo/0/publish/{name}").route(web::post().to(publish_test)) , 
+            ))
+            .await;
+
+        let req = test::TestRequest::post()
+            .uri("/echo/0/publish/echotester")
+            .header(header::CONTENT_TYPE ,  "application/json")
+            .set_payload(r###"{"media":{"type":"audio","protocol":"srt","format":"aac"},"reason":{"code":50000,"message":"unknown"},"props":{}}"###)
+            .to_request();
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status() ,  StatusCode::OK);
+
+        let resp_body = match resp.response().body().as_ref() {
+            Some(actix_web::body::Body::Bytes(bytes)) => bytes , 
+            _ => panic!("Response error") , 
+        };
+        let publish = &serde_json::from_slice::<serde_json::Value>(resp_body).unwrap()["publish"];
+
+        let transports: Vec<_> = publish["transports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|transport| {
+                (
+                    transport["type"].as_str().unwrap() , 
+                    transport["address"].as_str().unwrap() , 
+                )
+            })
+            .collect();
+        // every listener is bound to IPv4 only by default
+        assert_eq!(transports ,  vec![("IPv4" ,  "127.0.0.1") ,  ("IPv4" ,  "10.0.0.1")]);
+        assert_eq!(publish["rtmp"]["url"] ,  "rtmp://127.0.0.1/echotester");
+        let rtmp_urls: Vec<_> = publish["rtmp_transports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|transport| transport["url"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            rtmp_urls , 
+            vec!["rtmp://127.0.0.1/echotester" ,  "rtmp://10.0.0.1/echotester"]
         );
+        assert_eq!(publish["control"] ,  "http://127.0.0.1:5021/echo/0");
 
         Ok(())
     }
 
+    #[test]
+    fn test_reachable_ips() {
+        let v6 = IpAddr::from([0 ,  0 ,  0 ,  0 ,  0 ,  0 ,  0 ,  1u16]);
+        let v4 = IpAddr::from([10 ,  0 ,  0 ,  1]);
+        let ips = [v6 ,  v4];
+
+        let any_v6 = SocketAddr::new(IpAddr::from([0u16; 8]) ,  9000);
+        assert_eq!(reachable_ips(&ips ,  any_v6) ,  vec![v6 ,  v4]);
+        let any_v4 = SocketAddr::new(IpAddr::from([0 ,  0 ,  0 ,  0]) ,  9000);
+        assert_eq!(reachable_ips(&ips ,  any_v4) ,  vec![v4]);
+        assert_eq!(reachable_ips(&ips ,  SocketAddr::new(v6 ,  9000)) ,  vec![v6]);
+        // the first one if none can be reached
+        assert_eq!(reachable_ips(&[v6] ,  any_v4) ,  vec![v6]);
+    }
+
     #[actix_rt::test]
     async fn test_publish_rtmp() -> Result<() ,  Error> {
         let config = Config::default();
@@ -1045,10 +1521,12 @@ mod tests {
             config: config.clone() , 
             session_manager: manager_handle , 
             id_gen , 
-            public_ip: None , 
+            public_ips: vec![] , 
             sessions , 
             ports , 
+            router: None , 
             responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
         });
 
         let mut app =
@@ -1087,7 +1565,7 @@ mod tests {
 
         assert_eq!(
             resp_body , 
-            r###"{"publish":{"name":"echotester" , "control":"http://127.0.0.1:5021/echo/0" , "transports":[] , "media":{"type":"audio" , "protocol":"rtmp" , "format":"flv"} , "rtmp":{"url":"rtmp://127.0.0.1/echotester" , "name":"key"}}}"###
+            r###"{"publish":{"name":"echotester" , "control":"http://127.0.0.1:5021/echo/0" , "transports":[] , "media":{"type":"audio" , "protocol":"rtmp" , "format":"flv"} , "rtmp":{"url":"rtmp://127.0.0.1/echotester" , "name":"key"} , "rtmp_transports":[{"url":"rtmp://127.0.0.1/echotester" , "name":"key"}]}}"###
         );
 
         Ok(())
@@ -1111,10 +1589,12 @@ mod tests {
             config: config.clone() , 
             session_manager: manager_handle , 
             id_gen , 
-            public_ip: None , 
+            public_ips: vec![] , 
             sessions , 
             ports , 
+            router: None , 
             responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
         });
 
         let mut app = test::init_service(
@@ -1170,10 +1650,12 @@ mod tests {
             config: config.clone() , 
             session_manager: manager_handle , 
             id_gen , 
-            public_ip: None , 
+            public_ips: vec![] , 
             sessions , 
             ports , 
+            router: None , 
             responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
         });
 
         let mut app = test::init_service(
@@ -1212,4 +1694,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[actix_rt::test]
+    async fn test_state_v5() -> Result<() ,  Error> {
+        let config = Config::default();
+
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let ports = Arc::new(RwLock::new(
+            (config.echo_srt_min_port..=config.echo_srt_max_port).collect() , 
+        ));
+        let (responder ,  _accepter) = event_channel();
+
+        let session_manager = SessionManager::new(config.clone());
+        let manager_handle = session_manager.handle();
+        let id_gen = IdGenerator::new();
+
+        let service = Arc::new(ServiceInner {
+            config: config.clone() , 
+            session_manager: manager_handle , 
+            id_gen , 
+            public_ips: vec![] , 
+            sessions , 
+            ports , 
+            router: None , 
+            responder , 
+            shutdown: Arc::new(RwLock::new(None)) , 
+        });
+
+        let mut app = test::init_service(
+            App::new()
+                .data(service)
+                .service(web::resource("/echo/5/state").route(web::get().to(state_v5))) , 
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/echo/5/state").to_request();
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status() ,  StatusCode::UNAUTHORIZED);
+
+        let token = ControlToken::new(
+            1 , 
+            "dj".to_string() , 
+            config.echo_srt_priv_ip , 
+            config.ttl_max_duration , 
+        );
+        let req = test::TestRequest::get()
+            .uri("/echo/5/state")
+            .header(CONTROL_TOKEN ,  token.sign("another key"))
+            .to_request();
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status() ,  StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/echo/5/state")
+            .header(CONTROL_TOKEN ,  token.sign(&config.echo_priv_key))
+            .to_request();
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status() ,  StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
 }
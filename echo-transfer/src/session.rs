@@ -1,32 +1,67 @@
 use {
     crate::{
         message::{EventResponder ,  MessageAccepter ,  SessionEvent ,  SessionMessage ,  SessionState} , 
-        receiver::{close_event_channel ,  close_request_channel ,  CloseRequester ,  SrtReceiver} , 
+        receiver::{
+            close_request_channel ,  report_channel ,  CloseRequester ,  ReceiverReport ,  SrtReceiver , 
+        } , 
+        router::RouteReceiver , 
         Error , 
     } , 
     echo_core::session::{
-        AppName ,  InputQuality ,  ManageMessage ,  ManagerHandle ,  MediaMessage ,  SessionHandle , 
-        SessionId ,  StateReason , 
+        ingest_channel ,  AppName ,  IngestHandle ,  IngestMessage ,  IngestWatcher ,  InputQuality , 
+        ManageMessage ,  ManagerHandle ,  MediaMessage ,  SessionHandle ,  SessionId ,  StateReason , 
+    } , 
+    echo_types::{MediaFormat ,  Protocol} , 
+    srt_tokio::{tokio::create_bidrectional_srt ,  Connection ,  PackChan ,  SrtSocketBuilder} , 
+    std::{
+        io , 
+        net::IpAddr , 
+        pin::Pin , 
+        time::{Duration ,  Instant} , 
     } , 
-    echo_types::Protocol , 
-    srt_tokio::{tokio::create_bidrectional_srt ,  SrtSocketBuilder} , 
-    std::time::{Duration ,  Instant} , 
     tokio::{
-        stream::StreamExt , 
+        stream::{Stream ,  StreamExt} , 
         sync::{mpsc ,  oneshot} , 
         time::timeout , 
     } , 
 };
 
+type Binding = Pin<Box<dyn Stream<Item = io::Result<(Connection ,  PackChan)>> + Send>>;
+
+/// The passphrase a publisher has to encrypt with.
+#[derive(Debug ,  Clone)]
+pub(crate) struct SrtCrypto {
+    pub key_length: u8 , 
+    pub passphrase: String , 
+}
+
+/// How a session listens for its SRT connections and reads them.
+#[derive(Debug ,  Clone)]
+pub(crate) struct SessionSettings {
+    pub bind_ip: IpAddr , 
+    pub port: u16 , 
+    pub crypto: Option<SrtCrypto> , 
+    // what the SRT payloads carry
+    pub format: MediaFormat , 
+    pub connection_timeout: Duration , 
+    pub read_timeout: Duration , 
+    pub latency: Duration , 
+    // 0 reports on close only
+    pub stats_interval: Duration , 
+    // keep the previous connections as backups of a new one
+    pub failover: bool , 
+}
+
 pub(crate) struct EchoSession {
     id: SessionId , 
     name: AppName , 
-    port: u16 , 
-    connection_timeout: Duration , 
-    read_timeout: Duration , 
-    latency: Duration , 
+    settings: SessionSettings , 
+    // connections from the single port listener instead of its own
+    route: Option<RouteReceiver> , 
     session_manager: ManagerHandle , 
     session_handle: Option<SessionHandle> , 
+    ingest: IngestHandle , 
+    ingest_watcher: IngestWatcher , 
 
     accepter: MessageAccepter , 
     responder: EventResponder , 
@@ -36,39 +71,38 @@ pub(crate) struct EchoSession {
 }
 
 impl EchoSession {
+    /// The reason of the publish comes as the first message.
     pub fn new(
         id: SessionId , 
         name: &str , 
-        port: u16 , 
-        connection_timeout: Duration , 
-        read_timeout: Duration , 
-        latency: Duration , 
+        settings: SessionSettings , 
+        route: Option<RouteReceiver> , 
         accepter: MessageAccepter , 
         responder: EventResponder , 
         session_manager: ManagerHandle , 
-        reason: StateReason , 
     ) -> Self {
+        let (ingest ,  ingest_watcher) = ingest_channel();
         Self {
             id , 
             name: name.to_string() , 
-            port , 
-            connection_timeout , 
-            read_timeout , 
-            latency , 
+            settings , 
+            route , 
             session_manager , 
             session_handle: None , 
+            ingest , 
+            ingest_watcher , 
 
             accepter , 
             responder , 
 
-            last_message: Some(SessionMessage::Init(reason)) , 
+            last_message: None , 
             state: SessionState::Init , 
         }
     }
 
     pub async fn run(mut self) {
         if let Err(err) = self.run_inner().await {
-            log::error!("{} {} {}" ,  self.name ,  self.port ,  err);
+            log::error!("{} {} {}" ,  self.name ,  self.settings.port ,  err);
         }
 
         self.responder
@@ -76,16 +110,17 @@ impl EchoSession {
                 id: self.id , 
                 name: self.name.to_string() , 
                 state: SessionState::Terminated , 
+                quality: None , 
             })
             .map_err(|_| Error::OtherStr("failed to send session event")) // XXX
             .unwrap();
 
         if let Some(ref mut session) = self.session_handle {
             let name = self.name.to_string();
-            session
-                .send(MediaMessage::EndOfSample)
-                .map_err(|_| Error::SessionSendFailed)
-                .unwrap();
+            // the core session may have been evicted or handed over already
+            if session.send(MediaMessage::EndOfSample).is_err() {
+                log::debug!("{} {} session has already ended" ,  self.name ,  self.id);
+            }
             let msg = self.last_message.take();
             if let Some(SessionMessage::Shutdown(reason)) = msg {
                 self.send_manage_message(ManageMessage::ReleaseSession(name ,  self.id ,  reason));
@@ -112,6 +147,25 @@ impl EchoSession {
                 id: self.id , 
                 name: self.name.to_string() , 
                 state: self.state , 
+                quality: None , 
+            })
+            .map_err(|_| Error::OtherStr("failed to send session event"))
+            .unwrap();
+    }
+
+    // to the session manager and the state of the session
+    fn send_quality_report(&self ,  quality: InputQuality) {
+        self.send_manage_message(ManageMessage::InputQualityReport(
+            self.name.to_string() , 
+            self.id , 
+            quality , 
+        ));
+        self.responder
+            .send(SessionEvent {
+                id: self.id , 
+                name: self.name.to_string() , 
+                state: self.state , 
+                quality: Some(quality) , 
             })
             .map_err(|_| Error::OtherStr("failed to send session event"))
             .unwrap();
@@ -119,20 +173,29 @@ impl EchoSession {
 
     // TODO force shutdown
     async fn run_inner(&mut self) -> Result<() ,  Error> {
-        let binding = SrtSocketBuilder::new_listen()
-            .local_port(self.port)
-            .latency(self.latency)
-            .build_multiplexed()
-            .await?;
-
-        tokio::pin!(binding);
-
-        log::info!("{} {} srt multiplex listen ..." ,  self.name ,  self.port);
+        let mut binding: Binding = match self.route.take() {
+            Some(route) => {
+                log::info!("{} {} srt routed listen ..." ,  self.name ,  self.settings.port);
+                Box::pin(route.map(Ok))
+            }
+            None => {
+                let mut builder = SrtSocketBuilder::new_listen()
+                    .local_addr(self.settings.bind_ip)
+                    .local_port(self.settings.port)
+                    .latency(self.settings.latency);
+                if let Some(ref crypto) = self.settings.crypto {
+                    builder = builder.crypto(crypto.key_length ,  crypto.passphrase.as_str());
+                }
+                let binding = builder.build_multiplexed().await?;
+                log::info!("{} {} srt multiplex listen ..." ,  self.name ,  self.settings.port);
+                Box::pin(binding)
+            }
+        };
 
-        let (close_sender ,  mut close_receiver) = close_event_channel();
+        let (report_sender ,  mut report_receiver) = report_channel();
         let mut input_quality = InputQuality::default();
         let mut receiver_count = 0;
-        let mut receiver_handler: Option<CloseRequester> = None;
+        let mut receiver_handlers: Vec<CloseRequester> = Vec::new();
         let mut ready_time = None;
         let mut sid = 0;
 
@@ -152,45 +215,51 @@ impl EchoSession {
                 } , 
             }
 
-            match close_receiver.try_recv() {
-                Ok(close_result) => {
+            if let Ok(IngestMessage::Kick(reason)) = self.ingest_watcher.try_recv() {
+                log::warn!("{} {} srt session kicked: {:?}" ,  self.name ,  self.settings.port ,  reason);
+                self.last_message = Some(SessionMessage::Shutdown(reason));
+                break;
+            }
+
+            match report_receiver.try_recv() {
+                // the closed connections and the live one
+                Ok(ReceiverReport::Live(quality)) => {
+                    self.send_quality_report(input_quality + quality);
+                }
+                Ok(ReceiverReport::Closed(close_result)) => {
                     log::info!(
                         "{} {} receive close signal from receiver. {:?}" , 
                         self.name , 
-                        self.port , 
+                        self.settings.port , 
                         close_result , 
                     );
                     receiver_count -= 1;
                     if let Some(quality) = close_result {
                         input_quality = input_quality + quality;
-                        self.send_manage_message(ManageMessage::InputQualityReport(
-                            self.name.to_string() , 
-                            self.id , 
-                            input_quality , 
-                        ));
+                        self.send_quality_report(input_quality);
                     }
                 }
                 Err(err) => match err {
                     mpsc::error::TryRecvError::Closed => {
-                        return Err(Error::OtherStr("close_receiver closed"));
+                        return Err(Error::OtherStr("report_receiver closed"));
                     }
                     _ => {} // mpsc::error::TryRecvError::Empty
                 } , 
             }
             if receiver_count == 0 && ready_time.is_none() {
-                log::info!("{} {} srt waiting" ,  self.name ,  self.port);
+                log::info!("{} {} srt waiting" ,  self.name ,  self.settings.port);
                 ready_time = Some(Instant::now());
 
                 if self.session_handle.is_none() {
                     if self.state != SessionState::Ready {
-                        log::info!("{} {} srt ready" ,  self.name ,  self.port);
+                        log::info!("{} {} srt ready" ,  self.name ,  self.settings.port);
                         self.state = SessionState::Ready;
                         self.send_session_event();
                     }
                 } else {
                     // core session exists
                     if self.state != SessionState::Paused {
-                        log::info!("{} {} srt paused" ,  self.name ,  self.port);
+                        log::info!("{} {} srt paused" ,  self.name ,  self.settings.port);
                         self.state = SessionState::Paused;
                         self.send_session_event();
                         let msg = self.last_message.take();
@@ -236,35 +305,38 @@ impl EchoSession {
                                 }
                             }
 
-                            if let Some(handler) = receiver_handler.take() {
-                                log::info!(
-                                    "{} {} send close signal to receiver" , 
-                                    self.name , 
-                                    self.port
-                                );
-                                let _ = handler.send(());
+                            if !self.settings.failover {
+                                for handler in receiver_handlers.drain(..) {
+                                    log::info!(
+                                        "{} {} send close signal to receiver" , 
+                                        self.name , 
+                                        self.settings.port
+                                    );
+                                    let _ = handler.send(());
+                                }
                             }
 
                             if self.state != SessionState::Publishing {
-                                log::info!("{} {} srt publishing" ,  self.name ,  self.port);
+                                log::info!("{} {} srt publishing" ,  self.name ,  self.settings.port);
                                 self.state = SessionState::Publishing;
                                 self.send_session_event();
                             }
 
                             let srt_socket = create_bidrectional_srt(pack_chan ,  conn);
                             let (close_requester ,  close_accepter) = close_request_channel();
-                            receiver_handler = Some(close_requester);
+                            // the closed ones have nothing to close
+                            receiver_handlers.retain(|handler| !handler.is_closed());
+                            receiver_handlers.push(close_requester);
 
                             if let Some(ref session) = self.session_handle {
                                 let receiver = SrtReceiver::new(
                                     sid , 
                                     &self.name , 
-                                    self.port , 
                                     srt_socket , 
-                                    self.read_timeout , 
+                                    &self.settings , 
                                     session.clone() , 
                                     close_accepter , 
-                                    close_sender.clone() , 
+                                    report_sender.clone() , 
                                 );
                                 sid += 1;
                                 //echo receiver signal watting
@@ -276,6 +348,16 @@ impl EchoSession {
                                 }
                             }
                         }
+                        Some(Err(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+                            log::warn!(
+                                "{} {} srt connection rejected: {}" , 
+                                self.name , 
+                                self.settings.port , 
+                                err
+                            );
+                            input_quality.rejected_count += 1;
+                            self.send_quality_report(input_quality);
+                        }
                         Some(Err(err)) => {
                             log::error!("{} multiplex listen error: {:?}" ,  self.name ,  err);
                             return Err(Error::SessionCreationFailed);
@@ -288,13 +370,13 @@ impl EchoSession {
                 }
                 Err(_) => {
                     if let Some(inst) = ready_time {
-                        if Instant::now().duration_since(inst) > self.connection_timeout {
+                        if Instant::now().duration_since(inst) > self.settings.connection_timeout {
                             log::warn!(
                                 "{} {} srt connection timeout {:?} {:?}" , 
                                 self.name , 
-                                self.port , 
+                                self.settings.port , 
                                 Instant::now().duration_since(inst) , 
-                                self.connection_timeout
+                                self.settings.connection_timeout
                             );
                             break;
                         }
@@ -309,23 +391,19 @@ impl EchoSession {
                 break;
             }
 
-            match timeout(Duration::from_millis(500) ,  close_receiver.recv()).await {
+            match timeout(Duration::from_millis(500) ,  report_receiver.recv()).await {
                 Ok(res) => match res {
-                    Some(close_result) => {
+                    Some(ReceiverReport::Closed(close_result)) => {
                         log::info!(
                             "{} {} receive close signal from receiver. {:?}" , 
                             self.name , 
-                            self.port , 
+                            self.settings.port , 
                             close_result , 
                         );
                         receiver_count -= 1;
                         if let Some(quality) = close_result {
                             input_quality = input_quality + quality;
-                            self.send_manage_message(ManageMessage::InputQualityReport(
-                                self.name.to_string() , 
-                                self.id , 
-                                input_quality , 
-                            ));
+                            self.send_quality_report(input_quality);
                         }
                     }
                     _ => {}
@@ -334,8 +412,12 @@ impl EchoSession {
             }
 
             if receiver_count > 0 {
-                if let Some(handler) = receiver_handler.take() {
-                    log::info!("{} {} send close signal to receiver" ,  self.name ,  self.port);
+                for handler in receiver_handlers.drain(..) {
+                    log::info!(
+                        "{} {} send close signal to receiver" , 
+                        self.name , 
+                        self.settings.port
+                    );
                     if let Err(err) = handler.send(()) {
                         return Err(Error::OtherString(format!(
                             "receiver_handler error {:?}" , 
@@ -367,6 +449,7 @@ impl EchoSession {
                 Protocol::SRT , 
                 None , 
                 reason , 
+                self.ingest.clone() , 
                 accepter , 
             ));
         } else {
@@ -376,6 +459,7 @@ impl EchoSession {
                 Protocol::SRT , 
                 None , 
                 StateReason::unknown() , 
+                self.ingest.clone() , 
                 accepter , 
             ));
         }
@@ -0,0 +1,134 @@
+use {
+    echo_core::session::{AppName ,  SessionId} , 
+    hmac::{Hmac ,  Mac ,  NewMac} , 
+    rand::{distributions::Alphanumeric ,  thread_rng ,  Rng} , 
+    serde::{Deserialize ,  Serialize} , 
+    sha2::Sha256 , 
+    std::{
+        net::IpAddr , 
+        time::{Duration ,  SystemTime ,  UNIX_EPOCH} , 
+    } , 
+};
+
+const NONCE_LENGTH: usize = 16;
+
+/// Identifies the session of a v5 control request like the cookie of v3/v4.
+#[derive(Debug ,  Clone ,  PartialEq ,  Serialize ,  Deserialize)]
+pub(crate) struct ControlToken {
+    pub id: SessionId , 
+    pub name: AppName , 
+    // the server the session runs on
+    pub addr: IpAddr , 
+    // seconds since the epoch ,  the token is refused from then on
+    pub exp: u64 , 
+    // tells apart the tokens of the same session
+    pub nonce: String , 
+}
+
+impl ControlToken {
+    /// Expires after `max_age`.
+    pub fn new(id: SessionId ,  name: AppName ,  addr: IpAddr ,  max_age: Duration) -> Self {
+        Self {
+            id , 
+            name , 
+            addr , 
+            exp: now() + max_age.as_secs() , 
+            nonce: thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(NONCE_LENGTH)
+                .collect() , 
+        }
+    }
+
+    /// `<base64 JSON>.<base64 HMAC-SHA256 of it>`
+    pub fn sign(&self ,  key: &str) -> String {
+        let payload =
+            base64::encode_config(serde_json::to_vec(self).unwrap() ,  base64::URL_SAFE_NO_PAD);
+        let signature = base64::encode_config(
+            mac(key ,  &payload).finalize().into_bytes() , 
+            base64::URL_SAFE_NO_PAD , 
+        );
+        format!("{}.{}" ,  payload ,  signature)
+    }
+
+    /// `None` unless the token was signed with the key and has not expired.
+    pub fn verify(token: &str ,  key: &str) -> Option<Self> {
+        let mut parts = token.trim().splitn(2 ,  '.');
+        let payload = parts.next()?;
+        let signature = base64::decode_config(parts.next()? ,  base64::URL_SAFE_NO_PAD).ok()?;
+        mac(key ,  payload).verify(&signature).ok()?;
+        let payload = base64::decode_config(payload ,  base64::URL_SAFE_NO_PAD).ok()?;
+        let token: Self = serde_json::from_slice(&payload).ok()?;
+        if token.exp <= now() {
+            return None;
+        }
+        Some(token)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0 ,  |now| now.as_secs())
+}
+
+fn mac(key: &str ,  payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let token = ControlToken::new(
+            7 , 
+            "dj".to_string() , 
+            IpAddr::from([10 ,  0 ,  0 ,  1]) , 
+            Duration::from_secs(60) , 
+        );
+        let signed = token.sign("0759230f81a40bef363d741f6b2ea274");
+        assert_eq!(
+            ControlToken::verify(&signed ,  "0759230f81a40bef363d741f6b2ea274") , 
+            Some(token)
+        );
+        assert_eq!(ControlToken::verify(&signed ,  "another key") ,  None);
+
+        let tampered = format!("x{}" ,  signed);
+        assert_eq!(
+            ControlToken::verify(&tampered ,  "0759230f81a40bef363d741f6b2ea274") , 
+            None
+        );
+        assert_eq!(
+            ControlToken::verify("" ,  "0759230f81a40bef363d741f6b2ea274") , 
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_expired() {
+        let mut token = ControlToken::new(
+            7 , 
+            "dj".to_string() , 
+            IpAddr::from([10 ,  0 ,  0 ,  1]) , 
+            Duration::from_secs(60) , 
+        );
+        let another = ControlToken::new(
+            7 , 
+            "dj".to_string() , 
+            IpAddr::from([10 ,  0 ,  0 ,  1]) , 
+            Duration::from_secs(60) , 
+        );
+        assert_ne!(token.nonce ,  another.nonce);
+
+        token.exp = now() - 1;
+        let signed = token.sign("0759230f81a40bef363d741f6b2ea274");
+        assert_eq!(
+            ControlToken::verify(&signed ,  "0759230f81a40bef363d741f6b2ea274") , 
+            None
+        );
+    }
+}
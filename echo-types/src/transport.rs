@@ -1,5 +1,5 @@
 use {
-    crate::Timestamp , 
+    crate::{Duration ,  Timestamp} , 
     bytes::Bytes , 
     serde::{Deserialize ,  Serialize} , 
 };
@@ -38,6 +38,10 @@ pub struct MediaSample {
     pub sample_type: SampleType , 
     pub timestamp: Option<Timestamp> , 
     pub data: Bytes , 
+    // silence made up by the ingest for media which did not arrive
+    pub filler: bool , 
+    // the playing time of the frame ,  none if the ingest does not know it
+    pub duration: Option<Duration> , 
 }
 
 impl MediaSample {
@@ -57,6 +61,8 @@ impl MediaSample {
             sample_type , 
             timestamp , 
             data: bytes.into() , 
+            filler: false , 
+            duration: None , 
         }
     }
 
@@ -138,17 +138,36 @@ export ECHO_ADDR="0.0.0.0:5021"
 export ECHO_PRIV_KEY="0759230f81a40bef363d741f6b2ea274"
 export ECHO_SRT_PRIV_IP="127.0.0.1"
 export ECHO_SRT_PUB_IP="127.0.0.1"
+# "::" listens on IPv6 and IPv4 both
+export ECHO_SRT_BIND_IP="0.0.0.0"
+# Transports advertised in order, "public" for ECHO_SRT_PUB_IP or the looked up
+# addresses and "private" for ECHO_SRT_PRIV_IP, only the first of them if unset.
+# RTMP and the control get those of them RTMP_ADDR and ECHO_ADDR listen on
+#export ECHO_SRT_TRANSPORTS="public,private,::1"
+# Look up the public addresses at startup
+export ECHO_PUBLIC_IP_DISCOVERY=0
 export ECHO_SRT_MIN_PORT=30000
 export ECHO_SRT_MAX_PORT=49150
 export ECHO_SRT_CONNECTION_TIMEOUT=10
 export ECHO_SRT_READ_TIMEOUT=8
 # 50 milliscond = ADTS 2 packet
 export ECHO_SRT_LATENCY=0.2
+# Publish every SRT session on this port, the caller picks its session by
+# the stream id "#!::r=<name>,m=publish,s=<key>"
+#export ECHO_SRT_PORT=9000
+# v4 publish issues a passphrase for AES-128, 0 to publish unencrypted
+export ECHO_SRT_KEY_LENGTH=16
+# Seconds between the quality reports of a live SRT connection, 0 to report on close only
+export ECHO_SRT_STATS_INTERVAL=5
 
 export HLS_ENABLED=1
+# Sessions get HLS unless "hls": "false" is in their props, 0 to turn it on by "hls": "true" only
+export HLS_DEFAULT=1
 export HLS_ROOT_DIR=$OUTPUT_DIR
 export HLS_TARGET_DURATION=4
 export HLS_PREROLE_DIR=`(cd "${TOP_DIR}/../prerole"; pwd)`
+# "Be right back" slates played while a session is paused, none if the directory is missing
+export HLS_SLATE_DIR="${TOP_DIR}/../slate"
 
 # TS http downloader process
 export HLS_WEB_ENABLED=1
@@ -157,11 +176,14 @@ export HLS_WEB_PATH="cast"
 
 # RTMP options
 export RTMP_ENABLED=1
+# "[::]:1935" listens on IPv6 and IPv4 both
 export RTMP_ADDR="0.0.0.0:1935"
 export RTMP_CONNECTION_TIMEOUT=10
 
 # MP4 Recording 
 export RECORD_ENABLED=1
+# Same as HLS_DEFAULT with the "record" prop
+export RECORD_DEFAULT=1
 export RECORD_ROOT_DIR=$OUTPUT_DIR
 export RECORD_APPEND=1
 
@@ -174,3 +196,48 @@ export STAT_WEB_ENABLED=1
 export STAT_WEB_ADDR="0.0.0.0:8088"
 
 export TTL_MAX_DURATION=7200
+
+# Publish authorization webhook
+export AUTH_ENABLED=0
+export AUTH_URL="http://127.0.0.1:3000/authorize"
+export AUTH_TIMEOUT=3
+export AUTH_CACHE_TTL=60
+export AUTH_FAIL_OPEN=0
+
+# Session lifecycle webhooks
+export NOTIFY_ENABLED=0
+export NOTIFY_URLS="http://127.0.0.1:3000/events"
+export NOTIFY_SECRET="change-me"
+export NOTIFY_QUEUE_DIR=$OUTPUT_DIR/notify
+
+# Graceful shutdown
+export SHUTDOWN_TIMEOUT=30
+export SHUTDOWN_FLUSH_TIMEOUT=10
+export SHUTDOWN_NOTIFY_CLIENTS=1
+
+# Stream keys and session props, "memory" or "file"
+export SESSION_STORE=file
+export SESSION_STORE_PATH=$OUTPUT_DIR/sessions.log
+
+# Publish to a live stream name, "reject", "evict", "handover" or "backup"
+export DUPLICATE_PUBLISH_POLICY=evict
+
+# Admin API (list and terminate sessions)
+export ADMIN_ENABLED=0
+export ADMIN_ADDR=127.0.0.1:8089
+export ADMIN_TOKEN="change-me"
+
+# Samples queued per session, "drop_oldest" or "disconnect" when it is full
+export SESSION_QUEUE_SIZE=512
+export SESSION_OVERFLOW_POLICY=drop_oldest
+
+# Seconds of recent samples kept per session for outputs joining late, 0 to disable
+export SESSION_BACKLOG=10
+
+# Seconds without media from the live uplink before a backup takes over
+export SESSION_FAILOVER_TIMEOUT=1
+
+# Longest broadcast in seconds, 0 for no limit
+export SESSION_MAX_DURATION=7200
+# Seconds before the end of a broadcast to warn the app, comma separated
+export SESSION_EXPIRY_WARNINGS="600,60,10"
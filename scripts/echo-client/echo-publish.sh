@@ -98,8 +98,10 @@ if [ $USE_RTMP -eq 0 ]; then
     do
         ADDRESS=$(cat $RESP_FILE | jq ".publish.transports[${INDEX}].address" | sed -e 's/^"//' -e 's/"$//')
         PORT=$(cat $RESP_FILE | jq ".publish.transports[${INDEX}].port")
+        PASSPHRASE=$(cat $RESP_FILE | jq -r ".publish.transports[${INDEX}].passphrase // empty")
+        PBKEYLEN=$(cat $RESP_FILE | jq -r ".publish.transports[${INDEX}].pbkeylen // empty")
 
-        ./_build/echo-srt/echo-srt-send "$FILE" "$ADDRESS" $PORT > /dev/null 2> "$ERR_FILE" &
+        ./_build/echo-srt/echo-srt-send "$FILE" "$ADDRESS" $PORT $PASSPHRASE $PBKEYLEN > /dev/null 2> "$ERR_FILE" &
         echo $! > $PID_FILE
         sleep 5
         if is_srt_running; then
@@ -1,5 +1,6 @@
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 #include <unistd.h>
 #include <sys/time.h>
 #include <inttypes.h>
@@ -105,8 +106,8 @@ int main(int argc ,  char** argv)
 
     srt_setloglevel(LOG_DEBUG);
 
-    if (argc != 4) {
-      fprintf(stderr ,  "Usage: %s <input> <host> <port>\n" ,  argv[0]);
+    if (argc != 4 && argc != 6) {
+      fprintf(stderr ,  "Usage: %s <input> <host> <port> [<passphrase> <pbkeylen>]\n" ,  argv[0]);
       exit(EXIT_FAILURE);
     }
 
@@ -140,6 +141,11 @@ int main(int argc ,  char** argv)
     srt_setsockopt(ss ,  0 ,  SRTO_LATENCY ,  &latency ,  sizeof latency);
     int payloadsize = 1456; // SRT MAX SIZE - SRT HEADER SIZE
     srt_setsockopt(ss ,  0 ,  SRTO_PAYLOADSIZE ,  &payloadsize ,  sizeof payloadsize);
+    if (argc == 6) {
+        int pbkeylen = atoi(argv[5]);
+        srt_setsockopt(ss ,  0 ,  SRTO_PBKEYLEN ,  &pbkeylen ,  sizeof pbkeylen);
+        srt_setsockopt(ss ,  0 ,  SRTO_PASSPHRASE ,  argv[4] ,  strlen(argv[4]));
+    }
 
     printf("srt connect\n");
     st = srt_connect(ss ,  (struct sockaddr*)&sa ,  sizeof sa);
@@ -42,6 +42,9 @@ pub struct ConnectionSettings {
 
     // if this stream is encrypted ,  it needs a crypto manager
     pub crypto_manager: Option<CryptoManager> , 
+
+    // the stream id the caller sent in the handshake
+    pub stream_id: Option<String> , 
 }
 
 impl ConnectionSettings {
@@ -220,17 +220,34 @@ pub enum SocketType {
 #[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
 pub enum ShakeType {
     /// First handshake exchange in client-server connection
-    Induction = 1 , 
+    Induction , 
 
     /// A rendezvous connection ,  initial connect request ,  0
-    Waveahand = 0 , 
+    Waveahand , 
 
     /// A rendezvous connection ,  response to initial connect request ,  -1
     /// Also a regular connection client response to the second handshake
-    Conclusion = -1 , 
+    Conclusion , 
 
     /// Final rendezvous check ,  -2
-    Agreement = -2 , 
+    Agreement , 
+
+    /// The listener refuses the caller ,  1000 + the reason
+    Rejection(RejectReason) , 
+}
+
+/// Why a listener refuses a caller ,  the access control codes of the
+/// reference implementation
+#[derive(Debug ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub enum RejectReason {
+    /// SRT_REJX_BAD_REQUEST
+    BadRequest = 1400 , 
+
+    /// SRT_REJX_UNAUTHORIZED
+    Unauthorized = 1401 , 
+
+    /// SRT_REJX_NOTFOUND
+    NotFound = 1404 , 
 }
 
 impl HandshakeVSInfo {
@@ -663,7 +680,7 @@ impl ControlTypes {
                 into.put_u32(c.init_seq_num.as_raw());
                 into.put_u32(c.max_packet_size);
                 into.put_u32(c.max_flow_size);
-                into.put_i32(c.shake_type as i32);
+                into.put_i32(c.shake_type.to_i32());
                 into.put_u32(c.socket_id.0);
                 into.put_i32(c.syn_cookie);
 
@@ -854,14 +871,31 @@ impl Debug for HandshakeVSInfo {
 }
 
 impl ShakeType {
+    const REJECTION_BASE: i32 = 1000;
+
     /// Turns an i32 into a `ConnectionType` ,  returning Err(num) if no valid one was passed.
     pub fn from_i32(num: i32) -> Result<ShakeType ,  i32> {
+        use RejectReason::*;
         match num {
             1 => Ok(ShakeType::Induction) , 
             0 => Ok(ShakeType::Waveahand) , 
             -1 => Ok(ShakeType::Conclusion) , 
             -2 => Ok(ShakeType::Agreement) , 
-            i => Err(i) , 
+            i => [BadRequest ,  Unauthorized ,  NotFound]
+                .iter()
+                .find(|reason| Self::REJECTION_BASE + **reason as i32 == i)
+                .map(|reason| ShakeType::Rejection(*reason))
+                .ok_or(i) , 
+        }
+    }
+
+    pub fn to_i32(self) -> i32 {
+        match self {
+            ShakeType::Induction => 1 , 
+            ShakeType::Waveahand => 0 , 
+            ShakeType::Conclusion => -1 , 
+            ShakeType::Agreement => -2 , 
+            ShakeType::Rejection(reason) => Self::REJECTION_BASE + reason as i32 , 
         }
     }
 }
@@ -909,6 +943,34 @@ mod test {
         assert_eq!(pack ,  des);
     }
 
+    #[test]
+    fn rejection_ser_des_test() {
+        let pack = ControlPacket {
+            timestamp: TimeStamp::from_micros(0) , 
+            dest_sockid: SocketID(1231) , 
+            control_type: ControlTypes::Handshake(HandshakeControlInfo {
+                init_seq_num: SeqNumber::new_truncate(1_827_131) , 
+                max_packet_size: 1500 , 
+                max_flow_size: 25600 , 
+                shake_type: ShakeType::Rejection(RejectReason::Unauthorized) , 
+                socket_id: SocketID(0) , 
+                syn_cookie: 0 , 
+                peer_addr: "127.0.0.1".parse().unwrap() , 
+                info: HandshakeVSInfo::V5(HSV5Info::default()) , 
+            }) , 
+        };
+
+        let mut buf = vec![];
+        pack.serialize(&mut buf);
+        // the handshake type
+        assert_eq!(&buf[36..40] ,  &2401i32.to_be_bytes());
+
+        let des = ControlPacket::parse(&mut Cursor::new(buf)).unwrap();
+
+        assert_eq!(pack ,  des);
+        assert_eq!(ShakeType::from_i32(2402) ,  Err(2402));
+    }
+
     #[test]
     fn ack_ser_des_test() {
         let pack = ControlPacket {
@@ -6,7 +6,7 @@ pub mod rendezvous;
 
 use crate::{
     crypto::CryptoOptions , 
-    packet::{ControlTypes ,  HandshakeControlInfo} , 
+    packet::{ControlTypes ,  HandshakeControlInfo ,  RejectReason} , 
     DataPacket ,  SeqNumber ,  SocketID , 
 };
 use rand::random;
@@ -29,6 +29,8 @@ pub enum ConnectError {
     ExpectedExtFlags , 
     ExpectedNoExtFlags , 
     BadSecret , 
+    CryptoMismatch , 
+    Rejected(RejectReason) , 
 }
 
 #[derive(Debug ,  Clone)]
@@ -83,6 +85,8 @@ impl fmt::Display for ConnectError {
                 write!(f ,  "Initiator did not expect handshake flags ,  but got some")
             }
             BadSecret => write!(f ,  "Wrong password") , 
+            CryptoMismatch => write!(f ,  "Encryption settings do not match") , 
+            Rejected(reason) => write!(f ,  "Listener rejected the connection: {:?}" ,  reason) , 
         }
     }
 }
@@ -134,6 +134,9 @@ impl Connect {
             (ShakeType::Conclusion ,  5 ,  from) => Err(UnexpectedHost(self.remote ,  from)) , 
             (ShakeType::Conclusion ,  version ,  _) => Err(UnsupportedProtocolVersion(version)) , 
             (ShakeType::Induction ,  _ ,  _) => Ok(None) , 
+            (ShakeType::Rejection(reason) ,  _ ,  from) if from == self.remote => {
+                Err(Rejected(reason))
+            }
             (_ ,  _ ,  _) => Err(ConclusionExpected(info)) , 
         }
     }
@@ -36,7 +36,7 @@ pub fn gen_hsv5_response(
         // ok ,  both sizes have crypto
         (Some(co) ,  Some(SrtControlPacket::KeyManagerRequest(km))) => {
             if co.size != incoming.crypto_size {
-                unimplemented!("Key size mismatch");
+                return Err(ConnectError::CryptoMismatch);
             }
 
             Some(CryptoManager::new_from_kmreq(co.clone() ,  km)?)
@@ -44,8 +44,7 @@ pub fn gen_hsv5_response(
         // ok ,  neither have crypto
         (None ,  None) => None , 
         // bad cases
-        (Some(_) ,  Some(_)) => unimplemented!("Expected kmreq") , 
-        (Some(_) ,  None) | (None ,  Some(_)) => unimplemented!("Crypto mismatch") , 
+        _ => return Err(ConnectError::CryptoMismatch) , 
     };
     let outgoing_ext_km = if let Some(cm) = &cm {
         Some(cm.generate_km())
@@ -77,6 +76,7 @@ pub fn gen_hsv5_response(
             send_tsbpd_latency: Duration::max(settings.send_latency ,  hs.recv_latency) , 
             recv_tsbpd_latency: Duration::max(settings.recv_latency ,  hs.send_latency) , 
             crypto_manager: cm , 
+            stream_id: incoming.sid.clone() , 
         } , 
     ))
 }
@@ -154,6 +154,7 @@ impl StartedInitiator {
             send_tsbpd_latency: Duration::max(self.settings.send_latency ,  hs.recv_latency) , 
             recv_tsbpd_latency: Duration::max(self.settings.recv_latency ,  hs.send_latency) , 
             crypto_manager: self.cm , 
+            stream_id: None , 
         })
     }
 }
@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 
+use crate::crypto::CryptoOptions;
 use crate::packet::*;
 use crate::protocol::TimeStamp;
 use crate::ConnectionSettings;
@@ -174,6 +175,12 @@ impl Listen {
     pub fn state(&self) -> &ListenState {
         &self.state
     }
+
+    /// Replaces the crypto settings the conclusion is answered with ,  for a
+    /// listener that only learns them from the stream id of the caller.
+    pub fn set_crypto(&mut self ,  crypto: Option<CryptoOptions>) {
+        self.init_settings.crypto = crypto;
+    }
 }
 
 #[cfg(test)]
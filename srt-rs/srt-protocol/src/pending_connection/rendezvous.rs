@@ -223,7 +223,9 @@ impl Rendezvous {
                 self.send_conclusion(info.socket_id ,  hsv5_shake)
             }
             (ShakeType::Agreement ,  _) => Ok(None) , 
-            (ShakeType::Induction ,  _) => Err(RendezvousExpected(info.clone())) , 
+            (ShakeType::Induction ,  _) | (ShakeType::Rejection(_) ,  _) => {
+                Err(RendezvousExpected(info.clone()))
+            }
         }
     }
 
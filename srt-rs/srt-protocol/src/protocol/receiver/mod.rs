@@ -15,6 +15,7 @@ use crate::packet::{
     Packet ,  SrtControlPacket , 
 };
 use crate::protocol::handshake::Handshake;
+use crate::protocol::stats::SocketStatistics;
 use crate::protocol::TimeStamp;
 use crate::{seq_number::seq_num_range ,  ConnectionSettings ,  SeqNumber};
 
@@ -115,6 +116,9 @@ pub struct Receiver {
 
     /// Shutdown flag. This is set so when the buffer is flushed ,  it returns Async::Ready(None)
     shutdown_flag: bool , 
+
+    /// Counters of the received data ,  the RTT is filled in by `statistics`
+    stats: SocketStatistics , 
 }
 
 impl Receiver {
@@ -143,6 +147,14 @@ impl Receiver {
             lr_ack_acked: (0 ,  init_seq_num) , 
             receive_buffer: RecvBuffer::with(&settings) , 
             shutdown_flag: false , 
+            stats: SocketStatistics::default() , 
+        }
+    }
+
+    pub fn statistics(&self) -> SocketStatistics {
+        SocketStatistics {
+            rtt: self.rtt.mean_as_duration() , 
+            ..self.stats
         }
     }
 
@@ -490,6 +502,12 @@ impl Receiver {
         // 5) Record the packet arrival time in PKT History Window.
         self.packet_history_window.push((data.seq_number ,  ts_now));
 
+        self.stats.rx_packets += 1;
+        self.stats.rx_bytes += data.payload.len() as u64;
+        if data.retransmitted {
+            self.stats.rx_retransmitted += 1;
+        }
+
         // 6)
         // a. If the sequence number of the current data packet is greater
         //    than LRSN ,  put all the sequence numbers between (but
@@ -498,6 +516,7 @@ impl Receiver {
         match data.seq_number.cmp(&self.lrsn) {
             Ordering::Greater => {
                 // lrsn is the latest packet received ,  so nak the one after that
+                self.stats.rx_lost += (data.seq_number - self.lrsn) as u64;
                 for i in seq_num_range(self.lrsn ,  data.seq_number) {
                     self.loss_list.push(LossListEntry {
                         seq_num: i , 
@@ -583,8 +602,8 @@ impl Receiver {
         }
 
         // drop packets
-        // TODO: do something with this
-        let _dropped = self.receive_buffer.drop_too_late_packets(now);
+        let dropped = self.receive_buffer.drop_too_late_packets(now);
+        self.stats.rx_dropped += dropped as u64;
 
         self.data_release.pop_front()
     }
@@ -1,5 +1,22 @@
 use std::time::{Duration ,  Instant};
 
+/// Counters of a connection since it was established.
+#[derive(Debug ,  Default ,  Clone ,  Copy ,  PartialEq ,  Eq)]
+pub struct SocketStatistics {
+    /// Smoothed round trip time ,  updated with each ACK2
+    pub rtt: Duration , 
+    /// Data packets received ,  retransmissions included
+    pub rx_packets: u64 , 
+    /// Payload bytes received
+    pub rx_bytes: u64 , 
+    /// Packets found missing in the sequence and reported in a NAK
+    pub rx_lost: u64 , 
+    /// Retransmitted packets received
+    pub rx_retransmitted: u64 , 
+    /// Packets dropped because they arrived too late to be released
+    pub rx_dropped: u64 , 
+}
+
 pub(crate) trait Stats: Default {
     type Measure;
 
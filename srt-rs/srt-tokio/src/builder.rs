@@ -8,8 +8,8 @@ use futures::{future::ready ,  Sink ,  Stream ,  StreamExt};
 
 use crate::tokio::create_bidrectional_srt;
 use crate::{
-    connection::Connection ,  crypto::CryptoOptions ,  multiplex ,  pending_connection ,  PackChan ,  Packet , 
-    PacketCodec ,  PacketParseError ,  SrtSocket , 
+    connection::Connection ,  crypto::CryptoOptions ,  multiplex ,  multiplex_with_acceptor , 
+    pending_connection ,  PackChan ,  Packet ,  PacketCodec ,  PacketParseError ,  SrtSocket ,  StreamAcceptor , 
 };
 use log::warn;
 use srt_protocol::pending_connection::ConnInitSettings;
@@ -158,7 +158,8 @@ impl SrtSocketBuilder {
         self
     }
 
-    /// Se the crypto paramters. However ,  this is currently unimplemented.
+    /// Set the crypto parameters. A multiplexed listener yields a `PermissionDenied`
+    /// error for every caller which does not match them.
     ///
     /// # Panics:
     /// * size is not 16 ,  24 ,  or 32.
@@ -240,4 +241,21 @@ impl SrtSocketBuilder {
             _ => panic!("Cannot bind multiplexed with any connection mode other than listen") , 
         }
     }
+
+    /// Like [`build_multiplexed`](SrtSocketBuilder::build_multiplexed) ,  but each caller is
+    /// refused during the handshake unless the acceptor takes its stream id.
+    ///
+    /// # Panics:
+    /// If this is built with a non-listen builder
+    pub async fn build_multiplexed_with_acceptor(
+        self , 
+        acceptor: StreamAcceptor , 
+    ) -> Result<impl Stream<Item = Result<(Connection ,  PackChan) ,  io::Error>> ,  io::Error> {
+        match self.conn_type {
+            ConnInitMethod::Listen => {
+                multiplex_with_acceptor(self.local_addr ,  self.init_settings ,  Some(acceptor)).await
+            }
+            _ => panic!("Cannot bind multiplexed with any connection mode other than listen") , 
+        }
+    }
 }
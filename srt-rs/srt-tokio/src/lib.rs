@@ -59,10 +59,16 @@ mod util;
 use codec::PacketCodec;
 
 pub use crate::builder::{ConnInitMethod ,  SrtSocketBuilder};
-pub use crate::multiplex::{multiplex ,  PackChan ,  StreamerServer};
+pub use crate::multiplex::{
+    multiplex ,  multiplex_with_acceptor ,  PackChan ,  StreamAcceptor ,  StreamerServer , 
+};
+pub use srt_protocol::crypto::CryptoOptions;
+pub use srt_protocol::packet::RejectReason;
 pub use crate::tokio::SrtSocket;
+pub use srt_protocol::connection::Connection;
+pub use srt_protocol::protocol::stats::SocketStatistics;
 
-use srt_protocol::connection::{self ,  Connection ,  ConnectionSettings};
+use srt_protocol::connection::{self ,  ConnectionSettings};
 use srt_protocol::crypto;
 use srt_protocol::packet::{self ,  ControlPacket ,  Packet ,  PacketParseError};
 use srt_protocol::protocol;
@@ -5,6 +5,7 @@ pub use self::streamer_server::StreamerServer;
 use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use futures::future::{pending ,  select_all};
 use futures::prelude::*;
@@ -16,20 +17,31 @@ use tokio::net::UdpSocket;
 use tokio_util::udp::UdpFramed;
 
 use crate::channel::Channel;
+use crate::crypto::CryptoOptions;
+use crate::packet::{
+    ControlTypes ,  HSV5Info ,  HandshakeControlInfo ,  HandshakeVSInfo ,  RejectReason ,  ShakeType , 
+};
 use crate::protocol::handshake::Handshake;
-use crate::{Connection ,  Packet ,  PacketCodec ,  SocketID};
+use crate::protocol::TimeStamp;
+use crate::{Connection ,  ControlPacket ,  Packet ,  PacketCodec ,  SocketID};
 use srt_protocol::pending_connection::{
     listen::{Listen ,  ListenState} , 
-    ConnInitSettings , 
+    ConnInitSettings ,  ConnectError , 
 };
 
 pub type PackChan = Channel<(Packet ,  SocketAddr)>;
 
+/// Decides on a caller by its stream id ,  before its handshake is answered , 
+/// and gives the crypto settings to answer it with.
+pub type StreamAcceptor =
+    Arc<dyn Fn(Option<&str>) -> Result<Option<CryptoOptions> ,  RejectReason> + Send + Sync>;
+
 struct MultiplexState {
     sock: UdpFramed<PacketCodec> , 
     pending: HashMap<SocketAddr ,  Listen> , 
     conns: HashMap<SocketID ,  PackChan> , 
     init_settings: ConnInitSettings , 
+    acceptor: Option<StreamAcceptor> , 
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -112,9 +124,34 @@ impl MultiplexState {
             .entry(from)
             .or_insert_with(|| Listen::new(this_conn_settings.copy_randomize()));
 
+        // a caller the acceptor refuses is told why instead of timing out
+        let conclusion = match (&self.acceptor ,  conclusion(&pack)) {
+            (Some(acceptor) ,  Some((timestamp ,  shake)))
+                if matches!(listen.state() ,  ListenState::ConclusionWait(_)) =>
+            {
+                Some((acceptor(stream_id(shake)) ,  timestamp ,  shake))
+            }
+            _ => None , 
+        };
+        match conclusion {
+            Some((Ok(crypto) ,  _ ,  _)) => listen.set_crypto(crypto) , 
+            Some((Err(reason) ,  timestamp ,  shake)) => {
+                let rejection = rejection(timestamp ,  shake ,  reason);
+                self.pending.remove(&from);
+                self.sock.send((rejection ,  from)).await?;
+                return Ok(None);
+            }
+            None => {}
+        }
+
         // already started connection?
         match listen.handle_packet((pack ,  from)) {
             Ok(Some(pa)) => self.sock.send(pa).await? , 
+            // a caller with other crypto settings is reported ,  the listener keeps going
+            Err(e @ ConnectError::BadSecret) | Err(e @ ConnectError::CryptoMismatch) => {
+                self.pending.remove(&from);
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied ,  e));
+            }
             Err(e) => warn!("{:?}" ,  e) , 
             _ => {}
         }
@@ -134,9 +171,49 @@ impl MultiplexState {
     }
 }
 
+fn conclusion(pack: &Packet) -> Option<(TimeStamp ,  &HandshakeControlInfo)> {
+    match pack {
+        Packet::Control(ControlPacket {
+            timestamp , 
+            control_type: ControlTypes::Handshake(shake) , 
+            ..
+        }) if shake.shake_type == ShakeType::Conclusion => Some((*timestamp ,  shake)) , 
+        _ => None , 
+    }
+}
+
+fn stream_id(shake: &HandshakeControlInfo) -> Option<&str> {
+    match shake.info {
+        HandshakeVSInfo::V5(ref hsv5) => hsv5.sid.as_deref() , 
+        HandshakeVSInfo::V4(_) => None , 
+    }
+}
+
+fn rejection(timestamp: TimeStamp ,  shake: &HandshakeControlInfo ,  reason: RejectReason) -> Packet {
+    warn!("Rejecting {:?} with {:?}" ,  stream_id(shake) ,  reason);
+    Packet::Control(ControlPacket {
+        timestamp , 
+        dest_sockid: shake.socket_id , 
+        control_type: ControlTypes::Handshake(HandshakeControlInfo {
+            shake_type: ShakeType::Rejection(reason) , 
+            info: HandshakeVSInfo::V5(HSV5Info::default()) , 
+            ..shake.clone()
+        }) , 
+    })
+}
+
 pub async fn multiplex(
     addr: SocketAddr , 
     init_settings: ConnInitSettings , 
+) -> Result<impl Stream<Item = Result<(Connection ,  PackChan) ,  io::Error>> ,  io::Error> {
+    multiplex_with_acceptor(addr ,  init_settings ,  None).await
+}
+
+/// Like [`multiplex`] ,  callers are refused unless the acceptor takes them.
+pub async fn multiplex_with_acceptor(
+    addr: SocketAddr , 
+    init_settings: ConnInitSettings , 
+    acceptor: Option<StreamAcceptor> , 
 ) -> Result<impl Stream<Item = Result<(Connection ,  PackChan) ,  io::Error>> ,  io::Error> {
     Ok(unfold(
         MultiplexState {
@@ -144,6 +221,7 @@ pub async fn multiplex(
             pending: HashMap::new() , 
             conns: HashMap::new() , 
             init_settings , 
+            acceptor , 
         } , 
         |mut state| async move {
             match state.next_conn().await {
@@ -12,7 +12,7 @@ use srt_protocol::{
         connect::{Connect ,  ConnectState} , 
         listen::{Listen ,  ListenState} , 
         rendezvous::Rendezvous , 
-        ConnInitSettings , 
+        ConnInitSettings ,  ConnectError , 
     } , 
     protocol::handshake::Handshake , 
     Connection ,  Packet ,  PacketParseError ,  SeqNumber , 
@@ -48,6 +48,10 @@ where
             Ok(Some(packet)) => {
                 sock.send(packet).await?;
             }
+            // retrying would be rejected again
+            Err(e @ ConnectError::Rejected(_)) => {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused ,  e));
+            }
             Err(e) => {
                 warn!("{:?}" ,  e);
             }
@@ -4,6 +4,7 @@ use crate::protocol::connection::{Connection ,  ConnectionAction};
 use crate::protocol::handshake::Handshake;
 use crate::protocol::receiver::{Receiver ,  ReceiverAlgorithmAction};
 use crate::protocol::sender::{Sender ,  SenderAlgorithmAction};
+use crate::protocol::stats::SocketStatistics;
 use crate::protocol::TimeBase;
 use crate::Packet::*;
 use crate::{ConnectionSettings ,  ControlPacket ,  Packet};
@@ -46,6 +47,9 @@ pub struct SrtSocket {
     // shared state to wake up the
     flush_wakeup: Arc<Mutex<(Option<Waker> ,  bool)>> , 
 
+    // updated by the connection task
+    statistics: Arc<Mutex<SocketStatistics>> , 
+
     _drop_oneshot: oneshot::Sender<()> , 
 }
 
@@ -78,6 +82,9 @@ where
     let fw = Arc::new(Mutex::new((None as Option<Waker> ,  true)));
     let flush_wakeup = fw.clone();
 
+    let stats = Arc::new(Mutex::new(SocketStatistics::default()));
+    let statistics = stats.clone();
+
     tokio::spawn(async move {
         let mut close_receiver = close_oneshot.fuse();
         let _close_sender = close_send; // exists for drop
@@ -149,6 +156,8 @@ where
                     }
                 };
             };
+            *stats.lock().unwrap() = receiver.statistics();
+
             let connection_timeout = loop {
                 match connection.next_action(Instant::now()) {
                     ConnectionAction::ContinueUntil(timeout) => break Some(timeout) , 
@@ -305,6 +314,7 @@ where
         close: close_recv , 
         settings: conn.settings , 
         flush_wakeup , 
+        statistics , 
         _drop_oneshot , 
     }
 }
@@ -313,6 +323,12 @@ impl SrtSocket {
     pub fn settings(&self) -> &ConnectionSettings {
         &self.settings
     }
+
+    /// A snapshot of the counters of the connection ,  they keep the last values
+    /// after it closed.
+    pub fn statistics(&self) -> SocketStatistics {
+        *self.statistics.lock().unwrap()
+    }
 }
 
 impl Stream for SrtSocket {
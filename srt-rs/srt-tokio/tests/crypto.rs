@@ -3,10 +3,13 @@ use std::time::{Duration ,  Instant};
 use srt_tokio::SrtSocketBuilder;
 
 use bytes::Bytes;
-use futures::{SinkExt ,  TryStreamExt};
+use futures::{SinkExt ,  StreamExt ,  TryStreamExt};
 use log::info;
 
-use tokio::{spawn ,  time::delay_for};
+use tokio::{
+    spawn , 
+    time::{delay_for ,  timeout} , 
+};
 
 async fn test_crypto(size: u8) {
     let _ = env_logger::try_init();
@@ -49,5 +52,39 @@ async fn crypto_exchange() {
     test_crypto(32).await;
 }
 
-// TODO: bad password
-// TODO: mismatch
+async fn test_rejected(port: u16 ,  caller: SrtSocketBuilder) {
+    let binding = SrtSocketBuilder::new_listen()
+        .crypto(16 ,  "password123")
+        .local_port(port)
+        .build_multiplexed()
+        .await
+        .unwrap();
+    futures::pin_mut!(binding);
+
+    let _caller = spawn(caller.connect());
+
+    let err = timeout(Duration::from_secs(5) ,  binding.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind() ,  std::io::ErrorKind::PermissionDenied);
+}
+
+#[tokio::test]
+async fn crypto_rejected() {
+    // bad password
+    test_rejected(
+        2001 , 
+        SrtSocketBuilder::new_connect("127.0.0.1:2001").crypto(16 ,  "password456") , 
+    )
+    .await;
+    // mismatch
+    test_rejected(2002 ,  SrtSocketBuilder::new_connect("127.0.0.1:2002")).await;
+    test_rejected(
+        2003 , 
+        SrtSocketBuilder::new_connect("127.0.0.1:2003").crypto(32 ,  "password123") , 
+    )
+    .await;
+}
@@ -1,7 +1,9 @@
-use std::time::Instant;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::{Duration ,  Instant};
 
 use srt_tokio::tokio::create_bidrectional_srt;
-use srt_tokio::SrtSocketBuilder;
+use srt_tokio::{CryptoOptions ,  RejectReason ,  SrtSocketBuilder};
 
 use anyhow::Result;
 use bytes::Bytes;
@@ -10,6 +12,7 @@ use futures::future::join_all;
 use futures::stream;
 use futures::{FutureExt ,  SinkExt ,  StreamExt};
 use log::info;
+use tokio::time::timeout;
 
 #[tokio::test]
 async fn multiplexer() -> Result<()> {
@@ -68,4 +71,65 @@ async fn multiplexer() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn multiplexer_rejection() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut server = SrtSocketBuilder::new_listen()
+        .local_port(2001)
+        .build_multiplexed_with_acceptor(Arc::new(|stream_id| match stream_id {
+            Some(_) => Ok(None) , 
+            None => Err(RejectReason::BadRequest) , 
+        }))
+        .await?
+        .boxed();
+    tokio::spawn(async move { while server.next().await.is_some() {} });
+
+    // refused well before the connect timeout
+    let connect = SrtSocketBuilder::new_connect("127.0.0.1:2001").connect();
+    let err = timeout(Duration::from_secs(1) ,  connect)
+        .await?
+        .err()
+        .unwrap();
+    assert_eq!(err.kind() ,  ErrorKind::ConnectionRefused);
+    Ok(())
+}
+
+#[tokio::test]
+async fn multiplexer_acceptor_crypto() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut server = SrtSocketBuilder::new_listen()
+        .local_port(2002)
+        .build_multiplexed_with_acceptor(Arc::new(|_| {
+            Ok(Some(CryptoOptions {
+                size: 16 , 
+                passphrase: "password123".to_string() , 
+            }))
+        }))
+        .await?
+        .boxed();
+    tokio::spawn(async move {
+        while let Some(Ok((settings ,  channel))) = server.next().await {
+            let mut sender = create_bidrectional_srt(channel ,  settings);
+            tokio::spawn(async move {
+                sender
+                    .send((Instant::now() ,  Bytes::from("asdf")))
+                    .await
+                    .unwrap();
+                sender.close().await.unwrap();
+            });
+        }
+    });
+
+    // the caller encrypts with the passphrase the acceptor picked
+    let mut recvr = SrtSocketBuilder::new_connect("127.0.0.1:2002")
+        .crypto(16 ,  "password123")
+        .connect()
+        .await?;
+    let first = recvr.next().await;
+    assert_eq!(first.unwrap().unwrap().1 ,  "asdf");
+    Ok(())
+}
+
 // crypto!!
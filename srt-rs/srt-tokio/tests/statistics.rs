@@ -0,0 +1,42 @@
+use std::time::{Duration ,  Instant};
+
+use srt_tokio::SrtSocketBuilder;
+
+use bytes::Bytes;
+use futures::{SinkExt ,  TryStreamExt};
+
+use tokio::{spawn ,  time::delay_for};
+
+#[tokio::test]
+async fn receiver_statistics() {
+    let _ = env_logger::try_init();
+
+    let sender = SrtSocketBuilder::new_listen().local_port(2010).connect();
+    let recvr = SrtSocketBuilder::new_connect("127.0.0.1:2010").connect();
+
+    let t = spawn(async move {
+        let mut sender = sender.await.unwrap();
+        for _ in 0..10 {
+            sender
+                .send((Instant::now() ,  Bytes::from(vec![0; 100])))
+                .await
+                .unwrap();
+            delay_for(Duration::from_millis(10)).await;
+        }
+        sender.close().await.unwrap();
+    });
+
+    let mut recvr = recvr.await.unwrap();
+    let mut count = 0;
+    while recvr.try_next().await.unwrap().is_some() {
+        count += 1;
+    }
+    t.await.unwrap();
+
+    let stats = recvr.statistics();
+    assert_eq!(count ,  10);
+    assert_eq!(stats.rx_packets ,  10);
+    assert_eq!(stats.rx_bytes ,  1000);
+    assert_eq!(stats.rx_lost ,  0);
+    assert_eq!(stats.rx_dropped ,  0);
+}